@@ -0,0 +1,184 @@
+//! `--allow-file`: the listeners a host is supposed to have.
+//!
+//! The file holds one entry per line: a port (`22`), a port range
+//! (`8000-8099`), either narrowed to one protocol (`53/udp`), or a process
+//! name (`sshd`, matched case-insensitively against the whole name, or
+//! against the interpreter of a `python:manage.py` name).
+//! Blank lines and anything after `#` are ignored.
+
+use std::fs;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+
+use crate::types::{names_interpreter_of, parse_port_range, split_protocol, PortInfo, Protocol};
+
+#[derive(Debug, PartialEq)]
+enum Entry {
+    Ports {
+        range: RangeInclusive<u16>,
+        protocol: Option<Protocol>,
+    },
+    /// Lowercased.
+    Process(String),
+}
+
+impl Entry {
+    fn allows(&self, port: &PortInfo) -> bool {
+        match self {
+            Entry::Ports { range, protocol } => {
+                range.contains(&port.port) && protocol.is_none_or(|p| p == port.protocol)
+            }
+            Entry::Process(name) => {
+                let actual = port.process_name.to_lowercase();
+                actual == *name || names_interpreter_of(name, &actual)
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Allowlist {
+    path: PathBuf,
+    entries: Vec<Entry>,
+}
+
+impl Allowlist {
+    pub fn load(path: &Path) -> Result<Self> {
+        let text =
+            fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+        Self::parse(&text, path)
+    }
+
+    /// Parse allowlist text. `path` is only used in messages.
+    fn parse(text: &str, path: &Path) -> Result<Self> {
+        let mut entries = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let entry = parse_entry(line)
+                .with_context(|| format!("{}:{}: invalid entry `{line}`", path.display(), i + 1))?;
+            entries.push(entry);
+        }
+        Ok(Self {
+            path: path.to_path_buf(),
+            entries,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn allows(&self, port: &PortInfo) -> bool {
+        self.entries.iter().any(|e| e.allows(port))
+    }
+
+    /// Fill in `allowed` on every row.
+    pub fn mark(&self, ports: &mut [PortInfo]) {
+        for port in ports.iter_mut() {
+            port.allowed = Some(self.allows(port));
+        }
+    }
+}
+
+fn parse_entry(line: &str) -> Result<Entry> {
+    let (base, protocol) = split_protocol(line);
+    if let Some(range) = parse_port_range(base)? {
+        return Ok(Entry::Ports { range, protocol });
+    }
+    if let Ok(port) = base.parse::<u16>() {
+        return Ok(Entry::Ports {
+            range: port..=port,
+            protocol,
+        });
+    }
+    // Digits that didn't parse are a port typo, not a process name.
+    if base.bytes().all(|b| b.is_ascii_digit() || b == b'-') {
+        bail!("not a port or port range (1-65535)");
+    }
+    Ok(Entry::Process(line.to_lowercase()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Result<Allowlist> {
+        Allowlist::parse(text, Path::new("ports.allow"))
+    }
+
+    fn listener(port: u16, protocol: Protocol, name: &str) -> PortInfo {
+        PortInfo {
+            port,
+            protocol,
+            pid: 1,
+            process_name: name.to_string(),
+            address: format!("0.0.0.0:{port}"),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn parses_ports_ranges_protocols_and_processes() {
+        let allow = parse("# web\n80\n443/tcp  # tls\n\n53/udp\n8000-8099\nSSHD\n").unwrap();
+        assert_eq!(
+            allow.entries,
+            [
+                Entry::Ports {
+                    range: 80..=80,
+                    protocol: None
+                },
+                Entry::Ports {
+                    range: 443..=443,
+                    protocol: Some(Protocol::Tcp)
+                },
+                Entry::Ports {
+                    range: 53..=53,
+                    protocol: Some(Protocol::Udp)
+                },
+                Entry::Ports {
+                    range: 8000..=8099,
+                    protocol: None
+                },
+                Entry::Process("sshd".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn allows_matching_listeners_only() {
+        let allow = parse("53/udp\n8000-8099\nsshd\n").unwrap();
+        assert!(allow.allows(&listener(53, Protocol::Udp, "dnsmasq")));
+        assert!(!allow.allows(&listener(53, Protocol::Tcp, "dnsmasq")));
+        assert!(allow.allows(&listener(8042, Protocol::Tcp, "node")));
+        assert!(allow.allows(&listener(2222, Protocol::Tcp, "sshd")));
+        // Process entries match the whole name, not a substring.
+        assert!(!allow.allows(&listener(22, Protocol::Tcp, "sshd-session")));
+        assert!(!allow.allows(&listener(6379, Protocol::Tcp, "redis-server")));
+    }
+
+    #[test]
+    fn interpreter_entries_cover_script_named_processes() {
+        let allow = parse(
+            "python3
+node:server.js
+",
+        )
+        .unwrap();
+        assert!(allow.allows(&listener(8000, Protocol::Tcp, "python:manage.py")));
+        assert!(allow.allows(&listener(3000, Protocol::Tcp, "node:server.js")));
+        assert!(!allow.allows(&listener(3001, Protocol::Tcp, "node:worker.js")));
+        assert!(!allow.allows(&listener(4000, Protocol::Tcp, "ruby:app.rb")));
+    }
+
+    #[test]
+    fn bad_port_names_the_line() {
+        let err = parse("22\n70000\n").unwrap_err();
+        assert_eq!(err.to_string(), "ports.allow:2: invalid entry `70000`");
+        assert!(parse("9000-8000").is_err());
+    }
+}
@@ -26,6 +26,8 @@ pub fn build_ancestry(pid: u32) -> Option<ProcessAncestry> {
         git_context,
         systemd_unit,
         launchd_label: None,
+        started_at: None,
+        uptime_secs: None,
     })
 }
 
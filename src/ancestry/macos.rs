@@ -46,6 +46,8 @@ pub fn build_ancestry(pid: u32) -> Option<ProcessAncestry> {
         git_context,
         systemd_unit: None,
         launchd_label,
+        started_at: None,
+        uptime_secs: None,
     })
 }
 
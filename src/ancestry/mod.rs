@@ -12,9 +12,9 @@ mod macos;
 mod git;
 mod source;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::{LazyLock, Mutex};
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime};
 
 use serde::Serialize;
 
@@ -36,6 +36,8 @@ pub enum SourceType {
     Systemd,
     Launchd,
     Docker,
+    Snap,
+    Flatpak,
     Cron,
     Shell,
     Pm2,
@@ -55,6 +57,8 @@ impl std::fmt::Display for SourceType {
             SourceType::Systemd => write!(f, "systemd"),
             SourceType::Launchd => write!(f, "launchd"),
             SourceType::Docker => write!(f, "docker"),
+            SourceType::Snap => write!(f, "snap"),
+            SourceType::Flatpak => write!(f, "flatpak"),
             SourceType::Cron => write!(f, "cron"),
             SourceType::Shell => write!(f, "shell"),
             SourceType::Pm2 => write!(f, "pm2"),
@@ -70,12 +74,15 @@ impl std::fmt::Display for SourceType {
     }
 }
 
-/// Health warnings detected for a process.
-#[derive(Debug, Clone, Serialize)]
+/// Health warnings detected for a process. New variants may be added in
+/// minor releases.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[non_exhaustive]
 #[serde(rename_all = "snake_case")]
-// Constructed only by the linux ancestry path (`ancestry::linux`); appears
-// dead on macOS because `ancestry::linux` is `cfg(target_os = "linux")`-gated.
-// Do not delete these variants — they are part of the production Linux flow.
+// `ZombieProcess` is constructed only by the linux ancestry path
+// (`ancestry::linux`); it appears dead on macOS because `ancestry::linux`
+// is `cfg(target_os = "linux")`-gated. Do not delete it — it is part of
+// the production Linux flow.
 #[allow(dead_code)]
 pub enum HealthWarning {
     DeletedBinary,
@@ -111,6 +118,26 @@ pub struct ProcessAncestry {
     pub systemd_unit: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub launchd_label: Option<String>,
+    /// When the process started; RFC 3339 in JSON. Filled in by
+    /// [`ProcessAncestry::set_started`], not by the ancestry walk, which is
+    /// cached.
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "crate::output::json::serialize_time"
+    )]
+    pub started_at: Option<SystemTime>,
+    /// Whole seconds the process had been running when `started_at` was
+    /// filled in.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uptime_secs: Option<u64>,
+}
+
+impl ProcessAncestry {
+    /// Record the process start time, and its uptime as of `now`.
+    pub fn set_started(&mut self, started_at: SystemTime, now: SystemTime) {
+        self.started_at = Some(started_at);
+        self.uptime_secs = Some(now.duration_since(started_at).map_or(0, |d| d.as_secs()));
+    }
 }
 
 // ── Caching ─────────────────────────────────────────────────────────────────
@@ -166,18 +193,55 @@ pub fn get_ancestry(pid: u32, process_name: &str) -> Option<ProcessAncestry> {
 }
 
 /// Build ancestry for a batch of PIDs (more efficient for --why on list).
+///
+/// PIDs are deduplicated (first name wins) and split across scoped worker
+/// threads, since each chain walk is a handful of blocking `/proc` reads.
+/// Workers share `CACHE` through `get_ancestry`, which never holds the lock
+/// across I/O. The result is keyed by PID, so scheduling order can't leak
+/// into the output.
 pub fn get_ancestry_batch(pids_with_names: &[(u32, &str)]) -> HashMap<u32, ProcessAncestry> {
     // On macOS, build the process table once before walking chains.
     #[cfg(target_os = "macos")]
     macos::ensure_process_table();
 
-    let mut result = HashMap::new();
-    for &(pid, name) in pids_with_names {
-        if let Some(a) = get_ancestry(pid, name) {
-            result.insert(pid, a);
-        }
+    let mut seen = HashSet::new();
+    let unique: Vec<(u32, &str)> = pids_with_names
+        .iter()
+        .copied()
+        .filter(|&(pid, _)| seen.insert(pid))
+        .collect();
+
+    let workers = std::thread::available_parallelism()
+        .map_or(1, |n| n.get())
+        .min(unique.len());
+    if workers <= 1 {
+        return unique
+            .into_iter()
+            .filter_map(|(pid, name)| Some((pid, get_ancestry(pid, name)?)))
+            .collect();
     }
-    result
+
+    let chunk_size = unique.len().div_ceil(workers);
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = unique
+            .chunks(chunk_size)
+            .map(|chunk| {
+                scope.spawn(move || {
+                    chunk
+                        .iter()
+                        .filter_map(|&(pid, name)| Some((pid, get_ancestry(pid, name)?)))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|h| {
+                h.join()
+                    .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+            })
+            .collect()
+    })
 }
 
 // ── Platform dispatch ───────────────────────────────────────────────────────
@@ -213,6 +277,8 @@ mod tests {
         assert_eq!(SourceType::Unknown.to_string(), "unknown");
         assert_eq!(SourceType::Tmux.to_string(), "tmux");
         assert_eq!(SourceType::Nohup.to_string(), "nohup");
+        assert_eq!(SourceType::Snap.to_string(), "snap");
+        assert_eq!(SourceType::Flatpak.to_string(), "flatpak");
     }
 
     #[test]
@@ -235,4 +301,16 @@ mod tests {
         let result = get_ancestry(0, "nonexistent");
         assert!(result.is_none());
     }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_batch_dedups_and_matches_sequential() {
+        let me = std::process::id();
+        let name = std::fs::read_to_string(format!("/proc/{me}/comm")).unwrap();
+        let name = name.trim();
+        let batch = get_ancestry_batch(&[(me, name), (0, "nonexistent"), (me, name)]);
+        assert_eq!(batch.len(), 1);
+        let single = get_ancestry(me, name).unwrap();
+        assert_eq!(batch[&me].source, single.source);
+    }
 }
@@ -1,7 +1,7 @@
 //! Tiered source detection algorithm.
 //!
 //! Priority order (highest wins):
-//!   Tier 1: Container (cgroup-based) → Docker
+//!   Tier 1: Container or sandbox (cgroup-based) → Docker, Snap, Flatpak
 //!   Tier 2: Init system (cgroup/metadata) → Systemd, Launchd
 //!   Tier 3: Supervisors (chain name match, top-down) → Pm2, Supervisord, Gunicorn, Runit, S6
 //!   Tier 4: Multiplexers (chain name match) → Tmux, Screen, Nohup
@@ -53,6 +53,20 @@ pub fn detect_source(chain: &[Ancestor], cgroup: Option<&str>) -> SourceType {
         {
             return SourceType::Docker;
         }
+        // Snap scopes and services live under `snap.<name>.<app>` units,
+        // e.g. `.../app.slice/snap.firefox.firefox-1234.scope` or
+        // `/system.slice/snap.lxd.daemon.service` — so this must run
+        // before the `.service` check in tier 2.
+        if cg.contains("/snap.") {
+            return SourceType::Snap;
+        }
+        // `bwrap` alone is also used by non-Flatpak sandboxes; the
+        // `app-flatpak-` scope confirms it.
+        if cg.contains("/app-flatpak-")
+            && chain.iter().any(|a| a.name.eq_ignore_ascii_case("bwrap"))
+        {
+            return SourceType::Flatpak;
+        }
     }
 
     // Tier 2: Init system via cgroup metadata.
@@ -163,6 +177,47 @@ mod tests {
         assert_eq!(detect_source(&chain, Some(cgroup)), SourceType::Docker);
     }
 
+    #[test]
+    fn test_snap_via_cgroup_scope() {
+        let chain = make_chain(&[("firefox", 500), ("systemd", 200), ("systemd", 1)]);
+        let cgroup = "0::/user.slice/user-1000.slice/user@1000.service/app.slice/snap.firefox.firefox-1a2b.scope\n";
+        assert_eq!(detect_source(&chain, Some(cgroup)), SourceType::Snap);
+    }
+
+    #[test]
+    fn test_snap_service_beats_systemd() {
+        let chain = make_chain(&[("lxd", 500), ("systemd", 1)]);
+        let cgroup = "0::/system.slice/snap.lxd.daemon.service\n";
+        assert_eq!(detect_source(&chain, Some(cgroup)), SourceType::Snap);
+    }
+
+    #[test]
+    fn test_flatpak_via_bwrap_and_cgroup() {
+        let chain = make_chain(&[
+            ("app", 502),
+            ("bwrap", 501),
+            ("bwrap", 500),
+            ("systemd", 200),
+            ("systemd", 1),
+        ]);
+        let cgroup = "0::/user.slice/user-1000.slice/user@1000.service/app.slice/app-flatpak-org.example.App-1234.scope\n";
+        assert_eq!(detect_source(&chain, Some(cgroup)), SourceType::Flatpak);
+    }
+
+    #[test]
+    fn test_bwrap_without_flatpak_scope_is_not_flatpak() {
+        let chain = make_chain(&[("app", 501), ("bwrap", 500), ("zsh", 100), ("init", 1)]);
+        let cgroup = "0::/user.slice/user-1000.slice/session-2.scope\n";
+        assert_ne!(detect_source(&chain, Some(cgroup)), SourceType::Flatpak);
+    }
+
+    #[test]
+    fn test_docker_cgroup_beats_snap() {
+        let chain = make_chain(&[("node", 500), ("containerd-shim", 100), ("systemd", 1)]);
+        let cgroup = "0::/system.slice/snap.docker.dockerd.service/docker/abc123\n";
+        assert_eq!(detect_source(&chain, Some(cgroup)), SourceType::Docker);
+    }
+
     #[test]
     fn test_pm2_supervisor() {
         let chain = make_chain(&[("node", 500), ("PM2", 100), ("systemd", 1)]);
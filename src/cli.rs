@@ -1,37 +1,171 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
 use clap::{Parser, Subcommand, ValueEnum};
 use clap_complete::Shell;
 
+use crate::commands::kill::{parse_signal, Signal};
+use crate::commands::why::parse_window;
+
 #[derive(Parser)]
 #[command(name = "ports")]
-#[command(version, about = "Modern cross-platform port inspector")]
+#[command(
+    version,
+    disable_version_flag = true,
+    about = "Modern cross-platform port inspector"
+)]
 pub struct Cli {
-    /// Port number or process name to query
+    /// Port number or process name to query; separate several with commas
+    /// (e.g. nginx,redis,5432) to match any of them
     pub query: Option<String>,
 
     /// Output as JSON
     #[arg(long, global = true)]
     pub json: bool,
 
+    /// Security review: add a RISK column and list the riskiest listeners
+    /// first (admin services and privileged ports reachable beyond
+    /// loopback)
+    #[arg(long, global = true, conflicts_with = "connections")]
+    pub security: bool,
+
+    /// Warn about listeners on well-known ports held by an unexpected
+    /// process, e.g. port 22 bound by something other than sshd
+    #[arg(
+        long,
+        global = true,
+        conflicts_with_all = ["connections", "by_remote", "summary", "porcelain"]
+    )]
+    pub anomaly: bool,
+
+    /// File of expected listeners, one `port`, `port/proto` or process
+    /// name per line; adds an ALLOWED column and exits nonzero when
+    /// anything else is listening
+    #[arg(
+        long,
+        global = true,
+        value_name = "PATH",
+        conflicts_with_all = ["connections", "by_remote", "watch", "interactive", "baseline"]
+    )]
+    pub allow_file: Option<PathBuf>,
+
+    /// With --allow-file, show only listeners the file doesn't cover
+    #[arg(
+        long,
+        global = true,
+        requires = "allow_file",
+        conflicts_with_all = ["watch", "interactive", "baseline"]
+    )]
+    pub unexpected_only: bool,
+
+    /// With --connections, sample each TCP connection's send and receive
+    /// rate over half a second and add RX/s and TX/s columns (Linux only)
+    #[arg(long, global = true)]
+    pub throughput: bool,
+
+    /// Stable tab-separated output for scripts: PORT PROTO PID PROCESS
+    /// ADDRESS REMOTE CONTAINER, `-` for empty fields, no header
+    #[arg(
+        long,
+        global = true,
+        conflicts_with_all = ["json", "summary", "why", "group_by", "security", "anomaly", "with_conn_count", "watch", "interactive"]
+    )]
+    pub porcelain: bool,
+
+    /// Print JSON on a single line instead of pretty-printed
+    #[arg(long, global = true)]
+    pub compact: bool,
+
+    /// Write --json or --porcelain output to this file instead of stdout,
+    /// replacing it atomically; with `completions`, where to install
+    #[arg(long, global = true, value_name = "PATH", conflicts_with_all = ["watch", "interactive"])]
+    pub output: Option<PathBuf>,
+
     /// Watch mode: refresh continuously
     #[arg(short, long, global = true)]
     pub watch: bool,
 
-    /// Refresh interval in seconds (default: 1)
+    /// Refresh interval in seconds (default: 1; 0 = same as --manual)
     #[arg(short = 'n', long, default_value = "1", global = true)]
     pub interval: f64,
 
+    /// Watch mode: refresh when space or r is pressed (q to quit) instead
+    /// of every --interval seconds
+    #[arg(long, global = true)]
+    pub manual: bool,
+
+    /// Watch mode: exit after this many refreshes (0 = run until Ctrl+C)
+    #[arg(long, value_name = "N", global = true)]
+    pub iterations: Option<u64>,
+
+    /// Watch mode: only redraw when a port appears or disappears
+    #[arg(long, global = true)]
+    pub diff_only: bool,
+
+    /// Watch mode: ring the terminal bell when a new port appears
+    #[arg(long, global = true)]
+    pub bell: bool,
+
+    /// Watch mode: send a desktop notification when a new port appears
+    #[arg(long, global = true)]
+    pub notify: bool,
+
+    /// Watch mode output format (default: table, or json with --json).
+    /// Not global, since `history show` has its own `--format`
+    #[arg(long, value_enum, requires = "watch")]
+    pub format: Option<WatchFormat>,
+
     /// Show established connections instead of listening ports
     #[arg(short, long, global = true)]
     pub connections: bool,
 
-    /// Sort results by field
+    /// Sort results by field (default: port, then protocol, then PID)
     #[arg(short, long, value_enum, global = true)]
     pub sort: Option<SortField>,
 
-    /// Filter by protocol
+    /// Filter by protocol [default: both]
     #[arg(short, long, value_enum, global = true)]
     pub protocol: Option<ProtocolFilter>,
 
+    /// Only TCP sockets, same as --protocol tcp
+    #[arg(long, global = true, conflicts_with_all = ["protocol", "udp"])]
+    pub tcp: bool,
+
+    /// Only UDP sockets, same as --protocol udp
+    #[arg(long, global = true, conflicts_with = "protocol")]
+    pub udp: bool,
+
+    /// Filter by TCP socket state (excludes UDP)
+    #[arg(long, value_enum, global = true)]
+    pub state: Option<StateFilter>,
+
+    /// Show only ports bound to an address on this network interface
+    /// (wildcard binds are included)
+    #[arg(long, global = true, value_name = "NAME")]
+    pub interface: Option<String>,
+
+    /// Also list sockets in other network namespaces (containers,
+    /// `ip netns`), labelled with the namespace; Linux only, needs root
+    /// for other users' namespaces
+    #[arg(long, global = true)]
+    pub all_netns: bool,
+
+    /// Label sockets held by no process as `[kernel]` instead of `?`
+    /// (PID 0); Linux only, and only when every process could be
+    /// inspected, so usually under sudo
+    #[arg(long, global = true)]
+    pub kernel: bool,
+
+    /// Re-run under sudo so sockets of other users' processes can be
+    /// attributed (no-op when already root)
+    #[arg(long, global = true)]
+    pub sudo: bool,
+
+    /// Print parser diagnostics to stderr, such as /proc/net lines that
+    /// could not be parsed (ignored by `top` and --interactive)
+    #[arg(long, global = true)]
+    pub verbose: bool,
+
     /// Interactive mode: select a port to kill
     #[arg(short, long, global = true)]
     pub interactive: bool,
@@ -48,6 +182,102 @@ pub struct Cli {
     #[arg(long, global = true)]
     pub dev: bool,
 
+    /// Show only ports published by a Docker container
+    #[arg(long, global = true, conflicts_with = "no_docker")]
+    pub docker_only: bool,
+
+    /// Don't ask the Docker daemon for container names (faster when you
+    /// don't care about containers)
+    #[arg(long, global = true)]
+    pub no_docker: bool,
+
+    /// Show host names instead of addresses for the remote end of
+    /// connections (reverse DNS, cached; never waits more than 250ms)
+    #[arg(long, global = true)]
+    pub resolve_names: bool,
+
+    /// Show extra table columns (EXE path; RETR retransmits with --connections)
+    #[arg(long, global = true)]
+    pub wide: bool,
+
+    /// Collapse IPv4/IPv6 twins of the same listener (e.g. 0.0.0.0:80 and
+    /// [::]:80) into one row marked dual-stack; JSON stays exhaustive
+    #[arg(long, global = true)]
+    pub dedup: bool,
+
+    /// Add a CONNS column counting established connections on each
+    /// listening port (same port and process)
+    #[arg(long, global = true, conflicts_with = "connections")]
+    pub with_conn_count: bool,
+
+    /// Print a count of ports per service (http: 2, ssh: 1, ...) instead
+    /// of the table
+    #[arg(long, global = true)]
+    pub summary: bool,
+
+    /// Group connections by remote host: one row per remote IP with its
+    /// connection count and local ports (implies --connections)
+    #[arg(
+        long,
+        global = true,
+        conflicts_with_all = ["summary", "why", "group_by", "porcelain", "with_conn_count", "security", "watch", "interactive"]
+    )]
+    pub by_remote: bool,
+
+    /// Show only the first N rows after filtering and sorting. Not global,
+    /// since `history show` has its own `--limit`: put it before any
+    /// subcommand (`ports --limit 5 list`)
+    #[arg(long, value_name = "N")]
+    pub limit: Option<usize>,
+
+    /// Add an AGE column showing how long each listener has been up with
+    /// the same process, tracked across runs (requires `history`). With
+    /// --connections, an upper bound: how long the owning process has run
+    #[arg(long, global = true)]
+    pub age: bool,
+
+    /// Save the listening ports to the baseline file, or check the live
+    /// set against it and exit non-zero on any difference
+    #[arg(
+        long,
+        value_enum,
+        global = true,
+        value_name = "ACTION",
+        conflicts_with_all = ["connections", "watch", "interactive"]
+    )]
+    pub baseline: Option<BaselineAction>,
+
+    /// File used by --baseline
+    #[arg(
+        long,
+        global = true,
+        value_name = "PATH",
+        default_value = "ports-baseline.json"
+    )]
+    pub baseline_file: PathBuf,
+
+    /// Clip process, container, and address cells in tables to N
+    /// characters (0 = never clip; default: fit the terminal)
+    #[arg(long, global = true, value_name = "N")]
+    pub max_width: Option<usize>,
+
+    /// Collapse table rows that share a process into a single line
+    #[arg(long, value_enum, global = true)]
+    pub group_by: Option<GroupBy>,
+
+    /// When to use colored output (default: auto)
+    #[arg(long, value_enum, global = true)]
+    pub color: Option<ColorChoice>,
+
+    /// History database path (default: <data dir>/ports/ports_history.db)
+    #[arg(long, value_name = "PATH", global = true)]
+    pub db_path: Option<PathBuf>,
+
+    /// Print version (with --json: version, compiled-in features, and
+    /// platform backend)
+    #[arg(short = 'V', long)]
+    pub version: bool,
+
     #[command(subcommand)]
     pub command: Option<Commands>,
 }
@@ -57,12 +287,77 @@ pub enum SortField {
     Port,
     Pid,
     Name,
+    Protocol,
+    /// Bind address, numerically (IPv4 before IPv6)
+    Address,
+    /// Container name; ports without a container sort last
+    Container,
+    /// Keep the raw enumeration order
+    #[value(name = "none")]
+    Unsorted,
+}
+
+#[derive(Clone, Copy, ValueEnum, PartialEq, Eq)]
+pub enum BaselineAction {
+    /// Write the current listening ports to the baseline file
+    Save,
+    /// Compare the current listening ports against the baseline file
+    Check,
+}
+
+#[derive(Clone, Copy, ValueEnum, PartialEq, Eq)]
+pub enum GroupBy {
+    Process,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum, PartialEq, Eq)]
+pub enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(Clone, Copy, ValueEnum, PartialEq, Eq)]
+pub enum WatchFormat {
+    Table,
+    /// The full port list on every refresh (same as --json)
+    Json,
+    /// One compact line per refresh with only the ports that appeared or
+    /// disappeared
+    JsonDiff,
+}
+
+#[derive(Clone, Copy, ValueEnum, PartialEq, Eq)]
+pub enum HistoryFormat {
+    Table,
+    /// One pretty-printed array (same as --json)
+    Json,
+    /// One compact JSON object per line, streamed as rows are read
+    Ndjson,
 }
 
 #[derive(Clone, Copy, ValueEnum, PartialEq, Eq)]
 pub enum ProtocolFilter {
     Tcp,
     Udp,
+    /// TCP and UDP, same as leaving the filter off; overrides a configured
+    /// default protocol
+    Both,
+}
+
+#[derive(Clone, Copy, ValueEnum, PartialEq, Eq)]
+pub enum StateFilter {
+    Established,
+    SynSent,
+    SynRecv,
+    FinWait1,
+    FinWait2,
+    TimeWait,
+    Close,
+    CloseWait,
+    LastAck,
+    Listen,
+    Closing,
 }
 
 #[derive(Subcommand)]
@@ -71,7 +366,7 @@ pub enum Commands {
     List,
     /// Kill process using a port or by name
     Kill {
-        /// Port number or process name
+        /// Port number, port range (e.g. 3000-3010), or process name
         target: String,
         /// Skip confirmation prompt
         #[arg(short, long)]
@@ -82,12 +377,23 @@ pub enum Commands {
         /// Search established connections in addition to listening ports
         #[arg(long)]
         connections: bool,
+        /// Allow killing this process or one of its ancestors (e.g. your shell)
+        #[arg(long)]
+        include_self: bool,
+        /// Signal to send: TERM, KILL, INT, HUP, QUIT, USR1, USR2, or its
+        /// number (case-insensitive, SIG prefix optional)
+        #[arg(long, default_value = "TERM", value_parser = parse_signal)]
+        signal: Signal,
     },
     /// Interactive real-time view (like htop for ports)
     Top {
         /// Show connections instead of listening ports
         #[arg(short, long)]
         connections: bool,
+        /// Start in the default view and sort instead of the ones the last
+        /// session quit with, and don't remember this session's
+        #[arg(long)]
+        no_restore: bool,
     },
     /// Generate shell completions
     Completions {
@@ -95,14 +401,36 @@ pub enum Commands {
         #[arg(value_enum)]
         shell: Shell,
 
-        /// Print to stdout instead of installing to the shell's user dir
-        #[arg(long)]
+        /// Print to stdout instead of installing to the shell's user dir.
+        /// The global --output writes to a file instead (works for every
+        /// shell, including those without a standard install dir).
+        #[arg(long, conflicts_with = "output")]
         print: bool,
     },
     /// Show why a process is running (ancestry, source, supervisor)
     Why {
-        /// Port number, process name, or PID to investigate
-        target: String,
+        /// Port number, process name, or PID to investigate. Optional with
+        /// --newer-than, which then looks at every listening process.
+        #[arg(required_unless_present = "newer_than")]
+        target: Option<String>,
+        /// Only processes started within this window, e.g. 90s, 5m, 2h, 1d
+        #[arg(long, value_name = "DURATION", value_parser = parse_window)]
+        newer_than: Option<Duration>,
+        /// Draw the ancestry as a process tree from PID 1 down, merging
+        /// ancestors that several matches share (table output only)
+        #[arg(long)]
+        tree: bool,
+        /// Show these environment variables of each process, e.g.
+        /// NODE_ENV,PORT (Linux; other variables are never shown)
+        #[arg(long, value_name = "KEYS", value_delimiter = ',')]
+        env: Vec<String>,
+    },
+    /// Check whether a port is free to bind, and who holds it if not.
+    /// Exits non-zero unless it is free; --protocol limits the check
+    Open {
+        /// Port to try binding on 0.0.0.0
+        #[arg(value_parser = clap::value_parser!(u16).range(1..))]
+        port: u16,
     },
     /// Track port usage over time
     History {
@@ -127,12 +455,22 @@ pub enum HistoryAction {
         /// Filter by process name
         #[arg(short = 'P', long)]
         process: Option<String>,
+        /// Filter by bind address (substring, e.g. 0.0.0.0)
+        #[arg(long)]
+        address: Option<String>,
         /// Hours of history to show (default: 24)
         #[arg(short = 'H', long, default_value = "24")]
         hours: i64,
-        /// Maximum entries to show
+        /// Maximum entries to show (0 = no limit)
         #[arg(short, long, default_value = "100")]
         limit: usize,
+        /// Only the most recent entry per port, protocol and process: an
+        /// inventory of what was last seen
+        #[arg(long)]
+        latest: bool,
+        /// Output format (default: table, or json with --json)
+        #[arg(long, value_enum)]
+        format: Option<HistoryFormat>,
     },
     /// Show timeline for a specific port
     Timeline {
@@ -150,10 +488,15 @@ pub enum HistoryAction {
         #[arg(short, long, default_value = "168")]
         keep: i64,
     },
-    /// Show ports that appeared or disappeared between two snapshots
+    /// Show ports that appeared, disappeared, or changed process between two
+    /// snapshots
     Diff {
         /// Compare latest snapshot against this many snapshots ago (default: 1)
         #[arg(short, long, default_value = "1")]
         ago: usize,
+        /// With --json, emit {"appeared": [...], "disappeared": [...],
+        /// "changed": [...]} instead of a flat array
+        #[arg(long)]
+        grouped: bool,
     },
 }
@@ -0,0 +1,239 @@
+//! `ports --baseline save|check` — persist the listening set to a file,
+//! then diff the live set against it, e.g. as a CI gate after a deploy.
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::cli::{BaselineAction, ProtocolFilter, StateFilter};
+use crate::filter;
+use crate::output::json;
+use crate::platform;
+use crate::types::{names_interpreter_of, PortInfo, Protocol};
+
+/// What identifies a listener across runs. PIDs change on every restart,
+/// so they're left out; the bind address stays, since a service moving
+/// from loopback to a wildcard bind is exactly the change to catch.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+struct Listener {
+    port: u16,
+    protocol: Protocol,
+    address: String,
+    process_name: String,
+}
+
+impl From<&PortInfo> for Listener {
+    fn from(p: &PortInfo) -> Self {
+        Self {
+            port: p.port,
+            protocol: p.protocol,
+            address: p.address.clone(),
+            process_name: p.process_name.clone(),
+        }
+    }
+}
+
+/// The baseline file is the `ports --json` document. Only the identity
+/// fields of each port are read back, so `ports --json > file` makes a
+/// valid baseline too.
+#[derive(Deserialize)]
+struct BaselineFile {
+    ports: Vec<Listener>,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn execute(
+    action: BaselineAction,
+    path: &Path,
+    query: Option<&str>,
+    use_regex: bool,
+    protocol: Option<ProtocolFilter>,
+    state: Option<StateFilter>,
+    interface: Option<&str>,
+    dev: bool,
+    output_json: bool,
+) -> Result<()> {
+    let listing = platform::get_listening_ports()?;
+    let docker_status = listing.docker_status;
+    let ports = PortInfo::filter_protocol(listing.ports, protocol);
+    let ports = PortInfo::filter_state(ports, state);
+    let mut ports = PortInfo::filter_interface(ports, interface)?;
+    if dev {
+        filter::retain_dev_only(&mut ports);
+    }
+    if let Some(query) = query {
+        ports = PortInfo::filter_by_query(ports, query, use_regex)?;
+    }
+    PortInfo::sort_vec(&mut ports, None);
+
+    match action {
+        BaselineAction::Save => {
+            let document = json::ports_document(&ports, &docker_status);
+            let text = serde_json::to_string_pretty(&document)?;
+            fs::write(path, text + "\n")
+                .with_context(|| format!("writing baseline {}", path.display()))?;
+            eprintln!(
+                "Saved {} listening port(s) to {}",
+                ports.len(),
+                path.display()
+            );
+            Ok(())
+        }
+        BaselineAction::Check => check(path, &ports, output_json),
+    }
+}
+
+fn check(path: &Path, ports: &[PortInfo], output_json: bool) -> Result<()> {
+    let text =
+        fs::read_to_string(path).with_context(|| format!("reading baseline {}", path.display()))?;
+    let baseline: BaselineFile = serde_json::from_str(&text)
+        .with_context(|| format!("parsing baseline {}", path.display()))?;
+    let (appeared, disappeared) = diff(&baseline.ports, ports);
+
+    if output_json {
+        let output = json!({ "appeared": appeared, "disappeared": disappeared });
+        println!("{}", json::to_string(&output)?);
+    } else if appeared.is_empty() && disappeared.is_empty() {
+        eprintln!(
+            "{} {} listening port(s) match {}",
+            "OK:".green().bold(),
+            ports.len(),
+            path.display()
+        );
+    } else {
+        if !appeared.is_empty() {
+            println!("{}", "New since baseline:".green().bold());
+            for p in &appeared {
+                println!(
+                    "  + {}/{} {} ({}) {}",
+                    p.port, p.protocol, p.process_name, p.pid, p.address
+                );
+            }
+        }
+        if !disappeared.is_empty() {
+            println!("{}", "Missing since baseline:".red().bold());
+            for l in &disappeared {
+                println!(
+                    "  - {}/{} {} {}",
+                    l.port, l.protocol, l.process_name, l.address
+                );
+            }
+        }
+    }
+
+    if !appeared.is_empty() || !disappeared.is_empty() {
+        bail!(
+            "{} new and {} missing listening port(s) compared to {}",
+            appeared.len(),
+            disappeared.len(),
+            path.display()
+        );
+    }
+    Ok(())
+}
+
+impl Listener {
+    /// Whether `p` is this listener. A baseline saved before Linux named
+    /// interpreters after their script still matches: `python3` there is
+    /// `python:manage.py` now.
+    fn matches(&self, p: &PortInfo) -> bool {
+        self.port == p.port
+            && self.protocol == p.protocol
+            && self.address == p.address
+            && (self.process_name == p.process_name
+                || names_interpreter_of(&self.process_name, &p.process_name))
+    }
+}
+
+/// Ports listening now that the baseline doesn't have, and baseline
+/// listeners that are gone.
+fn diff<'a>(baseline: &[Listener], current: &'a [PortInfo]) -> (Vec<&'a PortInfo>, Vec<Listener>) {
+    let before: BTreeSet<&Listener> = baseline.iter().collect();
+
+    let appeared = current
+        .iter()
+        .filter(|p| !before.iter().any(|l| l.matches(p)))
+        .collect();
+    let disappeared = before
+        .into_iter()
+        .filter(|l| !current.iter().any(|p| l.matches(p)))
+        .cloned()
+        .collect();
+    (appeared, disappeared)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_port(port: u16, pid: u32, name: &str, address: &str) -> PortInfo {
+        PortInfo {
+            port,
+            protocol: Protocol::Tcp,
+            pid,
+            process_name: name.to_string(),
+            address: address.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn diff_ignores_pid_changes_but_not_rebinds() {
+        let baseline: Vec<Listener> = [
+            make_port(22, 100, "sshd", "0.0.0.0:22"),
+            make_port(5432, 200, "postgres", "127.0.0.1:5432"),
+            make_port(6379, 300, "redis", "127.0.0.1:6379"),
+        ]
+        .iter()
+        .map(Listener::from)
+        .collect();
+        let current = vec![
+            // Restarted: new PID, same listener.
+            make_port(22, 101, "sshd", "0.0.0.0:22"),
+            // Rebound from loopback to every interface.
+            make_port(5432, 200, "postgres", "0.0.0.0:5432"),
+            make_port(8080, 400, "node", "0.0.0.0:8080"),
+        ];
+
+        let (appeared, disappeared) = diff(&baseline, &current);
+        let appeared: Vec<_> = appeared.iter().map(|p| p.address.as_str()).collect();
+        let disappeared: Vec<_> = disappeared.iter().map(|l| l.address.as_str()).collect();
+        assert_eq!(appeared, ["0.0.0.0:5432", "0.0.0.0:8080"]);
+        assert_eq!(disappeared, ["127.0.0.1:5432", "127.0.0.1:6379"]);
+    }
+
+    #[test]
+    fn diff_matches_interpreters_saved_under_their_binary_name() {
+        let baseline: Vec<Listener> = [make_port(8000, 100, "python3", "0.0.0.0:8000")]
+            .iter()
+            .map(Listener::from)
+            .collect();
+        let current = vec![make_port(8000, 100, "python:manage.py", "0.0.0.0:8000")];
+
+        let (appeared, disappeared) = diff(&baseline, &current);
+        assert!(appeared.is_empty());
+        assert!(disappeared.is_empty());
+    }
+
+    #[test]
+    fn baseline_reads_ports_json_document() {
+        let text = r#"{
+            "version": 1,
+            "generated_at": "2026-01-03T14:05:09Z",
+            "ports": [{"port": 22, "protocol": "tcp", "pid": 1, "process_name": "sshd",
+                       "address": "0.0.0.0:22", "service_name": "ssh"}],
+            "docker_status": "not_queried",
+            "docker_reason": null
+        }"#;
+        let file: BaselineFile = serde_json::from_str(text).unwrap();
+        assert_eq!(
+            file.ports,
+            [Listener::from(&make_port(22, 1, "sshd", "0.0.0.0:22"))]
+        );
+    }
+}
@@ -1,17 +1,23 @@
 //! History command implementation
 
+use std::io::{self, Write};
+
 use anyhow::Result;
-use chrono::Local;
+use chrono::{DateTime, Local, Utc};
 use colored::Colorize;
-use comfy_table::{
-    presets::UTF8_FULL_CONDENSED, Attribute, Cell, Color, ContentArrangement, Table,
-};
+use comfy_table::{presets::UTF8_FULL_CONDENSED, Attribute, Cell, Color};
 
-use crate::history::{self, DiffAction, HistoryQuery};
+use crate::cli::{HistoryFormat, StateFilter};
+use crate::history::{
+    self, DiffAction, HistoryEntry, HistoryQuery, HistoryStats, PortTimelineEntry,
+};
+use crate::output::{self, json};
+use crate::types::SocketState;
 
 /// Record a snapshot of current port state
 pub fn record(include_connections: bool, json: bool) -> Result<()> {
     let result = history::record_snapshot(include_connections)?;
+    let mut out = io::stdout().lock();
 
     if json {
         let output = serde_json::json!({
@@ -19,9 +25,10 @@ pub fn record(include_connections: bool, json: bool) -> Result<()> {
             "port_count": result.port_count,
             "timestamp": result.timestamp.to_rfc3339(),
         });
-        println!("{}", serde_json::to_string_pretty(&output)?);
+        writeln!(out, "{}", json::to_string(&output)?)?;
     } else {
-        println!(
+        writeln!(
+            out,
             "{} Recorded {} ports at {}",
             "✓".green(),
             result.port_count.to_string().cyan(),
@@ -29,66 +36,78 @@ pub fn record(include_connections: bool, json: bool) -> Result<()> {
                 .timestamp
                 .with_timezone(&Local)
                 .format("%Y-%m-%d %H:%M:%S")
-        );
+        )?;
     }
 
     Ok(())
 }
 
 /// Show history for a port or process
+#[allow(clippy::too_many_arguments)]
 pub fn show(
     port: Option<u16>,
     process: Option<String>,
+    address: Option<String>,
+    state: Option<StateFilter>,
     hours: Option<i64>,
     limit: usize,
-    json: bool,
+    latest: bool,
+    format: HistoryFormat,
 ) -> Result<()> {
     let query = HistoryQuery {
         port,
         process,
+        address,
+        state: state.map(|s| SocketState::from(s).to_string()),
         hours,
         limit,
+        latest,
     };
 
+    if format == HistoryFormat::Ndjson {
+        let mut out = io::stdout().lock();
+        return history::for_each_history(&query, |entry| {
+            serde_json::to_writer(&mut out, &entry_json(&entry))?;
+            writeln!(out)?;
+            Ok(())
+        });
+    }
+
     let entries = history::get_history(&query)?;
+    let mut out = io::stdout().lock();
 
-    if json {
-        let output: Vec<_> = entries
-            .iter()
-            .map(|e| {
-                serde_json::json!({
-                    "timestamp": e.timestamp.to_rfc3339(),
-                    "port": e.port,
-                    "protocol": e.protocol,
-                    "address": e.address,
-                    "pid": e.pid,
-                    "process_name": e.process_name,
-                    "container": e.container,
-                    "state": e.state,
-                })
-            })
-            .collect();
-        println!("{}", serde_json::to_string_pretty(&output)?);
+    if format == HistoryFormat::Json {
+        let output: Vec<_> = entries.iter().map(entry_json).collect();
+        writeln!(out, "{}", json::to_string(&output)?)?;
         return Ok(());
     }
 
+    print_entries(&mut out, &entries)?;
+    Ok(())
+}
+
+/// The `history show` table, or a hint to start recording.
+fn print_entries(out: &mut dyn Write, entries: &[HistoryEntry]) -> io::Result<()> {
     if entries.is_empty() {
-        println!("{}", "No history found matching your query.".yellow());
-        println!("Run {} to start recording.", "ports history record".cyan());
+        writeln!(out, "{}", "No history found matching your query.".yellow())?;
+        writeln!(
+            out,
+            "Run {} to start recording.",
+            "ports history record".cyan()
+        )?;
         return Ok(());
     }
 
-    let mut table = Table::new();
-    table
-        .load_preset(UTF8_FULL_CONDENSED)
-        .set_content_arrangement(ContentArrangement::Dynamic)
-        .set_header(vec![
-            Cell::new("Time").add_attribute(Attribute::Bold),
-            Cell::new("Port").add_attribute(Attribute::Bold),
-            Cell::new("Proto").add_attribute(Attribute::Bold),
-            Cell::new("Process").add_attribute(Attribute::Bold),
-            Cell::new("State").add_attribute(Attribute::Bold),
-        ]);
+    let mut table = output::new_table();
+    output::fit_to_terminal(&mut table);
+    table.load_preset(UTF8_FULL_CONDENSED).set_header(vec![
+        Cell::new("Time").add_attribute(Attribute::Bold),
+        Cell::new("Port").add_attribute(Attribute::Bold),
+        Cell::new("Proto").add_attribute(Attribute::Bold),
+        Cell::new("Process").add_attribute(Attribute::Bold),
+        Cell::new("User").add_attribute(Attribute::Bold),
+        Cell::new("State").add_attribute(Attribute::Bold),
+    ]);
 
     for entry in entries {
         let local_time = entry.timestamp.with_timezone(&Local);
@@ -112,23 +131,58 @@ pub fn show(
             Cell::new(entry.port).fg(Color::Cyan),
             Cell::new(&entry.protocol),
             Cell::new(process_display),
+            Cell::new(user_display(entry)),
             state_cell,
         ]);
     }
 
-    println!("{table}");
-    Ok(())
+    writeln!(out, "{table}")
+}
+
+fn entry_json(e: &HistoryEntry) -> serde_json::Value {
+    serde_json::json!({
+        "timestamp": e.timestamp.to_rfc3339(),
+        "port": e.port,
+        "protocol": e.protocol,
+        "address": e.address,
+        "pid": e.pid,
+        "process_name": e.process_name,
+        "container": e.container,
+        "state": e.state,
+        "remote_address": e.remote_address,
+        "remote_host": e.remote_host,
+        "uid": e.uid,
+        "user": e.user,
+    })
+}
+
+/// The user name, else the bare uid (no password-database entry), else
+/// `-` for rows recorded before owners were tracked.
+fn user_display(e: &HistoryEntry) -> String {
+    match (&e.user, e.uid) {
+        (Some(user), _) => user.clone(),
+        (None, Some(uid)) => uid.to_string(),
+        (None, None) => "-".to_string(),
+    }
 }
 
 /// Show statistics about recorded history
 pub fn stats(json: bool) -> Result<()> {
     let stats = history::get_stats()?;
     let top_ports = history::get_top_ports(10)?;
+    let top_processes = history::get_top_processes(10)?;
+    let mut out = io::stdout().lock();
 
     if json {
         let output = serde_json::json!({
             "snapshot_count": stats.snapshot_count,
             "total_entries": stats.total_entries,
+            "tcp_entries": stats.tcp_entries,
+            "udp_entries": stats.udp_entries,
+            // An array, so the counts stay most common first.
+            "state_counts": stats.state_counts.iter().map(|(state, count)| {
+                serde_json::json!({ "state": state, "count": count })
+            }).collect::<Vec<_>>(),
             "unique_ports": stats.unique_ports,
             "oldest_snapshot": stats.oldest_snapshot.map(|dt| dt.to_rfc3339()),
             "newest_snapshot": stats.newest_snapshot.map(|dt| dt.to_rfc3339()),
@@ -140,49 +194,104 @@ pub fn stats(json: bool) -> Result<()> {
                     "occurrences": count,
                 })
             }).collect::<Vec<_>>(),
+            "top_processes": top_processes.iter().map(|(name, ports, count)| {
+                serde_json::json!({
+                    "process_name": name,
+                    "distinct_ports": ports,
+                    "occurrences": count,
+                })
+            }).collect::<Vec<_>>(),
         });
-        println!("{}", serde_json::to_string_pretty(&output)?);
+        writeln!(out, "{}", json::to_string(&output)?)?;
         return Ok(());
     }
 
-    println!("{}", "📊 History Statistics".bold());
-    println!();
-    println!(
+    print_stats(&mut out, &stats, &top_ports, &top_processes)?;
+    Ok(())
+}
+
+fn print_stats(
+    out: &mut dyn Write,
+    stats: &HistoryStats,
+    top_ports: &[(u16, String, usize)],
+    top_processes: &[(String, usize, usize)],
+) -> io::Result<()> {
+    writeln!(out, "{}", "📊 History Statistics".bold())?;
+    writeln!(out)?;
+    writeln!(
+        out,
         "  Snapshots:    {}",
         stats.snapshot_count.to_string().cyan()
-    );
-    println!("  Port entries: {}", stats.total_entries.to_string().cyan());
-    println!("  Unique ports: {}", stats.unique_ports.to_string().cyan());
-    println!(
+    )?;
+    writeln!(
+        out,
+        "  Port entries: {} {}",
+        stats.total_entries.to_string().cyan(),
+        format!("(tcp {}, udp {})", stats.tcp_entries, stats.udp_entries).dimmed()
+    )?;
+    if !stats.state_counts.is_empty() {
+        let states: Vec<String> = stats
+            .state_counts
+            .iter()
+            .map(|(state, count)| format!("{state} {count}"))
+            .collect();
+        writeln!(out, "  States:       {}", states.join(", "))?;
+    }
+    writeln!(
+        out,
+        "  Unique ports: {}",
+        stats.unique_ports.to_string().cyan()
+    )?;
+    writeln!(
+        out,
         "  Database:     {}",
         history::format_bytes(stats.db_size_bytes).cyan()
-    );
+    )?;
 
     if let Some(oldest) = stats.oldest_snapshot {
         let local = oldest.with_timezone(&Local);
-        println!(
+        writeln!(
+            out,
             "  Oldest:       {}",
             local.format("%Y-%m-%d %H:%M").to_string().dimmed()
-        );
+        )?;
     }
     if let Some(newest) = stats.newest_snapshot {
         let local = newest.with_timezone(&Local);
-        println!(
+        writeln!(
+            out,
             "  Newest:       {}",
             local.format("%Y-%m-%d %H:%M").to_string().dimmed()
-        );
+        )?;
     }
 
     if !top_ports.is_empty() {
-        println!();
-        println!("{}", "🔝 Most Recorded Ports".bold());
+        writeln!(out)?;
+        writeln!(out, "{}", "🔝 Most Recorded Ports".bold())?;
         for (port, proto, count) in top_ports {
-            println!(
+            writeln!(
+                out,
                 "  {:>5}/{:<3}  {} occurrences",
                 port.to_string().cyan(),
                 proto,
                 count
-            );
+            )?;
+        }
+    }
+
+    if !top_processes.is_empty() {
+        writeln!(out)?;
+        writeln!(out, "{}", "🔁 Most Active Processes".bold())?;
+        for (name, ports, count) in top_processes {
+            let port_word = if *ports == 1 { "port" } else { "ports" };
+            writeln!(
+                out,
+                "  {:<20} {} occurrences across {} {}",
+                name.cyan(),
+                count,
+                ports,
+                port_word
+            )?;
         }
     }
 
@@ -192,152 +301,348 @@ pub fn stats(json: bool) -> Result<()> {
 /// Show timeline for a specific port
 pub fn timeline(port: u16, hours: i64, json: bool) -> Result<()> {
     let entries = history::get_port_timeline(port, hours)?;
+    let snapshots = history::get_snapshot_times(hours)?;
+    let gaps = gaps_before(&entries, &snapshots);
+    let mut out = io::stdout().lock();
 
     if json {
         let output: Vec<_> = entries
             .iter()
-            .map(|e| {
+            .zip(&gaps)
+            .map(|(e, gap)| {
                 serde_json::json!({
                     "timestamp": e.timestamp.to_rfc3339(),
                     "protocol": e.protocol,
                     "process_name": e.process_name,
                     "container": e.container,
                     "state": e.state,
+                    "gap_before": gap.map(|(missed, since)| serde_json::json!({
+                        "snapshots": missed,
+                        "seconds": (e.timestamp - since).num_seconds(),
+                    })),
                 })
             })
             .collect();
-        println!("{}", serde_json::to_string_pretty(&output)?);
+        writeln!(out, "{}", json::to_string(&output)?)?;
         return Ok(());
     }
 
+    print_timeline(&mut out, port, hours, &entries, &gaps, &snapshots)?;
+    Ok(())
+}
+
+/// The `history timeline` view: one line per recorded state, with the
+/// snapshots the port was missing from in between.
+fn print_timeline(
+    out: &mut dyn Write,
+    port: u16,
+    hours: i64,
+    entries: &[PortTimelineEntry],
+    gaps: &[Option<(usize, DateTime<Utc>)>],
+    snapshots: &[DateTime<Utc>],
+) -> io::Result<()> {
     if entries.is_empty() {
-        println!(
+        writeln!(
+            out,
             "{}",
             format!(
                 "No history found for port {} in the last {} hours.",
                 port, hours
             )
             .yellow()
-        );
+        )?;
         return Ok(());
     }
 
-    println!(
+    writeln!(
+        out,
         "{}",
         format!("📅 Timeline for port {} (last {} hours)", port, hours).bold()
-    );
-    println!();
+    )?;
+    writeln!(out)?;
+
+    let process_display = |entry: &PortTimelineEntry| match entry.container {
+        Some(ref container) => format!("{} ({})", entry.process_name, container),
+        None => entry.process_name.clone(),
+    };
+    let width = entries
+        .iter()
+        .map(|e| process_display(e).chars().count())
+        .max()
+        .unwrap_or(0);
 
     let mut prev_process: Option<String> = None;
+    let mut prev_state: Option<Option<&str>> = None;
+
+    for (entry, gap) in entries.iter().zip(gaps) {
+        if let Some((missed, since)) = gap {
+            let away = output::table::format_age(entry.timestamp.into(), (*since).into());
+            writeln!(
+                out,
+                "  {}",
+                format!("(gap: {away}, missing from {missed} snapshot(s))").dimmed()
+            )?;
+            // The port coming back is a transition even in the same state.
+            prev_state = None;
+        }
 
-    for entry in entries {
         let local_time = entry.timestamp.with_timezone(&Local);
         let time_str = local_time.format("%m-%d %H:%M:%S").to_string();
-
-        let process_display = if let Some(ref container) = entry.container {
-            format!("{} ({})", entry.process_name, container)
-        } else {
-            entry.process_name.clone()
-        };
+        let process = process_display(entry);
 
         // Show change indicator
-        let indicator = if prev_process.as_ref() != Some(&process_display) {
-            prev_process = Some(process_display.clone());
+        let indicator = if prev_process.as_ref() != Some(&process) {
+            prev_process = Some(process.clone());
             "→".green()
         } else {
             "·".dimmed()
         };
 
-        let state_str = entry.state.as_deref().unwrap_or("-");
+        let state = entry.state.as_deref();
+        let state_str = state.unwrap_or("-");
+        let state_colored = if prev_state == Some(state) {
+            state_str.dimmed()
+        } else {
+            match state {
+                Some("LISTEN") => state_str.green().bold(),
+                Some("ESTABLISHED") => state_str.cyan().bold(),
+                Some(_) => state_str.yellow().bold(),
+                None => state_str.normal(),
+            }
+        };
+        prev_state = Some(state);
 
-        println!(
-            "  {} {} {} {} {}",
+        writeln!(
+            out,
+            "  {} {} {:<4} {} {}",
             time_str.dimmed(),
             indicator,
             entry.protocol,
-            process_display.cyan(),
-            state_str.dimmed()
-        );
+            format!("{process:<width$}").cyan(),
+            state_colored
+        )?;
+    }
+
+    if let Some(last) = entries.last() {
+        let missed = snapshots_between(snapshots, last.timestamp, None);
+        if missed > 0 {
+            writeln!(
+                out,
+                "  {}",
+                format!("(gone: missing from the last {missed} snapshot(s))").dimmed()
+            )?;
+        }
     }
 
     Ok(())
 }
 
+/// For each timeline entry, the number of snapshots the port was missing
+/// from since it was last seen, and when that was. `None` when the port
+/// was in every snapshot in between (or for the first entry).
+fn gaps_before(
+    entries: &[PortTimelineEntry],
+    snapshots: &[DateTime<Utc>],
+) -> Vec<Option<(usize, DateTime<Utc>)>> {
+    let mut last_seen: Option<DateTime<Utc>> = None;
+    entries
+        .iter()
+        .map(|entry| {
+            let gap = last_seen
+                .map(|since| {
+                    (
+                        snapshots_between(snapshots, since, Some(entry.timestamp)),
+                        since,
+                    )
+                })
+                .filter(|&(missed, _)| missed > 0);
+            last_seen = Some(entry.timestamp);
+            gap
+        })
+        .collect()
+}
+
+/// Snapshots in the sorted `snapshots` taken strictly after `from` and
+/// before `to` (or ever after, without `to`).
+fn snapshots_between(
+    snapshots: &[DateTime<Utc>],
+    from: DateTime<Utc>,
+    to: Option<DateTime<Utc>>,
+) -> usize {
+    let start = snapshots.partition_point(|t| *t <= from);
+    let end = to.map_or(snapshots.len(), |to| snapshots.partition_point(|t| *t < to));
+    end.saturating_sub(start)
+}
+
 /// Show diff between two snapshots
-pub fn diff(ago: usize, json: bool) -> Result<()> {
+pub fn diff(ago: usize, grouped: bool, json: bool) -> Result<()> {
     let entries = history::get_diff(ago)?;
+    let mut out = io::stdout().lock();
 
     if json {
-        let output: Vec<_> = entries
-            .iter()
-            .map(|e| {
-                serde_json::json!({
-                    "port": e.port,
-                    "protocol": e.protocol,
-                    "process_name": e.process_name,
-                    "action": match e.action {
+        let output = if grouped {
+            let bucket = |action: DiffAction| -> Vec<serde_json::Value> {
+                entries
+                    .iter()
+                    .filter(|e| e.action == action)
+                    .map(diff_entry_json)
+                    .collect()
+            };
+            serde_json::json!({
+                "appeared": bucket(DiffAction::Appeared),
+                "disappeared": bucket(DiffAction::Disappeared),
+                "changed": bucket(DiffAction::Changed),
+            })
+        } else {
+            // The flat array predates `changed`; keep its two actions.
+            entries
+                .iter()
+                .filter(|e| e.action != DiffAction::Changed)
+                .map(|e| {
+                    let mut value = diff_entry_json(e);
+                    value["action"] = serde_json::json!(match e.action {
                         DiffAction::Appeared => "appeared",
-                        DiffAction::Disappeared => "disappeared",
-                    },
+                        _ => "disappeared",
+                    });
+                    value
                 })
-            })
-            .collect();
-        println!("{}", serde_json::to_string_pretty(&output)?);
+                .collect()
+        };
+        writeln!(out, "{}", json::to_string(&output)?)?;
         return Ok(());
     }
 
+    print_diff(&mut out, &entries)?;
+    Ok(())
+}
+
+fn print_diff(out: &mut dyn Write, entries: &[history::DiffEntry]) -> io::Result<()> {
     if entries.is_empty() {
-        println!("{}", "No changes detected between snapshots.".yellow());
+        writeln!(out, "{}", "No changes detected between snapshots.".yellow())?;
         return Ok(());
     }
 
-    let mut table = Table::new();
-    table
-        .load_preset(UTF8_FULL_CONDENSED)
-        .set_content_arrangement(ContentArrangement::Dynamic)
-        .set_header(vec![
-            Cell::new("PORT").add_attribute(Attribute::Bold),
-            Cell::new("PROTO").add_attribute(Attribute::Bold),
-            Cell::new("PROCESS").add_attribute(Attribute::Bold),
-            Cell::new("ACTION").add_attribute(Attribute::Bold),
-        ]);
+    let mut table = output::new_table();
+    output::fit_to_terminal(&mut table);
+    table.load_preset(UTF8_FULL_CONDENSED).set_header(vec![
+        Cell::new("PORT").add_attribute(Attribute::Bold),
+        Cell::new("PROTO").add_attribute(Attribute::Bold),
+        Cell::new("PROCESS").add_attribute(Attribute::Bold),
+        Cell::new("ACTION").add_attribute(Attribute::Bold),
+    ]);
 
-    for entry in &entries {
+    for entry in entries {
         let (action_cell, port_color) = match entry.action {
             DiffAction::Appeared => (Cell::new("appeared").fg(Color::Green), Color::Green),
             DiffAction::Disappeared => (Cell::new("disappeared").fg(Color::Red), Color::Red),
+            DiffAction::Changed => (Cell::new("changed").fg(Color::Yellow), Color::Yellow),
+        };
+        let process = match &entry.previous_process_name {
+            Some(previous) => format!("{previous} → {}", entry.process_name),
+            None => entry.process_name.clone(),
         };
 
         table.add_row(vec![
             Cell::new(entry.port).fg(port_color),
             Cell::new(&entry.protocol),
-            Cell::new(&entry.process_name),
+            Cell::new(process),
             action_cell,
         ]);
     }
 
-    println!("{table}");
+    writeln!(out, "{table}")?;
     Ok(())
 }
 
+/// One diff entry as JSON. Grouped output leaves out `action`, since the
+/// bucket already says it; `previous_process_name` only appears on
+/// `changed` entries.
+fn diff_entry_json(e: &history::DiffEntry) -> serde_json::Value {
+    let mut value = serde_json::json!({
+        "port": e.port,
+        "protocol": e.protocol,
+        "process_name": e.process_name,
+    });
+    if let Some(previous) = &e.previous_process_name {
+        value["previous_process_name"] = serde_json::json!(previous);
+    }
+    value
+}
+
 /// Clean up old history
 pub fn cleanup(keep_hours: i64, json: bool) -> Result<()> {
     let result = history::cleanup(keep_hours)?;
+    let mut out = io::stdout().lock();
 
     if json {
         let output = serde_json::json!({
             "snapshots_deleted": result.snapshots_deleted,
             "entries_deleted": result.entries_deleted,
         });
-        println!("{}", serde_json::to_string_pretty(&output)?);
+        writeln!(out, "{}", json::to_string(&output)?)?;
     } else {
-        println!(
+        writeln!(
+            out,
             "{} Cleaned up {} snapshots ({} port entries)",
             "✓".green(),
             result.snapshots_deleted.to_string().cyan(),
             result.entries_deleted.to_string().cyan()
-        );
+        )?;
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 1, 3, 14, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn diff_table_shows_process_change() {
+        let entries = [history::DiffEntry {
+            port: 8080,
+            protocol: "tcp".to_string(),
+            process_name: "caddy".to_string(),
+            previous_process_name: Some("nginx".to_string()),
+            action: DiffAction::Changed,
+        }];
+        let mut buf = Vec::new();
+        print_diff(&mut buf, &entries).unwrap();
+        let text = String::from_utf8(buf).unwrap();
+        assert!(text.contains("nginx → caddy"), "{text}");
+        assert!(text.contains("changed"), "{text}");
+
+        let mut buf = Vec::new();
+        print_diff(&mut buf, &[]).unwrap();
+        assert!(String::from_utf8(buf)
+            .unwrap()
+            .contains("No changes detected"));
+    }
+
+    fn entry(minute: u32) -> PortTimelineEntry {
+        PortTimelineEntry {
+            timestamp: at(minute),
+            protocol: "tcp".to_string(),
+            process_name: "nginx".to_string(),
+            container: None,
+            state: Some("LISTEN".to_string()),
+        }
+    }
+
+    #[test]
+    fn gaps_count_snapshots_the_port_missed() {
+        let snapshots: Vec<_> = (0..6).map(|i| at(i * 10)).collect();
+        // Seen at :00 and :10 (twice: tcp and tcp6), gone at :20 and :30,
+        // back at :40, gone again at :50.
+        let entries = vec![entry(0), entry(10), entry(10), entry(40)];
+
+        let gaps = gaps_before(&entries, &snapshots);
+        assert_eq!(gaps, [None, None, None, Some((2, at(10)))]);
+        assert_eq!(snapshots_between(&snapshots, at(40), None), 1);
+    }
+}
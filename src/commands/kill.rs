@@ -1,16 +1,213 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashSet};
 use std::io::{self, Write};
 
 use anyhow::{bail, Context, Result};
 use colored::Colorize;
-use nix::sys::signal::{kill, Signal};
+#[cfg(unix)]
+use nix::sys::signal::kill;
+#[cfg(unix)]
+use nix::sys::signal::Signal as NixSignal;
+#[cfg(unix)]
 use nix::unistd::Pid;
+use serde::Serialize;
 
 use crate::ancestry;
+use crate::cli::ProtocolFilter;
+use crate::output::json;
 use crate::platform;
-use crate::types::PortInfo;
+use crate::types::{self, PortInfo};
 
-pub fn execute(target: &str, force: bool, all: bool, connections: bool) -> Result<()> {
+/// What `ports kill` did to one process, for `--json`.
+#[derive(Debug, Serialize)]
+struct KillEntry {
+    pid: u32,
+    name: String,
+    ports: Vec<u16>,
+    /// Service manager likely to restart the process (`systemd`, `launchd`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    managed_by: Option<String>,
+    /// systemd unit or launchd label, when `managed_by` is set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    unit: Option<String>,
+    /// Why a kill failed or a process was skipped.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason: Option<String>,
+}
+
+impl KillEntry {
+    fn new(pid: u32, infos: &[&PortInfo]) -> Self {
+        Self {
+            pid,
+            name: infos[0].process_name.clone(),
+            ports: infos.iter().map(|p| p.port).collect(),
+            managed_by: None,
+            unit: None,
+            reason: None,
+        }
+    }
+
+    fn with_reason(mut self, reason: impl Into<String>) -> Self {
+        self.reason = Some(reason.into());
+        self
+    }
+}
+
+/// Some, but not all, targeted processes were signalled. `main` exits
+/// with status 2 for this, so scripts can tell a partial cleanup from
+/// one where nothing was killed (status 1).
+#[derive(Debug, thiserror::Error)]
+#[error("Failed to kill {failed} of {total} process(es)")]
+pub struct PartialFailure {
+    pub failed: usize,
+    pub total: usize,
+}
+
+/// Signal names accepted by [`parse_signal`], in the order `ports top`
+/// offers them.
+#[cfg(unix)]
+pub const SIGNAL_NAMES: &[&str] = &["TERM", "KILL", "INT", "HUP", "QUIT", "USR1", "USR2"];
+#[cfg(not(unix))]
+pub const SIGNAL_NAMES: &[&str] = &["TERM", "KILL"];
+
+/// A signal `ports kill` can send, one of [`SIGNAL_NAMES`]. Wraps nix's
+/// type so that it stays out of the public [`crate::Cli`].
+#[cfg(unix)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Signal(NixSignal);
+
+#[cfg(unix)]
+impl Signal {
+    pub const SIGTERM: Signal = Signal(NixSignal::SIGTERM);
+    pub const SIGKILL: Signal = Signal(NixSignal::SIGKILL);
+
+    pub fn as_str(self) -> &'static str {
+        self.0.as_str()
+    }
+
+    /// The signal called `name` (upper case, no `SIG` prefix), if it's one
+    /// of [`SIGNAL_NAMES`].
+    fn from_name(name: &str) -> Option<Signal> {
+        if !SIGNAL_NAMES.contains(&name) {
+            return None;
+        }
+        format!("SIG{name}").parse().ok().map(Signal)
+    }
+}
+
+/// Windows has no signals; `taskkill` can only ask a process to close
+/// (TERM) or force it (KILL). Named after nix's variants so callers
+/// don't need to care which one they have.
+#[cfg(not(unix))]
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Signal {
+    SIGTERM,
+    SIGKILL,
+}
+
+#[cfg(not(unix))]
+impl Signal {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Signal::SIGTERM => "SIGTERM",
+            Signal::SIGKILL => "SIGKILL",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Signal> {
+        match name {
+            "TERM" => Some(Signal::SIGTERM),
+            "KILL" => Some(Signal::SIGKILL),
+            _ => None,
+        }
+    }
+}
+
+/// Parse a signal name or number: `TERM`, `sigkill`, `SIGHUP`, `9`.
+/// Names are case-insensitive and the `SIG` prefix is optional; numbers
+/// must be those of a signal in [`SIGNAL_NAMES`]. Used by
+/// `ports kill --signal` and the `ports top` kill prompt.
+pub fn parse_signal(s: &str) -> Result<Signal> {
+    let s = s.trim();
+    if let Ok(n) = s.parse::<i32>() {
+        return signal_from_number(n);
+    }
+    let upper = s.to_ascii_uppercase();
+    let name = upper.strip_prefix("SIG").unwrap_or(&upper);
+    Signal::from_name(name).ok_or_else(|| {
+        anyhow::anyhow!(
+            "unknown signal `{}` (expected one of {}, or its number)",
+            s,
+            SIGNAL_NAMES.join(", ")
+        )
+    })
+}
+
+#[cfg(unix)]
+fn signal_from_number(n: i32) -> Result<Signal> {
+    NixSignal::try_from(n)
+        .ok()
+        .and_then(|sig| Signal::from_name(&sig.as_str()["SIG".len()..]))
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "signal number {n} is not one of {}",
+                SIGNAL_NAMES.join(", ")
+            )
+        })
+}
+
+#[cfg(not(unix))]
+fn signal_from_number(n: i32) -> Result<Signal> {
+    match n {
+        15 => Ok(Signal::SIGTERM),
+        9 => Ok(Signal::SIGKILL),
+        _ => bail!("only signals 15 (TERM) and 9 (KILL) are supported on this platform"),
+    }
+}
+
+/// `ports kill --json` output. Printed on every exit once the sockets
+/// are listed, including refusals and a declined prompt.
+#[derive(Debug, Default, Serialize)]
+struct KillReport {
+    /// Signal that was sent, e.g. `SIGTERM`.
+    signal: &'static str,
+    killed: Vec<KillEntry>,
+    failed: Vec<KillEntry>,
+    skipped: Vec<KillEntry>,
+    /// Processes under systemd or launchd that were left alone.
+    skipped_managed: Vec<KillEntry>,
+}
+
+impl KillReport {
+    /// Record `entry` as not signalled, for `reason`.
+    fn skip(&mut self, entry: KillEntry, reason: &str) {
+        let entry = entry.with_reason(reason);
+        if entry.managed_by.is_some() {
+            self.skipped_managed.push(entry);
+        } else {
+            self.skipped.push(entry);
+        }
+    }
+
+    fn print(&self, output_json: bool) -> Result<()> {
+        if output_json {
+            println!("{}", json::to_string(self)?);
+        }
+        Ok(())
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn execute(
+    target: &str,
+    force: bool,
+    all: bool,
+    connections: bool,
+    include_self: bool,
+    signal: Signal,
+    protocol: Option<ProtocolFilter>,
+    output_json: bool,
+) -> Result<()> {
     // Kill drops docker_status — no output stage to display it.
     let mut ports = platform::get_listening_ports()?.ports;
     if connections {
@@ -19,16 +216,69 @@ pub fn execute(target: &str, force: bool, all: bool, connections: bool) -> Resul
         ports.sort_by_key(|p| (p.pid, p.port));
         ports.dedup_by_key(|p| (p.pid, p.port));
     }
+    let ports = PortInfo::filter_protocol(ports, protocol);
 
-    let matches = PortInfo::filter_by_query(ports, target, false)?;
+    let is_range = types::parse_port_range(types::split_protocol(target).0)?.is_some();
+    let matches = find_matches(ports, target)?;
+    let mut report = KillReport {
+        signal: signal.as_str(),
+        ..KillReport::default()
+    };
 
     if matches.is_empty() {
+        report.print(output_json)?;
         bail!("No process found matching '{}'", target);
     }
 
-    let grouped = group_by_pid(&matches);
+    let mut grouped = group_by_pid(&matches);
 
-    if grouped.len() > 1 && !all {
+    if let Some(unknown) = grouped.remove(&types::UNATTRIBUTED_PID) {
+        for port in &unknown {
+            eprintln!(
+                "{} Skipping port {}: its owning process is unknown",
+                "Warning:".yellow().bold(),
+                port.port
+            );
+        }
+        report.skip(
+            KillEntry::new(types::UNATTRIBUTED_PID, &unknown),
+            "owner unknown",
+        );
+        if grouped.is_empty() {
+            report.print(output_json)?;
+            bail!("No process we can signal matches '{}'", target);
+        }
+    }
+
+    if !include_self {
+        let protected = protected_pids();
+        grouped.retain(|pid, infos| {
+            if !protected.contains(pid) {
+                return true;
+            }
+            eprintln!(
+                "{} Skipping PID {} ({}): it is this process or one of its ancestors. \
+                 Use --include-self to kill it anyway.",
+                "Warning:".yellow().bold(),
+                pid,
+                infos[0].process_name
+            );
+            report.skip(KillEntry::new(*pid, infos), "own process ancestry");
+            false
+        });
+        if grouped.is_empty() {
+            report.print(output_json)?;
+            bail!(
+                "Refusing to kill '{}': every match is part of our own process ancestry",
+                target
+            );
+        }
+    }
+
+    // A range asks for many PIDs up front, so it only needs the single
+    // confirmation below — unless --force skips that, in which case
+    // --all is still required.
+    if grouped.len() > 1 && !all && (!is_range || force) {
         eprintln!("Multiple processes found:");
         for (pid, infos) in &grouped {
             let ports: Vec<_> = infos.iter().map(|p| p.port.to_string()).collect();
@@ -38,23 +288,28 @@ pub fn execute(target: &str, force: bool, all: bool, connections: bool) -> Resul
                 infos[0].process_name,
                 ports.join(", ")
             );
+            report.skip(KillEntry::new(*pid, infos), "multiple matches");
         }
+        report.print(output_json)?;
         bail!("Specify a more specific target, use a port number, or use --all");
     }
 
+    let mut targets = Vec::new();
     for (pid, infos) in &grouped {
-        let process_name = &infos[0].process_name;
-        let port_list: Vec<_> = infos.iter().map(|p| p.port.to_string()).collect();
-
+        let mut entry = KillEntry::new(*pid, infos);
         eprintln!(
             "PID {} ({}) listening on: {}",
             pid,
-            process_name,
-            port_list.join(", ")
+            entry.name,
+            infos
+                .iter()
+                .map(|p| p.port.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
         );
 
         // Show supervisor warning if managed by a restart-capable service manager.
-        if let Some(a) = ancestry::get_ancestry(*pid, process_name) {
+        if let Some(a) = ancestry::get_ancestry(*pid, &entry.name) {
             let unit_or_label = a.systemd_unit.as_deref().or(a.launchd_label.as_deref());
             match a.source {
                 ancestry::SourceType::Systemd | ancestry::SourceType::Launchd => {
@@ -68,37 +323,90 @@ pub fn execute(target: &str, force: bool, all: bool, connections: bool) -> Resul
                         manager,
                         detail
                     );
+                    entry.managed_by = Some(manager);
+                    entry.unit = unit_or_label.map(str::to_string);
                 }
                 _ => {}
             }
         }
+        targets.push(entry);
     }
 
     if !force && !confirm_kill()? {
         eprintln!("Aborted.");
-        return Ok(());
+        for entry in targets {
+            report.skip(entry, "aborted");
+        }
+        return report.print(output_json);
     }
 
-    let mut killed = 0;
-    for (pid, _) in grouped {
-        match kill_process(pid) {
+    for entry in targets {
+        match kill_process(entry.pid, signal) {
             Ok(()) => {
-                eprintln!("Killed PID {}", pid);
-                killed += 1;
+                if !output_json {
+                    if signal == Signal::SIGTERM {
+                        eprintln!("Killed PID {}", entry.pid);
+                    } else {
+                        eprintln!("Sent {} to PID {}", signal.as_str(), entry.pid);
+                    }
+                }
+                report.killed.push(entry);
+            }
+            Err(e) => {
+                if !output_json {
+                    eprintln!("Failed to kill PID {}: {}", entry.pid, e);
+                }
+                report.failed.push(entry.with_reason(format!("{e:#}")));
             }
-            Err(e) => eprintln!("Failed to kill PID {}: {}", pid, e),
         }
     }
 
-    if killed == 0 {
+    report.print(output_json)?;
+
+    if report.killed.is_empty() {
         bail!("Failed to kill any processes");
     }
+    if !report.failed.is_empty() {
+        return Err(PartialFailure {
+            failed: report.failed.len(),
+            total: report.failed.len() + report.killed.len(),
+        }
+        .into());
+    }
 
     Ok(())
 }
 
-fn group_by_pid(ports: &[PortInfo]) -> HashMap<u32, Vec<&PortInfo>> {
-    let mut map: HashMap<u32, Vec<&PortInfo>> = HashMap::new();
+/// PIDs that `kill` refuses to signal without `--include-self`: this
+/// process and every ancestor up to PID 1, so `ports kill bash` can't
+/// take down the shell (or terminal) it was launched from.
+fn protected_pids() -> HashSet<u32> {
+    let own = std::process::id();
+    let mut pids = HashSet::from([own]);
+    if let Some(a) = ancestry::get_ancestry(own, "ports") {
+        pids.extend(a.chain.iter().map(|anc| anc.pid));
+    }
+    pids
+}
+
+/// Select the ports a kill target refers to: every port inside a
+/// `START-END` range, otherwise the usual port/name query match. A `/tcp`
+/// or `/udp` suffix (`53/udp`, `3000-3010/tcp`) keeps one protocol.
+fn find_matches(ports: Vec<PortInfo>, target: &str) -> Result<Vec<PortInfo>> {
+    let (base, protocol) = types::split_protocol(target);
+    match types::parse_port_range(base)? {
+        Some(range) => Ok(ports
+            .into_iter()
+            .filter(|p| range.contains(&p.port) && protocol.is_none_or(|proto| p.protocol == proto))
+            .collect()),
+        None => PortInfo::filter_by_query(ports, target, false),
+    }
+}
+
+/// Group ports by owning PID. Ordered so the confirmation listing is
+/// stable across runs.
+fn group_by_pid(ports: &[PortInfo]) -> BTreeMap<u32, Vec<&PortInfo>> {
+    let mut map: BTreeMap<u32, Vec<&PortInfo>> = BTreeMap::new();
     for port in ports {
         map.entry(port.pid).or_default().push(port);
     }
@@ -117,8 +425,194 @@ fn confirm_kill() -> Result<bool> {
     Ok(matches!(input.trim().to_lowercase().as_str(), "y" | "yes"))
 }
 
-pub fn kill_process(pid: u32) -> Result<()> {
-    kill(Pid::from_raw(pid as i32), Signal::SIGTERM)
+/// Send `signal` to `pid`. Refuses [`types::UNATTRIBUTED_PID`]: on Unix,
+/// signalling PID 0 would hit our own process group.
+#[cfg(unix)]
+pub fn kill_process(pid: u32, signal: Signal) -> Result<()> {
+    if pid == types::UNATTRIBUTED_PID {
+        bail!("Cannot kill a socket whose owning process is unknown");
+    }
+    kill(Pid::from_raw(pid as i32), signal.0)
         .with_context(|| format!("Failed to kill PID {}", pid))?;
     Ok(())
 }
+
+/// Windows has no SIGTERM; `taskkill` without `/F` asks the process to
+/// close, which is the closest equivalent. SIGKILL adds `/F`.
+#[cfg(not(unix))]
+pub fn kill_process(pid: u32, signal: Signal) -> Result<()> {
+    if pid == types::UNATTRIBUTED_PID {
+        bail!("Cannot kill a socket whose owning process is unknown");
+    }
+    let mut command = std::process::Command::new("taskkill");
+    command.args(["/PID", &pid.to_string()]);
+    if signal == Signal::SIGKILL {
+        command.arg("/F");
+    }
+    let status = command
+        .stdout(std::process::Stdio::null())
+        .status()
+        .with_context(|| format!("Failed to kill PID {}", pid))?;
+    if !status.success() {
+        bail!(
+            "Failed to kill PID {}: taskkill exited with {}",
+            pid,
+            status
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Protocol;
+
+    fn make_port(port: u16, pid: u32, name: &str) -> PortInfo {
+        PortInfo {
+            port,
+            protocol: Protocol::Tcp,
+            pid,
+            process_name: name.to_string(),
+            address: format!("127.0.0.1:{port}"),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn range_target_groups_all_pids_in_range() {
+        let ports = vec![
+            make_port(2999, 10, "below"),
+            make_port(3000, 20, "node"),
+            make_port(3001, 20, "node"),
+            make_port(3005, 30, "vite"),
+            make_port(3010, 40, "python3"),
+            make_port(3011, 50, "above"),
+        ];
+
+        let matches = find_matches(ports, "3000-3010").unwrap();
+        let grouped = group_by_pid(&matches);
+
+        assert_eq!(grouped.keys().copied().collect::<Vec<_>>(), [20, 30, 40]);
+        let node_ports: Vec<u16> = grouped[&20].iter().map(|p| p.port).collect();
+        assert_eq!(node_ports, [3000, 3001]);
+    }
+
+    #[test]
+    fn protocol_suffix_applies_to_ranges() {
+        let udp = |port, pid| PortInfo {
+            protocol: Protocol::Udp,
+            ..make_port(port, pid, "dns")
+        };
+        let ports = vec![make_port(3000, 20, "node"), udp(3001, 30)];
+
+        let matches = find_matches(ports.clone(), "3000-3010/udp").unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].pid, 30);
+        let matches = find_matches(ports, "3000/tcp").unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].pid, 20);
+    }
+
+    #[test]
+    fn hyphenated_name_is_not_a_range() {
+        let ports = vec![
+            make_port(8080, 10, "docker-proxy"),
+            make_port(3000, 20, "node"),
+        ];
+
+        let matches = find_matches(ports, "docker-proxy").unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].pid, 10);
+    }
+
+    #[test]
+    fn reversed_range_is_rejected() {
+        assert!(find_matches(vec![make_port(3000, 20, "node")], "3010-3000").is_err());
+    }
+
+    #[test]
+    fn kill_report_serializes_outcomes() {
+        let a = make_port(3000, 10, "node");
+        let b = make_port(3001, 10, "node");
+        let mut report = KillReport {
+            signal: "SIGTERM",
+            ..KillReport::default()
+        };
+        report.killed.push(KillEntry::new(10, &[&a, &b]));
+        report.skip(KillEntry::new(0, &[&a]), "owner unknown");
+        let mut managed = KillEntry::new(20, &[&b]);
+        managed.managed_by = Some("systemd".to_string());
+        managed.unit = Some("web.service".to_string());
+        report.skip(managed, "aborted");
+
+        let value = serde_json::to_value(&report).unwrap();
+        assert_eq!(
+            value["killed"][0],
+            serde_json::json!({"pid": 10, "name": "node", "ports": [3000, 3001]})
+        );
+        assert_eq!(value["signal"], "SIGTERM");
+        assert_eq!(value["failed"], serde_json::json!([]));
+        assert_eq!(value["skipped"][0]["reason"], "owner unknown");
+        assert_eq!(value["skipped"].as_array().unwrap().len(), 1);
+        assert_eq!(value["skipped_managed"][0]["unit"], "web.service");
+        assert_eq!(value["skipped_managed"][0]["reason"], "aborted");
+    }
+
+    #[test]
+    fn kill_process_refuses_unattributed_pid() {
+        let err = kill_process(types::UNATTRIBUTED_PID, Signal::SIGTERM).unwrap_err();
+        assert!(err.to_string().contains("unknown"), "{err}");
+    }
+
+    #[test]
+    fn parse_signal_accepts_names_and_numbers() {
+        assert_eq!(parse_signal("TERM").unwrap(), Signal::SIGTERM);
+        assert_eq!(parse_signal("15").unwrap(), Signal::SIGTERM);
+        assert_eq!(parse_signal("9").unwrap(), Signal::SIGKILL);
+        for name in SIGNAL_NAMES {
+            assert!(parse_signal(name).is_ok(), "{name}");
+        }
+    }
+
+    #[test]
+    fn parse_signal_is_case_insensitive_with_optional_prefix() {
+        assert_eq!(
+            parse_signal("SIGKILL").unwrap(),
+            parse_signal("KILL").unwrap()
+        );
+        assert_eq!(parse_signal("sigkill").unwrap(), Signal::SIGKILL);
+        assert_eq!(parse_signal("Kill").unwrap(), Signal::SIGKILL);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn parse_signal_covers_unix_signals() {
+        assert_eq!(parse_signal("hup").unwrap().as_str(), "SIGHUP");
+        assert_eq!(parse_signal("SIGUSR2").unwrap().as_str(), "SIGUSR2");
+        assert_eq!(parse_signal("2").unwrap().as_str(), "SIGINT");
+        // A real signal, but not one `--signal` documents.
+        let err = parse_signal("19").unwrap_err().to_string();
+        assert!(err.contains("not one of TERM, KILL"), "{err}");
+    }
+
+    #[test]
+    fn parse_signal_rejects_unknown() {
+        let err = parse_signal("STOPIT").unwrap_err().to_string();
+        assert!(err.contains("TERM, KILL"), "{err}");
+        assert!(parse_signal("999").is_err());
+        assert!(parse_signal("").is_err());
+    }
+
+    #[test]
+    fn protected_pids_includes_self() {
+        assert!(protected_pids().contains(&std::process::id()));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn protected_pids_includes_parent() {
+        let ppid = nix::unistd::getppid().as_raw() as u32;
+        assert!(protected_pids().contains(&ppid));
+    }
+}
@@ -1,50 +1,220 @@
-use anyhow::Result;
+use std::io;
 
+use anyhow::{bail, Result};
+
+use crate::allowlist::Allowlist;
 use crate::ancestry;
-use crate::cli::{ProtocolFilter, SortField};
+use crate::cli::{GroupBy, ProtocolFilter, SortField, StateFilter};
 use crate::filter;
-use crate::output::{json, table};
+use crate::output::{self, json, porcelain, table};
 use crate::platform;
-use crate::types::PortInfo;
+use crate::types::{PortInfo, UNATTRIBUTED_PID};
 
+#[allow(clippy::too_many_arguments)]
 pub fn execute(
     output_json: bool,
     connections: bool,
     sort: Option<SortField>,
     protocol: Option<ProtocolFilter>,
+    state: Option<StateFilter>,
+    interface: Option<&str>,
     why: bool,
     dev: bool,
+    docker_only: bool,
+    group_by: Option<GroupBy>,
+    wide: bool,
+    dedup: bool,
+    age: bool,
+    summary: bool,
+    with_conn_count: bool,
+    limit: Option<usize>,
+    porcelain: bool,
+    security: bool,
+    by_remote: bool,
+    anomaly: bool,
+    allowlist: Option<&Allowlist>,
+    unexpected_only: bool,
+    throughput: bool,
 ) -> Result<()> {
-    let listing = if connections {
+    let mut listing = if connections {
         platform::get_connections()?
     } else {
         platform::get_listening_ports()?
     };
+    if !connections {
+        track_first_seen(&mut listing.ports, None, age)?;
+    }
     let docker_status = listing.docker_status;
-    let mut ports = PortInfo::filter_protocol(listing.ports, protocol);
+    let ports = PortInfo::filter_protocol(listing.ports, protocol);
+    let ports = PortInfo::filter_state(ports, state);
+    let mut ports = PortInfo::filter_interface(ports, interface)?;
     if dev {
         filter::retain_dev_only(&mut ports);
     }
+    if docker_only {
+        ports.retain(|p| p.container.is_some());
+    }
+    if connections && age {
+        attach_opened_after(&mut ports);
+    }
     PortInfo::sort_vec(&mut ports, sort);
+    if security {
+        PortInfo::rank_by_risk(&mut ports);
+    }
+    if anomaly {
+        PortInfo::flag_anomalies(&mut ports);
+    }
+    if let Some(allowlist) = allowlist {
+        allowlist.mark(&mut ports);
+        if unexpected_only {
+            ports.retain(|p| p.allowed == Some(false));
+        }
+    }
+    let unexpected = ports.iter().filter(|p| p.allowed == Some(false)).count();
+    let total = ports.len();
+    if by_remote {
+        let mut groups = PortInfo::group_by_remote(&ports);
+        groups.truncate(limit.unwrap_or(usize::MAX));
+        if output_json {
+            output::write_output(|out| json::print_remote_groups(out, &groups))?;
+        } else {
+            table::print_remote_groups(&mut io::stdout().lock(), &groups)?;
+        }
+        return Ok(());
+    }
+    if !summary {
+        apply_limit(&mut ports, limit);
+    }
+    if with_conn_count && !connections {
+        PortInfo::attach_conn_counts(&mut ports, &platform::get_connections()?.ports);
+    }
+    if throughput {
+        platform::sample_throughput(&mut ports)?;
+    }
+
+    if porcelain {
+        output::write_output(|out| porcelain::print_ports(out, &ports))?;
+        return check_unexpected(allowlist, unexpected);
+    }
 
-    if why {
+    if summary {
+        if output_json {
+            output::write_output(|out| json::print_service_summary(out, &ports))?;
+        } else {
+            table::print_service_summary(&mut io::stdout().lock(), &ports)?;
+        }
+    } else if why {
         let pids_with_names: Vec<(u32, &str)> = ports
             .iter()
             .map(|p| (p.pid, p.process_name.as_str()))
             .collect();
         let ancestry_map = ancestry::get_ancestry_batch(&pids_with_names);
         if output_json {
-            json::print_ports_why(&ports, &ancestry_map, &docker_status);
+            output::write_output(|out| {
+                json::print_ports_why(out, &ports, &ancestry_map, &docker_status)
+            })?;
         } else {
             table::print_warning(&docker_status);
-            table::print_ports_why(&ports, &ancestry_map);
+            table::print_platform_note();
+            table::print_attribution_hint(&ports);
+            let mut out = io::stdout().lock();
+            match group_by {
+                Some(GroupBy::Process) => {
+                    table::print_ports_grouped(&mut out, &ports, Some(&ancestry_map))?
+                }
+                None => table::print_ports_why(&mut out, &ports, &ancestry_map)?,
+            }
         }
     } else if output_json {
-        json::print_ports(&ports, &docker_status);
+        output::write_output(|out| json::print_ports(out, &ports, &docker_status))?;
     } else {
         table::print_warning(&docker_status);
-        table::print_ports(&ports);
+        table::print_platform_note();
+        table::print_attribution_hint(&ports);
+        let mut out = io::stdout().lock();
+        match group_by {
+            Some(GroupBy::Process) => table::print_ports_grouped(&mut out, &ports, None)?,
+            None => table::print_ports(&mut out, &ports, wide, dedup)?,
+        }
     }
 
+    if !output_json && !summary {
+        table::print_limit_note(ports.len(), total);
+        if security {
+            table::print_security_note(&ports);
+        }
+        if anomaly {
+            table::print_anomalies(&ports);
+        }
+    }
+
+    check_unexpected(allowlist, unexpected)
+}
+
+/// With `--allow-file`, fail once the listing is out if anything the file
+/// doesn't cover is listening, so a CI job catches the drift.
+pub(super) fn check_unexpected(allowlist: Option<&Allowlist>, unexpected: usize) -> Result<()> {
+    match allowlist {
+        Some(allowlist) if unexpected > 0 => bail!(
+            "{unexpected} listening port(s) not in {}",
+            allowlist.path().display()
+        ),
+        _ => Ok(()),
+    }
+}
+
+/// Keep only the first `limit` rows of the sorted listing.
+pub(super) fn apply_limit(ports: &mut Vec<PortInfo>, limit: Option<usize>) {
+    if let Some(limit) = limit {
+        ports.truncate(limit);
+    }
+}
+
+/// `--age` on connections: no OS we support records when a connection was
+/// established, so each row gets its process's start time as a bound.
+pub(super) fn attach_opened_after(ports: &mut [PortInfo]) {
+    let mut pids: Vec<u32> = ports
+        .iter()
+        .map(|p| p.pid)
+        .filter(|&pid| pid != UNATTRIBUTED_PID)
+        .collect();
+    pids.sort_unstable();
+    pids.dedup();
+    let start_times = platform::process_start_times(&pids);
+    for port in ports.iter_mut() {
+        port.opened_after = start_times.get(&port.pid).copied();
+    }
+}
+
+/// Keep the persistent first-seen map current on every listing, so `--age`
+/// has times to show the first time it's used, and attach them to `ports`
+/// with `--age`. `only` is the ports a narrowed listing was limited to.
+/// Without `--age` this is best effort: an unwritable history database
+/// shouldn't break a plain listing.
+#[cfg(feature = "history")]
+pub(super) fn track_first_seen(
+    ports: &mut [PortInfo],
+    only: Option<&[u16]>,
+    age: bool,
+) -> Result<()> {
+    match crate::history::update_first_seen(ports, only, age) {
+        Err(e) if age => Err(e.context("tracking first-seen times for --age")),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(not(feature = "history"))]
+pub(super) fn track_first_seen(
+    _ports: &mut [PortInfo],
+    _only: Option<&[u16]>,
+    age: bool,
+) -> Result<()> {
+    if age {
+        anyhow::bail!(
+            "this binary was built without the `history` feature; \
+             --age requires it. Rebuild with default features \
+             or `cargo install portls --features history`"
+        );
+    }
     Ok(())
 }
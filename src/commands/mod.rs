@@ -1,6 +1,9 @@
+pub mod baseline;
 #[cfg(feature = "history")]
 pub mod history;
 pub mod kill;
 pub mod list;
+pub mod open;
 pub mod query;
+pub mod version;
 pub mod why;
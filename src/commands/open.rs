@@ -0,0 +1,195 @@
+//! `ports open <port>` — can a server bind this port right now, and if
+//! not, who holds it?
+
+use std::collections::HashSet;
+use std::io;
+use std::net::{Ipv4Addr, TcpListener, UdpSocket};
+
+use anyhow::{bail, Context, Result};
+use colored::Colorize;
+
+use crate::ancestry;
+use crate::cli::ProtocolFilter;
+use crate::commands::why;
+use crate::output::{self, json};
+use crate::platform;
+use crate::types::{PortInfo, Protocol};
+
+/// Outcome of trying to bind the port.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BindStatus {
+    Free,
+    InUse,
+    /// A privileged port (below 1024) and we aren't root. The kernel checks
+    /// privilege before conflicts, so this says nothing about whether the
+    /// port is taken.
+    Denied,
+}
+
+impl BindStatus {
+    fn as_str(self) -> &'static str {
+        match self {
+            BindStatus::Free => "free",
+            BindStatus::InUse => "in_use",
+            BindStatus::Denied => "permission_denied",
+        }
+    }
+}
+
+/// Bind `port` on every IPv4 address, as a server would, and let go of it
+/// right away.
+fn probe(port: u16, protocol: Protocol) -> Result<BindStatus> {
+    let addr = (Ipv4Addr::UNSPECIFIED, port);
+    let bound = match protocol {
+        Protocol::Tcp => TcpListener::bind(addr).map(drop),
+        Protocol::Udp => UdpSocket::bind(addr).map(drop),
+    };
+    match bound {
+        Ok(()) => Ok(BindStatus::Free),
+        Err(e) if e.kind() == io::ErrorKind::AddrInUse => Ok(BindStatus::InUse),
+        Err(e) if e.kind() == io::ErrorKind::PermissionDenied => Ok(BindStatus::Denied),
+        Err(e) => Err(e).with_context(|| format!("binding {port}/{protocol}")),
+    }
+}
+
+pub fn execute(port: u16, protocol: Option<ProtocolFilter>, output_json: bool) -> Result<()> {
+    let protocols = match protocol {
+        Some(ProtocolFilter::Tcp) => vec![Protocol::Tcp],
+        Some(ProtocolFilter::Udp) => vec![Protocol::Udp],
+        None | Some(ProtocolFilter::Both) => vec![Protocol::Tcp, Protocol::Udp],
+    };
+    let statuses = protocols
+        .into_iter()
+        .map(|protocol| Ok((protocol, probe(port, protocol)?)))
+        .collect::<Result<Vec<_>>>()?;
+    let taken: Vec<Protocol> = statuses
+        .iter()
+        .filter(|(_, status)| *status != BindStatus::Free)
+        .map(|(protocol, _)| *protocol)
+        .collect();
+
+    let holders = if taken.is_empty() {
+        Vec::new()
+    } else {
+        holders(port, &taken)?
+    };
+    let statuses = settle(statuses, &holders);
+    let in_use: Vec<Protocol> = statuses
+        .iter()
+        .filter(|(_, status)| *status == BindStatus::InUse)
+        .map(|(protocol, _)| *protocol)
+        .collect();
+    let pids_with_names: Vec<(u32, &str)> = holders
+        .iter()
+        .map(|p| (p.pid, p.process_name.as_str()))
+        .collect();
+    let ancestry_map = ancestry::get_ancestry_batch(&pids_with_names);
+
+    if output_json {
+        let status: Vec<(String, &str)> = statuses
+            .iter()
+            .map(|(protocol, status)| (protocol.to_string(), status.as_str()))
+            .collect();
+        output::write_output(|out| json::print_open(out, port, &status, &holders, &ancestry_map))?;
+    } else {
+        for (protocol, status) in &statuses {
+            let label = match status {
+                BindStatus::Free => "free".green(),
+                BindStatus::InUse => "in use".red().bold(),
+                BindStatus::Denied => "cannot tell: binding needs privileges".yellow(),
+            };
+            println!("{port}/{protocol}  {label}");
+        }
+        if !holders.is_empty() {
+            println!();
+            why::print_holders(&holders, &ancestry_map);
+        } else if !in_use.is_empty() {
+            eprintln!(
+                "{}",
+                "note: no process found holding the port; it may be in another network \
+                 namespace or need sudo to see"
+                    .dimmed()
+            );
+        }
+    }
+
+    if !in_use.is_empty() {
+        bail!("port {port} is in use");
+    }
+    if statuses.iter().any(|(_, s)| *s == BindStatus::Denied) {
+        bail!("cannot tell whether port {port} is free: binding it needs elevated privileges");
+    }
+    Ok(())
+}
+
+/// A bind refused for lack of privilege is `InUse` when a socket on that
+/// protocol was found holding the port, and stays `Denied` (unknown)
+/// otherwise.
+fn settle(
+    statuses: Vec<(Protocol, BindStatus)>,
+    holders: &[PortInfo],
+) -> Vec<(Protocol, BindStatus)> {
+    statuses
+        .into_iter()
+        .map(|(protocol, status)| {
+            let held = holders.iter().any(|h| h.protocol == protocol);
+            match status {
+                BindStatus::Denied if held => (protocol, BindStatus::InUse),
+                status => (protocol, status),
+            }
+        })
+        .collect()
+}
+
+/// Sockets on `port` for the protocols that didn't bind: listeners
+/// first, then connections, one row per process.
+fn holders(port: u16, protocols: &[Protocol]) -> Result<Vec<PortInfo>> {
+    let mut ports = platform::get_listening_ports()?.ports;
+    if let Ok(conns) = platform::get_connections() {
+        ports.extend(conns.ports);
+    }
+    let mut seen_pids = HashSet::new();
+    Ok(ports
+        .into_iter()
+        .filter(|p| p.port == port && protocols.contains(&p.protocol))
+        .filter(|p| seen_pids.insert(p.pid))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bound_port_is_in_use_until_released() {
+        let listener = TcpListener::bind((Ipv4Addr::UNSPECIFIED, 0)).unwrap();
+        let port = listener.local_addr().unwrap().port();
+        assert_eq!(probe(port, Protocol::Tcp).unwrap(), BindStatus::InUse);
+        drop(listener);
+        assert_eq!(probe(port, Protocol::Tcp).unwrap(), BindStatus::Free);
+
+        let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).unwrap();
+        let port = socket.local_addr().unwrap().port();
+        assert_eq!(probe(port, Protocol::Udp).unwrap(), BindStatus::InUse);
+    }
+
+    #[test]
+    fn denied_bind_is_in_use_only_with_a_holder() {
+        let holder = PortInfo {
+            port: 81,
+            protocol: Protocol::Tcp,
+            ..Default::default()
+        };
+        let statuses = vec![
+            (Protocol::Tcp, BindStatus::Denied),
+            (Protocol::Udp, BindStatus::Denied),
+        ];
+        assert_eq!(
+            settle(statuses, &[holder]),
+            [
+                (Protocol::Tcp, BindStatus::InUse),
+                (Protocol::Udp, BindStatus::Denied)
+            ]
+        );
+    }
+}
@@ -1,9 +1,12 @@
+use std::io;
+
 use anyhow::Result;
 
+use crate::allowlist::Allowlist;
 use crate::ancestry;
-use crate::cli::{ProtocolFilter, SortField};
+use crate::cli::{GroupBy, ProtocolFilter, SortField, StateFilter};
 use crate::filter;
-use crate::output::{json, table};
+use crate::output::{self, json, porcelain, table};
 use crate::platform;
 use crate::types::PortInfo;
 
@@ -14,43 +17,149 @@ pub fn execute(
     connections: bool,
     sort: Option<SortField>,
     protocol: Option<ProtocolFilter>,
+    state: Option<StateFilter>,
+    interface: Option<&str>,
     use_regex: bool,
     why: bool,
     dev: bool,
+    docker_only: bool,
+    group_by: Option<GroupBy>,
+    wide: bool,
+    dedup: bool,
+    age: bool,
+    summary: bool,
+    with_conn_count: bool,
+    limit: Option<usize>,
+    porcelain: bool,
+    security: bool,
+    by_remote: bool,
+    anomaly: bool,
+    allowlist: Option<&Allowlist>,
+    unexpected_only: bool,
+    throughput: bool,
 ) -> Result<()> {
-    let listing = if connections {
-        platform::get_connections()?
-    } else {
-        platform::get_listening_ports()?
+    // A query of port numbers only needs those ports' sockets, and only
+    // those ports' first-seen times are updated from them.
+    let only_ports = PortInfo::query_ports(query, use_regex);
+    let mut listing = match (&only_ports, connections) {
+        (Some(on), true) => platform::get_connections_on(on)?,
+        (Some(on), false) => platform::get_listening_ports_on(on)?,
+        (None, true) => platform::get_connections()?,
+        (None, false) => platform::get_listening_ports()?,
     };
+    if !connections {
+        super::list::track_first_seen(&mut listing.ports, only_ports.as_deref(), age)?;
+    }
     let docker_status = listing.docker_status;
-    let mut ports = PortInfo::filter_protocol(listing.ports, protocol);
+    let ports = PortInfo::filter_protocol(listing.ports, protocol);
+    let ports = PortInfo::filter_state(ports, state);
+    let mut ports = PortInfo::filter_interface(ports, interface)?;
     if dev {
         filter::retain_dev_only(&mut ports);
     }
+    if docker_only {
+        ports.retain(|p| p.container.is_some());
+    }
 
     let mut filtered = PortInfo::filter_by_query(ports, query, use_regex)?;
+    if connections && age {
+        super::list::attach_opened_after(&mut filtered);
+    }
 
     PortInfo::sort_vec(&mut filtered, sort);
+    if security {
+        PortInfo::rank_by_risk(&mut filtered);
+    }
+    if anomaly {
+        PortInfo::flag_anomalies(&mut filtered);
+    }
+    if let Some(allowlist) = allowlist {
+        allowlist.mark(&mut filtered);
+        if unexpected_only {
+            filtered.retain(|p| p.allowed == Some(false));
+        }
+    }
+    let unexpected = filtered.iter().filter(|p| p.allowed == Some(false)).count();
+    let total = filtered.len();
+    if by_remote {
+        let mut groups = PortInfo::group_by_remote(&filtered);
+        groups.truncate(limit.unwrap_or(usize::MAX));
+        if output_json {
+            output::write_output(|out| json::print_remote_groups(out, &groups))?;
+        } else {
+            table::print_remote_groups(&mut io::stdout().lock(), &groups)?;
+        }
+        return Ok(());
+    }
+    if !summary {
+        super::list::apply_limit(&mut filtered, limit);
+    }
+    if with_conn_count && !connections {
+        let conns = match &only_ports {
+            Some(on) => platform::get_connections_on(on)?,
+            None => platform::get_connections()?,
+        };
+        PortInfo::attach_conn_counts(&mut filtered, &conns.ports);
+    }
+    if throughput {
+        platform::sample_throughput(&mut filtered)?;
+    }
 
-    if why {
+    if porcelain {
+        output::write_output(|out| porcelain::print_ports(out, &filtered))?;
+        return super::list::check_unexpected(allowlist, unexpected);
+    }
+
+    if summary {
+        if output_json {
+            output::write_output(|out| json::print_service_summary(out, &filtered))?;
+        } else {
+            table::print_service_summary(&mut io::stdout().lock(), &filtered)?;
+        }
+    } else if why {
         let pids_with_names: Vec<(u32, &str)> = filtered
             .iter()
             .map(|p| (p.pid, p.process_name.as_str()))
             .collect();
         let ancestry_map = ancestry::get_ancestry_batch(&pids_with_names);
         if output_json {
-            json::print_ports_why(&filtered, &ancestry_map, &docker_status);
+            output::write_output(|out| {
+                json::print_ports_why(out, &filtered, &ancestry_map, &docker_status)
+            })?;
         } else {
             table::print_warning(&docker_status);
-            table::print_ports_why(&filtered, &ancestry_map);
+            table::print_platform_note();
+            table::print_attribution_hint(&filtered);
+            let mut out = io::stdout().lock();
+            match group_by {
+                Some(GroupBy::Process) => {
+                    table::print_ports_grouped(&mut out, &filtered, Some(&ancestry_map))?
+                }
+                None => table::print_ports_why(&mut out, &filtered, &ancestry_map)?,
+            }
         }
     } else if output_json {
-        json::print_ports(&filtered, &docker_status);
+        output::write_output(|out| json::print_ports(out, &filtered, &docker_status))?;
     } else {
         table::print_warning(&docker_status);
-        table::print_ports(&filtered);
+        table::print_platform_note();
+        table::print_attribution_hint(&filtered);
+        let mut out = io::stdout().lock();
+        match group_by {
+            Some(GroupBy::Process) => table::print_ports_grouped(&mut out, &filtered, None)?,
+            None => table::print_ports(&mut out, &filtered, wide, dedup)?,
+        }
+    }
+
+    if !output_json && !summary {
+        table::print_limit_note(filtered.len(), total);
+        if security {
+            table::print_security_note(&filtered);
+        }
+        if anomaly {
+            table::print_anomalies(&filtered);
+        }
     }
 
-    Ok(())
+    super::list::check_unexpected(allowlist, unexpected)
 }
@@ -0,0 +1,78 @@
+//! `ports --version [--json]` — the version, plus what this binary was
+//! built with, for comparing binaries across hosts.
+
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::output::json;
+
+#[derive(Serialize)]
+struct BuildInfo {
+    name: &'static str,
+    version: &'static str,
+    /// Cargo features compiled in.
+    features: Vec<&'static str>,
+    /// How sockets are enumerated on this target.
+    backend: &'static str,
+    os: &'static str,
+    arch: &'static str,
+}
+
+fn build_info() -> BuildInfo {
+    let features = [
+        ("docker", cfg!(feature = "docker")),
+        ("tui", cfg!(feature = "tui")),
+        ("history", cfg!(feature = "history")),
+        ("watch", cfg!(feature = "watch")),
+        ("netstat", cfg!(feature = "netstat")),
+        ("notify", cfg!(feature = "notify")),
+    ];
+    BuildInfo {
+        name: "ports",
+        version: env!("CARGO_PKG_VERSION"),
+        features: features
+            .into_iter()
+            .filter_map(|(name, on)| on.then_some(name))
+            .collect(),
+        backend: backend(),
+        os: std::env::consts::OS,
+        arch: std::env::consts::ARCH,
+    }
+}
+
+fn backend() -> &'static str {
+    if cfg!(target_os = "linux") {
+        "procfs"
+    } else if cfg!(target_os = "macos") {
+        "listeners+lsof"
+    } else if cfg!(feature = "netstat") {
+        "listeners+netstat"
+    } else {
+        "listeners"
+    }
+}
+
+/// Print `ports <version>` as clap would, or the build info as JSON.
+pub fn execute(output_json: bool) -> Result<()> {
+    let info = build_info();
+    if output_json {
+        println!("{}", json::to_string(&info)?);
+    } else {
+        println!("{} {}", info.name, info.version);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_info_lists_compiled_features() {
+        let info = build_info();
+        assert_eq!(info.version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(info.features.contains(&"docker"), cfg!(feature = "docker"));
+        assert_eq!(info.features.contains(&"notify"), cfg!(feature = "notify"));
+        assert_eq!(info.os, std::env::consts::OS);
+    }
+}
@@ -1,77 +1,146 @@
 //! `ports why <target>` subcommand — trace process ancestry.
 
 use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 use colored::Colorize;
 
-use crate::ancestry::{self, ProcessAncestry};
+use crate::ancestry::{self, Ancestor, ProcessAncestry};
+use crate::cli::ProtocolFilter;
+use crate::output::{self, json, table};
 use crate::platform;
-use crate::types::PortInfo;
+use crate::types::{self, DockerStatus, PortInfo};
 
-pub fn execute(target: &str, output_json: bool) -> Result<()> {
+/// Parse a `--newer-than` window: a number followed by `s`, `m`, `h`,
+/// or `d` (`90s`, `5m`, `2h`, `1d`). A bare number is seconds.
+pub fn parse_window(s: &str) -> Result<Duration> {
+    let s = s.trim();
+    let (digits, unit) = s.split_at(s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len()));
+    let Ok(n) = digits.parse::<u64>() else {
+        bail!("invalid duration `{s}` (expected e.g. 90s, 5m, 2h, 1d)");
+    };
+    let scale = match unit {
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86400,
+        _ => bail!("invalid duration unit in `{s}` (expected s, m, h, or d)"),
+    };
+    let Some(secs) = n.checked_mul(scale) else {
+        bail!("duration `{s}` is too large");
+    };
+    Ok(Duration::from_secs(secs))
+}
+
+/// Render a window in the largest unit that divides it evenly.
+fn format_window(window: Duration) -> String {
+    let secs = window.as_secs();
+    match secs {
+        0 => "0s".to_string(),
+        _ if secs.is_multiple_of(86400) => format!("{}d", secs / 86400),
+        _ if secs.is_multiple_of(3600) => format!("{}h", secs / 3600),
+        _ if secs.is_multiple_of(60) => format!("{}m", secs / 60),
+        _ => format!("{secs}s"),
+    }
+}
+
+/// Investigate `target`, or, with no target, every listening process.
+/// `newer_than` keeps only processes started within that window; `tree`
+/// draws one merged process tree instead of a chain line per process;
+/// `env` names environment variables to show for each process. Only
+/// sockets of `protocol` are considered.
+pub fn execute(
+    target: Option<&str>,
+    newer_than: Option<Duration>,
+    tree: bool,
+    env: &[String],
+    protocol: Option<ProtocolFilter>,
+    output_json: bool,
+) -> Result<()> {
     // Fetch both listening ports and connections for maximum coverage.
-    // `ports why` has its own JSON shape (an array of WhyEntry) and does not
-    // surface docker_status today; adding it is tracked separately.
-    let mut ports = platform::get_listening_ports()?.ports;
+    // Docker status comes from the listening pass, which is the one that
+    // resolves container names for `docker-proxy`.
+    let listing = platform::get_listening_ports()?;
+    let docker_status = listing.docker_status;
+    let mut ports = PortInfo::filter_protocol(listing.ports, protocol);
+    let listening = ports.len();
     if let Ok(conns) = platform::get_connections() {
-        ports.extend(conns.ports);
+        ports.extend(PortInfo::filter_protocol(conns.ports, protocol));
     }
-    // Auto-detect target type: try port number first, then PID, then name.
-    let matches = if let Ok(port_num) = target.parse::<u16>() {
-        let by_port: Vec<_> = ports
-            .iter()
-            .filter(|p| p.port == port_num)
-            .cloned()
-            .collect();
-        if by_port.is_empty() {
-            // u16 fits in u32 — try as PID.
-            ports
-                .iter()
-                .filter(|p| p.pid == port_num as u32)
-                .cloned()
-                .collect()
-        } else {
-            by_port
-        }
-    } else if let Ok(pid) = target.parse::<u32>() {
-        // Doesn't fit in u16, so it can only be a PID.
-        ports.iter().filter(|p| p.pid == pid).cloned().collect()
-    } else {
-        let target_lower = target.to_lowercase();
-        ports
-            .iter()
-            .filter(|p| {
-                p.process_name.to_lowercase().contains(&target_lower)
-                    || p.container
-                        .as_ref()
-                        .map(|c| c.to_lowercase().contains(&target_lower))
-                        .unwrap_or(false)
-            })
-            .cloned()
-            .collect()
+    let matches = match target {
+        Some(target) => match_target(&ports, target),
+        // Without a target, connections would drag in every client
+        // process; only listeners are of interest.
+        None => ports[..listening].to_vec(),
     };
 
     if matches.is_empty() {
         if output_json {
-            println!("[]");
-        } else {
+            output::write_output(|out| {
+                json::print_ports_why(out, &[], &HashMap::new(), &docker_status)
+            })?;
+        } else if let Some(target) = target {
             eprintln!(
                 "{} No process found matching '{}'",
                 "Error:".red().bold(),
                 target
             );
+        } else {
+            eprintln!("{}", "No listening processes found".yellow());
         }
         return Ok(());
     }
 
     // Deduplicate by PID.
     let mut seen_pids = std::collections::HashSet::new();
-    let unique: Vec<_> = matches
+    let mut unique: Vec<_> = matches
         .into_iter()
         .filter(|p| seen_pids.insert(p.pid))
         .collect();
 
+    // Narrow to recently started processes before the (costly) ancestry
+    // walk. Processes whose start time can't be read are dropped: we
+    // can't show they're new.
+    let mut start_times = HashMap::new();
+    if let Some(window) = newer_than {
+        let pids: Vec<u32> = unique
+            .iter()
+            .map(|p| p.pid)
+            .filter(|&pid| pid != 0)
+            .collect();
+        start_times = platform::process_start_times(&pids);
+        // A window reaching back before the epoch has no lower bound.
+        let cutoff = SystemTime::now().checked_sub(window);
+        unique.retain(|p| {
+            start_times
+                .get(&p.pid)
+                .is_some_and(|&start| cutoff.is_none_or(|cutoff| start >= cutoff))
+        });
+
+        if unique.is_empty() {
+            if output_json {
+                output::write_output(|out| {
+                    json::print_ports_why(out, &[], &HashMap::new(), &docker_status)
+                })?;
+            } else {
+                let what = match target {
+                    Some(target) => format!("matching '{target}'"),
+                    None => "listening".to_string(),
+                };
+                eprintln!(
+                    "{}",
+                    format!(
+                        "No process {what} started in the last {}",
+                        format_window(window)
+                    )
+                    .yellow()
+                );
+            }
+            return Ok(());
+        }
+    }
+
     // Gather ports-per-PID for display.
     let mut ports_by_pid: HashMap<u32, Vec<&PortInfo>> = HashMap::new();
     for p in &ports {
@@ -83,22 +152,156 @@ pub fn execute(target: &str, output_json: bool) -> Result<()> {
         .iter()
         .map(|p| (p.pid, p.process_name.as_str()))
         .collect();
-    let ancestry_map = ancestry::get_ancestry_batch(&pids_with_names);
+    let mut ancestry_map = ancestry::get_ancestry_batch(&pids_with_names);
+
+    // Only the variables asked for are read back, never the whole
+    // environment: it routinely holds secrets.
+    let env_map: HashMap<u32, Option<Vec<(String, String)>>> = if env.is_empty() {
+        HashMap::new()
+    } else {
+        unique
+            .iter()
+            .map(|p| (p.pid, platform::process_env(p.pid, env)))
+            .collect()
+    };
 
     if output_json {
-        print_json(&unique, &ports_by_pid, &ancestry_map);
+        // JSON is the complete picture: start time and uptime for every
+        // process, not just with --newer-than.
+        if newer_than.is_none() {
+            let pids: Vec<u32> = unique
+                .iter()
+                .map(|p| p.pid)
+                .filter(|&pid| pid != 0)
+                .collect();
+            start_times = platform::process_start_times(&pids);
+        }
+        let now = SystemTime::now();
+        for (pid, ancestry) in &mut ancestry_map {
+            if let Some(&start) = start_times.get(pid) {
+                ancestry.set_started(start, now);
+            }
+        }
+        let env_map = env_map
+            .into_iter()
+            .filter_map(|(pid, vars)| Some((pid, vars?)))
+            .collect();
+        print_json(
+            &unique,
+            &ports_by_pid,
+            &ancestry_map,
+            &env_map,
+            &docker_status,
+        )?;
     } else {
-        print_table(&unique, &ports_by_pid, &ancestry_map);
+        let details = Details {
+            start_times: &start_times,
+            env_keys: env,
+            env_map: &env_map,
+        };
+        print_table(&unique, &ports_by_pid, &ancestry_map, &details, tree);
     }
 
     Ok(())
 }
 
+/// Auto-detect the target type: try port number first, then PID, then
+/// name. A port with a `/tcp` or `/udp` suffix (`53/udp`) only matches
+/// that protocol.
+fn match_target(ports: &[PortInfo], target: &str) -> Vec<PortInfo> {
+    if let (port, Some(protocol)) = types::split_protocol(target) {
+        return match port.parse::<u16>() {
+            Ok(port_num) => ports
+                .iter()
+                .filter(|p| p.port == port_num && p.protocol == protocol)
+                .cloned()
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+    }
+    if let Ok(port_num) = target.parse::<u16>() {
+        let by_port: Vec<_> = ports
+            .iter()
+            .filter(|p| p.port == port_num)
+            .cloned()
+            .collect();
+        if by_port.is_empty() {
+            // u16 fits in u32 — try as PID.
+            ports
+                .iter()
+                .filter(|p| p.pid == port_num as u32)
+                .cloned()
+                .collect()
+        } else {
+            by_port
+        }
+    } else if let Ok(pid) = target.parse::<u32>() {
+        // Doesn't fit in u16, so it can only be a PID.
+        ports.iter().filter(|p| p.pid == pid).cloned().collect()
+    } else {
+        match_by_name(ports, target)
+    }
+}
+
+/// Match a non-numeric target against process and container names and
+/// container-ID prefixes, falling back to the full command line when none
+/// match — so `ports why manage.py` picks the Django server out of a dozen
+/// `python` processes.
+fn match_by_name(ports: &[PortInfo], target: &str) -> Vec<PortInfo> {
+    let target_lower = target.to_lowercase();
+    let contains = |s: &str| s.to_lowercase().contains(&target_lower);
+
+    let by_name: Vec<_> = ports
+        .iter()
+        .filter(|p| {
+            contains(&p.process_name)
+                || p.container.as_deref().is_some_and(contains)
+                || p.container_id_starts_with(&target_lower)
+        })
+        .cloned()
+        .collect();
+    if !by_name.is_empty() {
+        return by_name;
+    }
+
+    ports
+        .iter()
+        .filter(|p| p.command_line.as_deref().is_some_and(contains))
+        .cloned()
+        .collect()
+}
+
+/// Print the ancestry of each process in `holders`, as `ports why` does,
+/// for commands that name who holds a port.
+pub fn print_holders(holders: &[PortInfo], ancestry_map: &HashMap<u32, ProcessAncestry>) {
+    let mut ports_by_pid: HashMap<u32, Vec<&PortInfo>> = HashMap::new();
+    for p in holders {
+        ports_by_pid.entry(p.pid).or_default().push(p);
+    }
+    let details = Details {
+        start_times: &HashMap::new(),
+        env_keys: &[],
+        env_map: &HashMap::new(),
+    };
+    print_table(holders, &ports_by_pid, ancestry_map, &details, false);
+}
+
+/// Optional per-process lines for the table: start time with
+/// `--newer-than`, environment variables with `--env`.
+struct Details<'a> {
+    start_times: &'a HashMap<u32, SystemTime>,
+    env_keys: &'a [String],
+    env_map: &'a HashMap<u32, Option<Vec<(String, String)>>>,
+}
+
 fn print_table(
     processes: &[PortInfo],
     ports_by_pid: &HashMap<u32, Vec<&PortInfo>>,
     ancestry_map: &HashMap<u32, ProcessAncestry>,
+    details: &Details,
+    tree: bool,
 ) {
+    let now = SystemTime::now();
     for (i, proc_info) in processes.iter().enumerate() {
         if i > 0 {
             println!();
@@ -124,6 +327,31 @@ fn print_table(
             println!("  {:<10} {}", "Ports:".dimmed(), port_strs.join(", "));
         }
 
+        if let Some(&start) = details.start_times.get(&pid) {
+            println!(
+                "  {:<10} {} ago",
+                "Started:".dimmed(),
+                table::format_age(now, start)
+            );
+        }
+
+        if !details.env_keys.is_empty() {
+            let env_str = match details.env_map.get(&pid) {
+                Some(Some(vars)) if !vars.is_empty() => vars
+                    .iter()
+                    .map(|(key, value)| format!("{key}={value}"))
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                Some(Some(_)) => format!("none of {} set", details.env_keys.join(", "))
+                    .dimmed()
+                    .to_string(),
+                _ => "unavailable (another user's process, or not Linux)"
+                    .dimmed()
+                    .to_string(),
+            };
+            println!("  {:<10} {}", "Env:".dimmed(), env_str);
+        }
+
         // Ancestry details.
         if let Some(ancestry) = ancestry_map.get(&pid) {
             println!(
@@ -140,20 +368,23 @@ fn print_table(
                 println!("  {:<10} {}", "Label:".dimmed(), label);
             }
 
-            // Chain display: root -> ... -> target
-            let chain_str: Vec<String> = ancestry
-                .chain
-                .iter()
-                .rev()
-                .map(|a| {
-                    if a.pid == pid {
-                        format!("{}({})", a.name.bold(), a.pid)
-                    } else {
-                        format!("{}({})", a.name, a.pid)
-                    }
-                })
-                .collect();
-            println!("  {:<10} {}", "Chain:".dimmed(), chain_str.join(" → "));
+            // Chain display: root -> ... -> target. With --tree, the
+            // merged tree printed after all processes replaces it.
+            if !tree {
+                let chain_str: Vec<String> = ancestry
+                    .chain
+                    .iter()
+                    .rev()
+                    .map(|a| {
+                        if a.pid == pid {
+                            format!("{}({})", a.name.bold(), a.pid)
+                        } else {
+                            format!("{}({})", a.name, a.pid)
+                        }
+                    })
+                    .collect();
+                println!("  {:<10} {}", "Chain:".dimmed(), chain_str.join(" → "));
+            }
 
             if let Some(ref git) = ancestry.git_context {
                 let branch_str = git
@@ -186,56 +417,256 @@ fn print_table(
             );
         }
     }
+
+    if tree {
+        print_tree(processes, ports_by_pid, ancestry_map);
+    }
 }
 
-fn print_json(
+/// Print every traced process's ancestry as one tree, with each target
+/// labelled by its ports.
+fn print_tree(
     processes: &[PortInfo],
     ports_by_pid: &HashMap<u32, Vec<&PortInfo>>,
     ancestry_map: &HashMap<u32, ProcessAncestry>,
 ) {
-    use serde::Serialize;
+    let chains = processes
+        .iter()
+        .filter_map(|p| ancestry_map.get(&p.pid))
+        .map(|a| a.chain.as_slice());
+    let forest = build_tree(chains);
+    if forest.is_empty() {
+        return;
+    }
+    let labels: HashMap<u32, String> = processes
+        .iter()
+        .map(|p| {
+            let ports: Vec<String> = ports_by_pid
+                .get(&p.pid)
+                .into_iter()
+                .flatten()
+                .map(|p| format!("{}/{}", p.port, p.protocol))
+                .collect();
+            (p.pid, ports.join(", "))
+        })
+        .collect();
 
-    #[derive(Serialize)]
-    struct WhyEntry {
-        pid: u32,
-        process_name: String,
-        ports: Vec<PortEntry>,
-        #[serde(skip_serializing_if = "Option::is_none")]
-        ancestry: Option<ProcessAncestry>,
+    let mut lines = Vec::new();
+    render_tree(&forest, &labels, "", true, &mut lines);
+    println!("\n{}", "Process tree:".cyan().bold());
+    for line in lines {
+        println!("  {line}");
     }
+}
 
-    #[derive(Serialize)]
-    struct PortEntry {
-        port: u16,
-        protocol: String,
-        address: String,
+/// A process in the `--tree` view and the children through which some
+/// target was reached.
+struct TreeNode<'a> {
+    pid: u32,
+    name: &'a str,
+    children: Vec<TreeNode<'a>>,
+}
+
+/// Merge ancestry chains (target first, as traced) into a forest rooted
+/// at each chain's topmost ancestor, so shared ancestors appear once.
+/// Children keep the order they were first reached in.
+fn build_tree<'a>(chains: impl IntoIterator<Item = &'a [Ancestor]>) -> Vec<TreeNode<'a>> {
+    let mut roots: Vec<TreeNode> = Vec::new();
+    for chain in chains {
+        let mut level = &mut roots;
+        for ancestor in chain.iter().rev() {
+            let idx = match level.iter().position(|n| n.pid == ancestor.pid) {
+                Some(idx) => idx,
+                None => {
+                    level.push(TreeNode {
+                        pid: ancestor.pid,
+                        name: &ancestor.name,
+                        children: Vec::new(),
+                    });
+                    level.len() - 1
+                }
+            };
+            level = &mut level[idx].children;
+        }
+    }
+    roots
+}
+
+/// Render `nodes` pstree-style into `out`, one line per process. Roots
+/// get no connector; `labels` annotates the targets.
+fn render_tree(
+    nodes: &[TreeNode],
+    labels: &HashMap<u32, String>,
+    prefix: &str,
+    root: bool,
+    out: &mut Vec<String>,
+) {
+    for (i, node) in nodes.iter().enumerate() {
+        let last = i + 1 == nodes.len();
+        let (branch, indent) = match (root, last) {
+            (true, _) => ("", ""),
+            (false, true) => ("└─ ", "   "),
+            (false, false) => ("├─ ", "│  "),
+        };
+        let mut line = format!("{prefix}{branch}{}({})", node.name, node.pid);
+        if let Some(label) = labels.get(&node.pid) {
+            line.push_str("  ← ");
+            line.push_str(label);
+        }
+        out.push(line);
+        render_tree(
+            &node.children,
+            labels,
+            &format!("{prefix}{indent}"),
+            false,
+            out,
+        );
     }
+}
 
-    let entries: Vec<WhyEntry> = processes
+/// JSON uses the same envelope and per-port shape as `ports --why --json`:
+/// every socket of each matched process, with its ancestry attached.
+fn print_json(
+    processes: &[PortInfo],
+    ports_by_pid: &HashMap<u32, Vec<&PortInfo>>,
+    ancestry_map: &HashMap<u32, ProcessAncestry>,
+    env_map: &HashMap<u32, Vec<(String, String)>>,
+    docker_status: &DockerStatus,
+) -> Result<()> {
+    let ports: Vec<PortInfo> = processes
         .iter()
-        .map(|p| {
-            let port_entries = ports_by_pid
-                .get(&p.pid)
-                .map(|pp| {
-                    pp.iter()
-                        .map(|pi| PortEntry {
-                            port: pi.port,
-                            protocol: format!("{}", pi.protocol),
-                            address: pi.address.clone(),
-                        })
-                        .collect()
-                })
-                .unwrap_or_default();
-
-            WhyEntry {
-                pid: p.pid,
-                process_name: p.process_name.clone(),
-                ports: port_entries,
-                ancestry: ancestry_map.get(&p.pid).cloned(),
-            }
-        })
+        .flat_map(|p| ports_by_pid.get(&p.pid).into_iter().flatten())
+        .map(|p| (*p).clone())
         .collect();
+    output::write_output(|out| {
+        json::print_ports_why_env(out, &ports, ancestry_map, env_map, docker_status)
+    })
+}
 
-    let json = serde_json::to_string_pretty(&entries).expect("Failed to serialize to JSON");
-    println!("{json}");
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Protocol;
+
+    fn make_port(port: u16, pid: u32, name: &str, command_line: &str) -> PortInfo {
+        PortInfo {
+            port,
+            protocol: Protocol::Tcp,
+            pid,
+            process_name: name.to_string(),
+            address: format!("127.0.0.1:{port}"),
+            command_line: Some(command_line.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn parses_and_formats_windows() {
+        assert_eq!(parse_window("5m").unwrap(), Duration::from_secs(300));
+        assert_eq!(parse_window("90s").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse_window("45").unwrap(), Duration::from_secs(45));
+        assert_eq!(parse_window("2h").unwrap(), Duration::from_secs(7200));
+        assert_eq!(parse_window("1d").unwrap(), Duration::from_secs(86400));
+        assert!(parse_window("5 min").is_err());
+        assert!(parse_window("m").is_err());
+        assert!(parse_window("-5m").is_err());
+        let err = parse_window("999999999999999d").unwrap_err();
+        assert_eq!(err.to_string(), "duration `999999999999999d` is too large");
+
+        assert_eq!(format_window(Duration::from_secs(300)), "5m");
+        assert_eq!(format_window(Duration::from_secs(90)), "90s");
+        assert_eq!(format_window(Duration::from_secs(7200)), "2h");
+    }
+
+    #[test]
+    fn protocol_suffix_picks_one_of_a_shared_port() {
+        let tcp = make_port(53, 10, "named", "named -f");
+        let udp = PortInfo {
+            protocol: Protocol::Udp,
+            pid: 20,
+            ..make_port(53, 20, "dnsmasq", "dnsmasq -k")
+        };
+        let ports = vec![tcp, udp];
+
+        assert_eq!(match_target(&ports, "53").len(), 2);
+        let hits = match_target(&ports, "53/udp");
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].process_name, "dnsmasq");
+        // The PID fallback doesn't apply to a qualified port.
+        assert!(match_target(&ports, "10/tcp").is_empty());
+    }
+
+    #[test]
+    fn tree_merges_shared_ancestors() {
+        let ancestor = |pid, name: &str, ppid| Ancestor {
+            pid,
+            name: name.to_string(),
+            ppid,
+        };
+        // Chains are traced target first.
+        let node = vec![
+            ancestor(40, "node", 30),
+            ancestor(30, "bash", 20),
+            ancestor(20, "sshd", 1),
+            ancestor(1, "systemd", 0),
+        ];
+        let python = vec![
+            ancestor(31, "python", 20),
+            ancestor(20, "sshd", 1),
+            ancestor(1, "systemd", 0),
+        ];
+        let nginx = vec![ancestor(50, "nginx", 1), ancestor(1, "systemd", 0)];
+        let forest = build_tree([node.as_slice(), python.as_slice(), nginx.as_slice()]);
+        let labels = HashMap::from([
+            (40, "3000/tcp".to_string()),
+            (31, "8000/tcp".to_string()),
+            (50, "80/tcp, 443/tcp".to_string()),
+        ]);
+
+        let mut lines = Vec::new();
+        render_tree(&forest, &labels, "", true, &mut lines);
+        assert_eq!(
+            lines,
+            [
+                "systemd(1)",
+                "├─ sshd(20)",
+                "│  ├─ bash(30)",
+                "│  │  └─ node(40)  ← 3000/tcp",
+                "│  └─ python(31)  ← 8000/tcp",
+                "└─ nginx(50)  ← 80/tcp, 443/tcp",
+            ]
+        );
+    }
+
+    #[test]
+    fn cmdline_disambiguates_same_named_processes() {
+        let ports = vec![
+            make_port(8000, 10, "python", "python manage.py runserver"),
+            make_port(8888, 11, "python", "python -m jupyter notebook"),
+        ];
+        let matches = match_by_name(&ports, "manage.py");
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].pid, 10);
+    }
+
+    #[test]
+    fn process_name_match_wins_over_cmdline() {
+        let ports = vec![
+            make_port(3000, 20, "node", "node server.js"),
+            make_port(5000, 21, "python", "python serve_node.py"),
+        ];
+        let matches = match_by_name(&ports, "node");
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].pid, 20);
+    }
+
+    #[test]
+    fn container_id_prefix_matches() {
+        let mut proxy = make_port(8080, 30, "docker-proxy", "docker-proxy -host-port 8080");
+        proxy.container_id = Some("3f2a9c1b7d4e".to_string());
+        let ports = vec![proxy, make_port(3000, 31, "node", "node server.js")];
+        let matches = match_by_name(&ports, "3f2a9c");
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].pid, 30);
+    }
 }
@@ -0,0 +1,325 @@
+//! User config file with default values for global flags.
+//!
+//! The file is read from `$PORTS_CONFIG` when set, otherwise from
+//! `$XDG_CONFIG_HOME/ports/config.toml` (falling back to
+//! `~/.config/ports/config.toml`). A missing default file is not an error;
+//! a missing `$PORTS_CONFIG` file is.
+//!
+//! Precedence, highest first: command-line flags, the config file, built-in
+//! defaults. Boolean flags can only be switched on from the command line, so
+//! `json = true` or `connections = true` in the file cannot be undone by a
+//! flag — point `PORTS_CONFIG` at an empty file to bypass it for one run.
+//!
+//! Unknown keys are reported on stderr and otherwise ignored, so a config
+//! written for a newer release keeps working with an older binary.
+//!
+//! A few environment variables (`PORTS_DEFAULT_PROTOCOL`,
+//! `PORTS_DEFAULT_SORT`, `PORTS_CONNECTIONS`) set the same defaults for CI
+//! images where exporting a variable is easier than writing a file. They
+//! rank between the command line and the config file; invalid values are
+//! warned about and ignored.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use colored::Colorize;
+use serde::Deserialize;
+
+use crate::cli::{Cli, ColorChoice, ProtocolFilter, SortField};
+
+const KNOWN_KEYS: &[&str] = &[
+    "json",
+    "connections",
+    "sort",
+    "protocol",
+    "color",
+    "db_path",
+];
+
+/// Defaults parsed from the config file. `None` means "not set".
+#[derive(Default)]
+pub struct Config {
+    pub json: Option<bool>,
+    pub connections: Option<bool>,
+    pub sort: Option<SortField>,
+    pub protocol: Option<ProtocolFilter>,
+    pub color: Option<ColorChoice>,
+    pub db_path: Option<PathBuf>,
+}
+
+/// On-disk shape. Enum-valued keys stay strings here so they can be parsed
+/// with clap's value names and match the command-line spelling exactly.
+#[derive(Deserialize)]
+struct RawConfig {
+    json: Option<bool>,
+    connections: Option<bool>,
+    sort: Option<String>,
+    protocol: Option<String>,
+    color: Option<String>,
+    db_path: Option<PathBuf>,
+}
+
+impl Config {
+    /// Parse config text. `source` is only used in messages.
+    fn parse(text: &str, source: &Path) -> Result<Self> {
+        let table: toml::Table = text
+            .parse()
+            .with_context(|| format!("parsing {}", source.display()))?;
+
+        for key in table.keys().filter(|k| !KNOWN_KEYS.contains(&k.as_str())) {
+            eprintln!(
+                "{} unknown key `{key}` in {} (ignored)",
+                "Warning:".yellow().bold(),
+                source.display()
+            );
+        }
+
+        let raw: RawConfig = toml::Value::Table(table)
+            .try_into()
+            .with_context(|| format!("parsing {}", source.display()))?;
+
+        Ok(Self {
+            json: raw.json,
+            connections: raw.connections,
+            sort: parse_value(raw.sort, "sort", source)?,
+            protocol: parse_value(raw.protocol, "protocol", source)?,
+            color: parse_value(raw.color, "color", source)?,
+            db_path: raw.db_path,
+        })
+    }
+
+    /// Fill in any flag the command line left unset.
+    pub fn apply(self, cli: &mut Cli) {
+        cli.json |= self.json.unwrap_or(false);
+        cli.connections |= self.connections.unwrap_or(false);
+        cli.sort = cli.sort.or(self.sort);
+        cli.protocol = cli.protocol.or(self.protocol);
+        cli.color = cli.color.or(self.color);
+        if cli.db_path.is_none() {
+            cli.db_path = self.db_path;
+        }
+    }
+}
+
+fn parse_value<T: ValueEnum>(value: Option<String>, key: &str, source: &Path) -> Result<Option<T>> {
+    let Some(value) = value else {
+        return Ok(None);
+    };
+    T::from_str(&value, true).map(Some).map_err(|_| {
+        anyhow::anyhow!(
+            "invalid value `{value}` for `{key}` in {} (expected one of: {})",
+            source.display(),
+            allowed_values::<T>()
+        )
+    })
+}
+
+fn allowed_values<T: ValueEnum>() -> String {
+    T::value_variants()
+        .iter()
+        .filter_map(|v| v.to_possible_value())
+        .map(|v| v.get_name().to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Defaults from the `PORTS_*` environment variables. Apply before the
+/// config file so the environment wins over it.
+pub fn from_env() -> Config {
+    env_defaults(|key| env::var(key).ok())
+}
+
+fn env_defaults(var: impl Fn(&str) -> Option<String>) -> Config {
+    let connections =
+        var("PORTS_CONNECTIONS").and_then(|value| match value.to_ascii_lowercase().as_str() {
+            "1" | "true" | "yes" => Some(true),
+            "" | "0" | "false" | "no" => None,
+            _ => {
+                warn_env("PORTS_CONNECTIONS", &value, "1, 0, true, false");
+                None
+            }
+        });
+    Config {
+        connections,
+        sort: env_value(&var, "PORTS_DEFAULT_SORT"),
+        protocol: env_value(&var, "PORTS_DEFAULT_PROTOCOL"),
+        ..Config::default()
+    }
+}
+
+fn env_value<T: ValueEnum>(var: &impl Fn(&str) -> Option<String>, key: &str) -> Option<T> {
+    let value = var(key).filter(|v| !v.is_empty())?;
+    let parsed = T::from_str(&value, true).ok();
+    if parsed.is_none() {
+        warn_env(key, &value, &allowed_values::<T>());
+    }
+    parsed
+}
+
+fn warn_env(key: &str, value: &str, allowed: &str) {
+    eprintln!(
+        "{} ignoring {key}=`{value}` (expected one of: {allowed})",
+        "Warning:".yellow().bold()
+    );
+}
+
+fn default_path_under(home: &Path, xdg_config: Option<&Path>) -> PathBuf {
+    xdg_config
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| home.join(".config"))
+        .join("ports/config.toml")
+}
+
+/// Load the config file, if any. Returns the default (empty) config when
+/// no file exists at the default location.
+pub fn load() -> Result<Config> {
+    if let Some(path) = env::var_os("PORTS_CONFIG").filter(|p| !p.is_empty()) {
+        let path = PathBuf::from(path);
+        let text =
+            fs::read_to_string(&path).with_context(|| format!("reading {}", path.display()))?;
+        return Config::parse(&text, &path);
+    }
+
+    let Some(home) = dirs::home_dir() else {
+        return Ok(Config::default());
+    };
+    let xdg_config = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute());
+    let path = default_path_under(&home, xdg_config.as_deref());
+    match fs::read_to_string(&path) {
+        Ok(text) => Config::parse(&text, &path),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Config::default()),
+        Err(e) => Err(e).with_context(|| format!("reading {}", path.display())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    fn parse(text: &str) -> Result<Config> {
+        Config::parse(text, Path::new("config.toml"))
+    }
+
+    #[test]
+    fn parses_all_known_keys() {
+        let config = parse(
+            r#"
+            json = true
+            connections = true
+            sort = "pid"
+            protocol = "udp"
+            color = "never"
+            db_path = "/tmp/history.db"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.json, Some(true));
+        assert_eq!(config.connections, Some(true));
+        assert!(matches!(config.sort, Some(SortField::Pid)));
+        assert!(config.protocol == Some(ProtocolFilter::Udp));
+        assert_eq!(config.color, Some(ColorChoice::Never));
+        assert_eq!(config.db_path, Some(PathBuf::from("/tmp/history.db")));
+    }
+
+    #[test]
+    fn unknown_keys_are_ignored() {
+        let config = parse("colour = \"never\"\nsort = \"name\"\n").unwrap();
+        assert!(matches!(config.sort, Some(SortField::Name)));
+        assert_eq!(config.color, None);
+    }
+
+    #[test]
+    fn invalid_enum_value_is_an_error() {
+        let Err(err) = parse("sort = \"size\"") else {
+            panic!("expected an error");
+        };
+        let err = err.to_string();
+        assert!(err.contains("`sort`"), "{err}");
+        assert!(
+            err.contains("port, pid, name, protocol, address, container, none"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn wrong_type_is_an_error() {
+        assert!(parse("json = \"yes\"").is_err());
+    }
+
+    #[test]
+    fn cli_flags_take_precedence() {
+        let config = parse("sort = \"pid\"\nprotocol = \"udp\"\nconnections = true").unwrap();
+        let mut cli = Cli::parse_from(["ports", "--sort", "name"]);
+        config.apply(&mut cli);
+        assert!(matches!(cli.sort, Some(SortField::Name)));
+        assert!(cli.protocol == Some(ProtocolFilter::Udp));
+        assert!(cli.connections);
+
+        let config = parse("protocol = \"udp\"").unwrap();
+        let mut cli = Cli::parse_from(["ports", "--protocol", "both"]);
+        config.apply(&mut cli);
+        assert!(cli.protocol == Some(ProtocolFilter::Both));
+    }
+
+    #[test]
+    fn env_defaults_parse_known_values() {
+        let config = env_defaults(|key| match key {
+            "PORTS_DEFAULT_PROTOCOL" => Some("TCP".to_string()),
+            "PORTS_DEFAULT_SORT" => Some("pid".to_string()),
+            "PORTS_CONNECTIONS" => Some("1".to_string()),
+            _ => None,
+        });
+        assert!(config.protocol == Some(ProtocolFilter::Tcp));
+        assert!(matches!(config.sort, Some(SortField::Pid)));
+        assert_eq!(config.connections, Some(true));
+    }
+
+    #[test]
+    fn env_defaults_ignore_invalid_values() {
+        let config = env_defaults(|key| match key {
+            "PORTS_DEFAULT_PROTOCOL" => Some("sctp".to_string()),
+            "PORTS_DEFAULT_SORT" => Some(String::new()),
+            "PORTS_CONNECTIONS" => Some("maybe".to_string()),
+            _ => None,
+        });
+        assert!(config.protocol.is_none());
+        assert!(config.sort.is_none());
+        assert_eq!(config.connections, None);
+    }
+
+    #[test]
+    fn cli_flags_beat_env_and_env_beats_file() {
+        let env = env_defaults(|key| (key == "PORTS_DEFAULT_SORT").then(|| "name".to_string()));
+        let file = parse("sort = \"pid\"\nprotocol = \"udp\"").unwrap();
+
+        let mut cli = Cli::parse_from(["ports"]);
+        env.apply(&mut cli);
+        file.apply(&mut cli);
+        assert!(matches!(cli.sort, Some(SortField::Name)));
+        assert!(cli.protocol == Some(ProtocolFilter::Udp));
+
+        let env = env_defaults(|key| (key == "PORTS_DEFAULT_SORT").then(|| "name".to_string()));
+        let mut cli = Cli::parse_from(["ports", "--sort", "port"]);
+        env.apply(&mut cli);
+        assert!(matches!(cli.sort, Some(SortField::Port)));
+    }
+
+    #[test]
+    fn default_path_respects_xdg_config_home() {
+        let home = Path::new("/home/u");
+        assert_eq!(
+            default_path_under(home, None),
+            PathBuf::from("/home/u/.config/ports/config.toml")
+        );
+        assert_eq!(
+            default_path_under(home, Some(Path::new("/xdg"))),
+            PathBuf::from("/xdg/ports/config.toml")
+        );
+    }
+}
@@ -0,0 +1,234 @@
+//! `--resolve-names`: reverse DNS for the remote end of connections.
+//!
+//! One process-wide resolver serves the live tables and `history record`.
+//! Answers, failures included, are cached for [`TTL`]. Each lookup runs on
+//! its own thread, so a slow or dead DNS server never holds up a listing:
+//! [`resolve_remote_hosts`] waits at most [`TIMEOUT`] for the whole batch,
+//! and a lookup still running after that lands in the cache for the next
+//! refresh. At most [`MAX_IN_FLIGHT`] lookups run at once; addresses past
+//! the cap are tried again on a later call.
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Condvar, Mutex, MutexGuard, OnceLock, PoisonError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::types::{parse_bind_ip, PortInfo};
+
+/// How long an answer is trusted before it's looked up again.
+const TTL: Duration = Duration::from_secs(300);
+/// How long a listing waits for its lookups.
+const TIMEOUT: Duration = Duration::from_millis(250);
+const MAX_IN_FLIGHT: usize = 8;
+
+static ENABLED: OnceLock<bool> = OnceLock::new();
+
+/// Look up host names for connection peers (`--resolve-names`). Call once,
+/// before the first enumeration.
+pub fn set_enabled(on: bool) {
+    let _ = ENABLED.set(on);
+}
+
+/// Defaults to off: lookups leave the machine.
+pub(crate) fn enabled() -> bool {
+    ENABLED.get().copied().unwrap_or(false)
+}
+
+#[derive(Default)]
+struct Entry {
+    host: Option<String>,
+    /// `None` until the first lookup finishes.
+    resolved_at: Option<Instant>,
+    pending: bool,
+}
+
+#[derive(Default)]
+struct State {
+    entries: HashMap<IpAddr, Entry>,
+    in_flight: usize,
+}
+
+struct Resolver {
+    state: Mutex<State>,
+    /// Signalled whenever a lookup finishes.
+    done: Condvar,
+    lookup: fn(IpAddr) -> Option<String>,
+    ttl: Duration,
+    max_in_flight: usize,
+}
+
+impl Resolver {
+    fn new(lookup: fn(IpAddr) -> Option<String>, ttl: Duration, max_in_flight: usize) -> Self {
+        Self {
+            state: Mutex::default(),
+            done: Condvar::new(),
+            lookup,
+            ttl,
+            max_in_flight,
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// The cached name of `ip`, never waiting. A missing or expired answer
+    /// starts a lookup (room permitting); an expired one is still returned
+    /// until the new one arrives.
+    fn get(&'static self, ip: IpAddr) -> Option<String> {
+        let mut state = self.lock();
+        let entry = state.entries.get(&ip);
+        let host = entry.and_then(|e| e.host.clone());
+        let fresh = entry
+            .is_some_and(|e| e.pending || e.resolved_at.is_some_and(|at| at.elapsed() < self.ttl));
+        if fresh || state.in_flight >= self.max_in_flight {
+            return host;
+        }
+
+        state.in_flight += 1;
+        state.entries.entry(ip).or_default().pending = true;
+        drop(state);
+        let spawned = thread::Builder::new()
+            .name("dns".to_string())
+            .spawn(move || {
+                let host = (self.lookup)(ip);
+                let mut state = self.lock();
+                state.in_flight -= 1;
+                state.entries.insert(
+                    ip,
+                    Entry {
+                        host,
+                        resolved_at: Some(Instant::now()),
+                        pending: false,
+                    },
+                );
+                self.done.notify_all();
+            });
+        if spawned.is_err() {
+            let mut state = self.lock();
+            state.in_flight -= 1;
+            if let Some(entry) = state.entries.get_mut(&ip) {
+                entry.pending = false;
+            }
+        }
+        host
+    }
+
+    /// Wait until none of `ips` has a lookup running, or until `deadline`.
+    fn wait(&self, ips: &[IpAddr], deadline: Instant) {
+        let mut state = self.lock();
+        while ips
+            .iter()
+            .any(|ip| state.entries.get(ip).is_some_and(|e| e.pending))
+        {
+            let Some(left) = deadline.checked_duration_since(Instant::now()) else {
+                return;
+            };
+            state = self
+                .done
+                .wait_timeout(state, left)
+                .unwrap_or_else(PoisonError::into_inner)
+                .0;
+        }
+    }
+}
+
+fn resolver() -> &'static Resolver {
+    static RESOLVER: OnceLock<Resolver> = OnceLock::new();
+    RESOLVER.get_or_init(|| Resolver::new(lookup_addr, TTL, MAX_IN_FLIGHT))
+}
+
+/// The PTR name of `ip`. Some resolvers answer with the address itself
+/// when there is no name; that counts as none.
+fn lookup_addr(ip: IpAddr) -> Option<String> {
+    dns_lookup::lookup_addr(&ip)
+        .ok()
+        .filter(|host| host.parse::<IpAddr>().is_err())
+}
+
+/// The host name of `ip` if one is cached, starting a lookup in the
+/// background otherwise. Never waits.
+pub fn resolve_cached(ip: IpAddr) -> Option<String> {
+    resolver().get(ip)
+}
+
+/// Fill in [`PortInfo::remote_host`] on every connection. All lookups
+/// start together and share one [`TIMEOUT`].
+pub fn resolve_remote_hosts(ports: &mut [PortInfo]) {
+    let ips: Vec<Option<IpAddr>> = ports
+        .iter()
+        .map(|p| p.remote_address.as_deref().and_then(parse_bind_ip))
+        .collect();
+    let mut started: Vec<IpAddr> = ips.iter().flatten().copied().collect();
+    started.sort_unstable();
+    started.dedup();
+    for &ip in &started {
+        resolve_cached(ip);
+    }
+    resolver().wait(&started, Instant::now() + TIMEOUT);
+
+    for (port, ip) in ports.iter_mut().zip(ips) {
+        port.remote_host = ip.and_then(resolve_cached);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    fn leak(resolver: Resolver) -> &'static Resolver {
+        Box::leak(Box::new(resolver))
+    }
+
+    #[test]
+    fn answers_are_cached_until_they_expire() {
+        static LOOKUPS: AtomicUsize = AtomicUsize::new(0);
+        fn lookup(ip: IpAddr) -> Option<String> {
+            let n = LOOKUPS.fetch_add(1, Ordering::SeqCst);
+            Some(format!("host{n}.{ip}.example"))
+        }
+        let ip = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+        let deadline = || Instant::now() + Duration::from_secs(5);
+
+        let cached = leak(Resolver::new(lookup, Duration::from_secs(60), 4));
+        assert_eq!(cached.get(ip), None);
+        cached.wait(&[ip], deadline());
+        assert_eq!(cached.get(ip).as_deref(), Some("host0.10.0.0.1.example"));
+        assert_eq!(cached.get(ip).as_deref(), Some("host0.10.0.0.1.example"));
+        assert_eq!(LOOKUPS.load(Ordering::SeqCst), 1);
+
+        // Expired: the old name is served while the new one is looked up.
+        let expired = leak(Resolver::new(lookup, Duration::ZERO, 4));
+        expired.get(ip);
+        expired.wait(&[ip], deadline());
+        assert_eq!(expired.get(ip).as_deref(), Some("host1.10.0.0.1.example"));
+        expired.wait(&[ip], deadline());
+        assert_eq!(expired.get(ip).as_deref(), Some("host2.10.0.0.1.example"));
+    }
+
+    #[test]
+    fn slow_lookups_are_capped_and_not_waited_for() {
+        fn lookup(_: IpAddr) -> Option<String> {
+            thread::sleep(Duration::from_secs(2));
+            Some("slow.example".to_string())
+        }
+        let resolver = leak(Resolver::new(lookup, TTL, 1));
+        let a = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+        let b = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2));
+
+        let start = Instant::now();
+        assert_eq!(resolver.get(a), None);
+        assert_eq!(resolver.get(b), None);
+        resolver.wait(&[a, b], start + Duration::from_millis(50));
+        assert!(start.elapsed() < Duration::from_secs(1));
+
+        let state = resolver.lock();
+        assert_eq!(state.in_flight, 1);
+        assert!(state.entries[&a].pending);
+        assert!(!state.entries.contains_key(&b));
+    }
+}
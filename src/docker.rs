@@ -4,13 +4,19 @@
 //! cache to avoid repeated subprocess overhead in watch/top mode. Failed
 //! fetches are cached for 500ms (much shorter) so daemon recovery surfaces
 //! on the next tick rather than after the full 3-second success window.
+//!
+//! The query itself is bounded by a 500ms timeout: on boot or under load
+//! the daemon can accept the connection and then not answer, and a wedged
+//! Docker must never freeze `ports`. A query that fails outright (daemon
+//! still starting) gets one quick retry.
 
 use std::collections::HashMap;
 use std::fmt;
+use std::path::Path;
 use std::sync::{LazyLock, Mutex};
 use std::time::{Duration, Instant};
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use regex::Regex;
 
 use crate::types::DockerStatus;
@@ -19,7 +25,11 @@ use crate::types::DockerStatus;
 #[derive(Debug, Clone)]
 pub struct ContainerInfo {
     pub name: String,
+    /// Short (12-character) container ID.
+    pub id: Option<String>,
     pub image: Option<String>,
+    /// Port inside the container, set per published mapping.
+    pub container_port: Option<u16>,
 }
 
 type PortCache = Option<(Instant, HashMap<u16, ContainerInfo>, DockerStatus)>;
@@ -29,6 +39,12 @@ pub(crate) static DOCKER_CACHE: LazyLock<Mutex<PortCache>> = LazyLock::new(|| Mu
 
 const CACHE_TTL: Duration = Duration::from_secs(3);
 const FAILURE_CACHE_TTL: Duration = Duration::from_millis(500);
+/// Where bollard connects when `DOCKER_HOST` is unset.
+const DEFAULT_SOCKET: &str = "/var/run/docker.sock";
+/// How long one container listing may take before we give up on it.
+const QUERY_TIMEOUT: Duration = Duration::from_millis(500);
+/// Pause before retrying a listing that failed without timing out.
+const RETRY_DELAY: Duration = Duration::from_millis(100);
 
 /// Get a mapping of host ports to container information, plus the
 /// reachability status of the Docker daemon.
@@ -58,6 +74,21 @@ pub fn get_port_mappings() -> (HashMap<u16, ContainerInfo>, DockerStatus) {
     (fresh, status)
 }
 
+/// Drop the cached mappings so the next `get_port_mappings` call queries
+/// the daemon again, regardless of TTL. Only `ports top`'s refresh key
+/// needs this.
+#[cfg(any(feature = "tui", test))]
+pub fn clear_cache() {
+    *DOCKER_CACHE.lock().unwrap() = None;
+}
+
+/// Whether a daemon is configured where a local connection would look:
+/// `DOCKER_HOST`, or the default socket. A cheap check that spares hosts
+/// without Docker a connection attempt.
+pub fn daemon_configured() -> bool {
+    std::env::var_os("DOCKER_HOST").is_some() || Path::new(DEFAULT_SOCKET).exists()
+}
+
 /// Look up the Docker image name for a container on a given port.
 ///
 /// Reads from the cached port mappings (does not trigger a refresh).
@@ -90,12 +121,37 @@ fn fetch_with_status() -> (HashMap<u16, ContainerInfo>, DockerStatus) {
         Err(status) => return (HashMap::new(), status),
     };
 
-    match rt.block_on(fetch_from_bollard(&docker)) {
+    let result = match rt.block_on(fetch_with_retry(&docker)) {
         Ok(map) => (map, DockerStatus::Ok),
         Err(e) => (HashMap::new(), unreachable_from("docker query", &e)),
+    };
+    // A timed-out request can leave connection tasks behind; don't wait
+    // for them on the way out.
+    rt.shutdown_background();
+    result
+}
+
+/// [`fetch_from_bollard`] bounded by [`QUERY_TIMEOUT`], retried once after
+/// [`RETRY_DELAY`] if it failed quickly (e.g. connection refused while the
+/// daemon starts). A timeout isn't retried: a daemon that didn't answer in
+/// time won't answer a moment later either, and retrying would double the
+/// stall.
+async fn fetch_with_retry(docker: &bollard::Docker) -> Result<HashMap<u16, ContainerInfo>> {
+    match fetch_with_timeout(docker).await {
+        Err(e) if !e.is::<tokio::time::error::Elapsed>() => {
+            tokio::time::sleep(RETRY_DELAY).await;
+            fetch_with_timeout(docker).await
+        }
+        result => result,
     }
 }
 
+async fn fetch_with_timeout(docker: &bollard::Docker) -> Result<HashMap<u16, ContainerInfo>> {
+    tokio::time::timeout(QUERY_TIMEOUT, fetch_from_bollard(docker))
+        .await
+        .with_context(|| format!("no answer within {}ms", QUERY_TIMEOUT.as_millis()))?
+}
+
 /// Build an `Unreachable` status with a labelled reason. All three
 /// failure sites route through this so the `prefix: details` shape is
 /// enforced by construction rather than relying on convention.
@@ -139,8 +195,14 @@ async fn fetch_from_bollard(docker: &bollard::Docker) -> Result<HashMap<u16, Con
             .unwrap_or_default();
 
         let image = container.image.clone();
+        let id = container.id.as_deref().map(short_id);
 
-        let info = ContainerInfo { name, image };
+        let info = ContainerInfo {
+            name,
+            id,
+            image,
+            container_port: None,
+        };
 
         if let Some(ports) = container.ports {
             for p in &ports {
@@ -148,7 +210,11 @@ async fn fetch_from_bollard(docker: &bollard::Docker) -> Result<HashMap<u16, Con
                     continue;
                 }
                 if let Some(host_port) = p.public_port {
-                    mappings.insert(host_port, info.clone());
+                    let info = ContainerInfo {
+                        container_port: Some(p.private_port),
+                        ..info.clone()
+                    };
+                    mappings.insert(host_port, info);
                 }
             }
         }
@@ -157,6 +223,11 @@ async fn fetch_from_bollard(docker: &bollard::Docker) -> Result<HashMap<u16, Con
     Ok(mappings)
 }
 
+/// The 12-character form `docker ps` prints.
+fn short_id(id: &str) -> String {
+    id.chars().take(12).collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -174,6 +245,8 @@ mod tests {
             8080,
             ContainerInfo {
                 name: "web".to_string(),
+                id: None,
+                container_port: None,
                 image: Some("postgres:16".to_string()),
             },
         );
@@ -187,6 +260,8 @@ mod tests {
             3001,
             ContainerInfo {
                 name: "web".to_string(),
+                id: None,
+                container_port: None,
                 image: Some("nginx:1".to_string()),
             },
         );
@@ -209,8 +284,34 @@ mod tests {
         assert!(returned.is_empty());
         assert_eq!(status, DockerStatus::Unreachable { reason });
 
-        // Clean up.
-        *DOCKER_CACHE.lock().unwrap() = None;
+        // Sub-case 4: clear_cache forces the next call to refetch.
+        clear_cache();
+        assert!(DOCKER_CACHE.lock().unwrap().is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn wedged_daemon_times_out_without_retry() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("docker.sock");
+        // Connections complete via the backlog, but nothing ever answers.
+        let _listener = std::os::unix::net::UnixListener::bind(&path).unwrap();
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let docker = rt
+            .block_on(async {
+                bollard::Docker::connect_with_unix(
+                    path.to_str().unwrap(),
+                    120,
+                    bollard::API_DEFAULT_VERSION,
+                )
+            })
+            .unwrap();
+
+        let start = Instant::now();
+        let err = rt.block_on(fetch_with_retry(&docker)).unwrap_err();
+        assert!(start.elapsed() < QUERY_TIMEOUT + RETRY_DELAY);
+        assert_eq!(err.to_string(), "no answer within 500ms");
+        rt.shutdown_background();
     }
 
     #[test]
@@ -0,0 +1,86 @@
+//! Typed errors for the library enumeration API.
+//!
+//! [`snapshot`](crate::snapshot), [`connections`](crate::connections) and
+//! the platform enumerators behind them return [`PortsError`] so library
+//! callers can react to the kind of failure — retry under `sudo`, fall
+//! back to another tool — instead of matching on message text. The CLI
+//! keeps using `anyhow` and converts with `?`.
+
+use std::io;
+use std::path::PathBuf;
+
+/// Why enumerating sockets failed.
+///
+/// New variants may be added in minor releases.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum PortsError {
+    /// The OS refused to let us read a socket table.
+    #[error("permission denied reading {} (try again with sudo)", path.display())]
+    PermissionDenied { path: PathBuf },
+
+    /// Enumeration isn't available on this platform, or this build left
+    /// out the feature it needs.
+    #[error("{0}")]
+    UnsupportedPlatform(String),
+
+    /// A socket table (`/proc/net/*`, or a helper tool's output) was
+    /// readable but not in a format we understand.
+    #[error("could not parse {}: {detail}", path.display())]
+    ProcParse { path: PathBuf, detail: String },
+
+    /// Any other I/O failure, e.g. a helper tool that failed to start.
+    #[error("{context}")]
+    Io {
+        context: String,
+        #[source]
+        source: io::Error,
+    },
+}
+
+impl PortsError {
+    /// An [`Io`](Self::Io) error, except that permission failures become
+    /// [`PermissionDenied`](Self::PermissionDenied) for `path`.
+    pub(crate) fn from_io(source: io::Error, path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        if source.kind() == io::ErrorKind::PermissionDenied {
+            return Self::PermissionDenied { path };
+        }
+        Self::Io {
+            context: format!("Failed to read {}", path.display()),
+            source,
+        }
+    }
+
+    /// Only the `lsof`, `netstat` and `listeners` backends need this.
+    #[cfg(any(not(target_os = "linux"), test))]
+    pub(crate) fn io(context: impl Into<String>, source: io::Error) -> Self {
+        Self::Io {
+            context: context.into(),
+            source,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn permission_errors_get_their_own_variant() {
+        let denied = io::Error::from(io::ErrorKind::PermissionDenied);
+        let err = PortsError::from_io(denied, "/proc/net/tcp");
+        assert!(matches!(err, PortsError::PermissionDenied { .. }));
+        assert_eq!(
+            err.to_string(),
+            "permission denied reading /proc/net/tcp (try again with sudo)"
+        );
+
+        let other = io::Error::other("disk on fire");
+        let err = PortsError::from_io(other, "/proc/net/udp");
+        assert!(matches!(err, PortsError::Io { .. }));
+        assert_eq!(err.to_string(), "Failed to read /proc/net/udp");
+        let source = std::error::Error::source(&err).unwrap();
+        assert_eq!(source.to_string(), "disk on fire");
+    }
+}
@@ -233,12 +233,7 @@ mod tests {
             pid: 1234,
             process_name: process_name.to_string(),
             address: "127.0.0.1:8080".to_string(),
-            remote_address: None,
-            container: None,
-            service_name: None,
-            command_line: None,
-            cwd: None,
-            framework: None,
+            ..Default::default()
         }
     }
 
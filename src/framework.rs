@@ -281,7 +281,9 @@ const PROCESS_NAME_MAP: &[(&str, &str)] = &[
 ];
 
 fn detect_process_name(info: &PortInfo) -> Option<String> {
+    // Interpreters are named after their script on Linux: `python:manage.py`.
     let name = info.process_name.to_lowercase();
+    let name = name.split(':').next().unwrap_or_default();
     for &(proc_name, fw) in PROCESS_NAME_MAP {
         if name == proc_name {
             return Some(fw.to_string());
@@ -306,12 +308,7 @@ mod tests {
             pid: 1000,
             process_name: "node".to_string(),
             address: "127.0.0.1:3000".to_string(),
-            remote_address: None,
-            container: None,
-            service_name: None,
-            command_line: None,
-            cwd: None,
-            framework: None,
+            ..Default::default()
         }
     }
 
@@ -334,7 +331,9 @@ mod tests {
             3000,
             docker::ContainerInfo {
                 name: "my-pg".into(),
+                id: None,
                 image: Some("postgres:16-alpine".into()),
+                container_port: None,
             },
         );
         *docker::DOCKER_CACHE.lock().unwrap() = Some((
@@ -351,7 +350,9 @@ mod tests {
             3000,
             docker::ContainerInfo {
                 name: "custom-app".into(),
+                id: None,
                 image: Some("my-org/custom-thing:latest".into()),
+                container_port: None,
             },
         );
         *docker::DOCKER_CACHE.lock().unwrap() = Some((
@@ -2,18 +2,37 @@
 //!
 //! Stores snapshots of port activity in a SQLite database for historical analysis.
 
+use std::collections::HashMap;
 use std::path::PathBuf;
+use std::sync::OnceLock;
+use std::time::UNIX_EPOCH;
 
 use anyhow::{Context, Result};
-use chrono::{DateTime, Duration, Utc};
+use chrono::{DateTime, Duration, SecondsFormat, SubsecRound, Utc};
 use rusqlite::{params, Connection};
 
 use crate::platform;
+use crate::types::PortInfo;
 
 const DB_NAME: &str = "ports_history.db";
 
+static DB_PATH_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+
+/// Use `path` instead of the default database location (`--db-path` or
+/// `db_path` in the config file). Only the first call takes effect.
+pub fn set_db_path(path: PathBuf) {
+    let _ = DB_PATH_OVERRIDE.set(path);
+}
+
 /// Get the path to the history database
 fn db_path() -> Result<PathBuf> {
+    if let Some(path) = DB_PATH_OVERRIDE.get() {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        return Ok(path.clone());
+    }
+
     let data_dir = dirs::data_local_dir()
         .or_else(dirs::home_dir)
         .context("Could not determine data directory")?;
@@ -58,7 +77,49 @@ fn init_db(conn: &Connection) -> Result<()> {
         )?;
         conn.execute_batch("PRAGMA user_version = 1;")?;
     }
-    // Future: if version < 2 { ALTER TABLE ... }
+    if version < 2 {
+        // One row per listener currently believed to be up; `since` is
+        // reset whenever the owning process changes.
+        conn.execute_batch(
+            "
+            CREATE TABLE IF NOT EXISTS first_seen (
+                port INTEGER NOT NULL,
+                protocol TEXT NOT NULL,
+                process_name TEXT NOT NULL,
+                pid INTEGER NOT NULL,
+                since INTEGER NOT NULL,
+                PRIMARY KEY (port, protocol, process_name)
+            );
+            PRAGMA user_version = 2;
+            ",
+        )?;
+    }
+    if version < 3 {
+        // Socket owner. Rows recorded before this stay NULL. One
+        // transaction, so a failed upgrade can simply be retried.
+        conn.execute_batch(
+            "
+            BEGIN;
+            ALTER TABLE ports ADD COLUMN uid INTEGER;
+            ALTER TABLE ports ADD COLUMN user TEXT;
+            PRAGMA user_version = 3;
+            COMMIT;
+            ",
+        )?;
+    }
+    if version < 4 {
+        // Peer host name from `--resolve-names`; NULL when it was off or
+        // the lookup didn't answer in time.
+        conn.execute_batch(
+            "
+            BEGIN;
+            ALTER TABLE ports ADD COLUMN remote_host TEXT;
+            PRAGMA user_version = 4;
+            COMMIT;
+            ",
+        )?;
+    }
+    // Future: if version < 5 { ALTER TABLE ... }
     Ok(())
 }
 
@@ -72,9 +133,14 @@ pub fn open_db() -> Result<Connection> {
 }
 
 /// Record the current port state as a snapshot
+///
+/// `timestamp` keeps milliseconds so snapshots taken within one second
+/// still read apart; `unix_ts` stays in whole seconds for the time-window
+/// filters. The snapshot `id` is what orders snapshots, since the wall
+/// clock can repeat or step back.
 pub fn record_snapshot(include_connections: bool) -> Result<RecordResult> {
     let conn = open_db()?;
-    let now = Utc::now();
+    let now = Utc::now().trunc_subsecs(3);
 
     // Get current ports. History records all observations and does not
     // surface docker_status — per spec, the user's history view is about
@@ -86,23 +152,23 @@ pub fn record_snapshot(include_connections: bool) -> Result<RecordResult> {
     // Insert snapshot
     conn.execute(
         "INSERT INTO snapshots (timestamp, unix_ts) VALUES (?1, ?2)",
-        params![now.to_rfc3339(), now.timestamp()],
+        params![
+            now.to_rfc3339_opts(SecondsFormat::Millis, true),
+            now.timestamp()
+        ],
     )?;
     let snapshot_id = conn.last_insert_rowid();
 
     // Insert ports
     let mut stmt = conn.prepare(
-        "INSERT INTO ports (snapshot_id, port, protocol, address, pid, process_name, container, state, remote_addr)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)"
+        "INSERT INTO ports (snapshot_id, port, protocol, address, pid, process_name, container, state, remote_addr, remote_host, uid, user)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)"
     )?;
 
     for port in &all_ports {
-        // Determine state based on whether this is a listening port or connection
-        let state: Option<&str> = if port.remote_address.is_some() {
-            Some("ESTABLISHED")
-        } else {
-            Some("LISTEN")
-        };
+        // The socket's own state (LISTEN, ESTABLISHED, TIME_WAIT, ...).
+        // UDP has none, so its rows store NULL.
+        let state = port.state.map(|s| s.to_string());
 
         stmt.execute(params![
             snapshot_id,
@@ -114,8 +180,12 @@ pub fn record_snapshot(include_connections: bool) -> Result<RecordResult> {
             port.container,
             state,
             port.remote_address,
+            port.remote_host,
+            port.uid,
+            port.user,
         ])?;
     }
+    track_first_seen(&conn, &all_ports, None, now.timestamp())?;
 
     Ok(RecordResult {
         snapshot_id,
@@ -124,6 +194,97 @@ pub fn record_snapshot(include_connections: bool) -> Result<RecordResult> {
     })
 }
 
+/// `(port, protocol, process_name)`: what identifies a listener for
+/// first-seen tracking.
+type ListenerKey = (u16, String, String);
+
+fn listener_key(port: &PortInfo) -> ListenerKey {
+    (
+        port.port,
+        port.protocol.to_string(),
+        port.process_name.clone(),
+    )
+}
+
+/// Update the first-seen map from a listing and return when each listener
+/// in it was first seen (Unix time). Listeners missing from `ports` are
+/// dropped, so one that goes away and comes back starts over; so does one
+/// whose PID changed. A listing narrowed to `only` some ports says nothing
+/// about the others, which are left as they were. Connections in `ports`
+/// are ignored.
+fn track_first_seen(
+    conn: &Connection,
+    ports: &[PortInfo],
+    only: Option<&[u16]>,
+    now: i64,
+) -> Result<HashMap<ListenerKey, i64>> {
+    let mut current: HashMap<ListenerKey, Vec<u32>> = HashMap::new();
+    for port in ports.iter().filter(|p| p.remote_address.is_none()) {
+        current
+            .entry(listener_key(port))
+            .or_default()
+            .push(port.pid);
+    }
+
+    let tx = conn.unchecked_transaction()?;
+    let previous: HashMap<ListenerKey, (u32, i64)> = tx
+        .prepare("SELECT port, protocol, process_name, pid, since FROM first_seen")?
+        .query_map([], |row| {
+            Ok((
+                (row.get::<_, i32>(0)? as u16, row.get(1)?, row.get(2)?),
+                (row.get::<_, i64>(3)? as u32, row.get(4)?),
+            ))
+        })?
+        .collect::<Result<_, _>>()?;
+
+    match only {
+        Some(only) => {
+            let mut delete = tx.prepare("DELETE FROM first_seen WHERE port = ?1")?;
+            for &port in only {
+                delete.execute(params![port as i32])?;
+            }
+        }
+        None => {
+            tx.execute("DELETE FROM first_seen", [])?;
+        }
+    }
+    let mut since_by_key = HashMap::new();
+    {
+        let mut insert = tx.prepare(
+            "INSERT INTO first_seen (port, protocol, process_name, pid, since)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+        )?;
+        for (key, pids) in current {
+            // SO_REUSEPORT workers share a key; keep the clock running as
+            // long as the PID we started it for is still one of them.
+            let (pid, since) = match previous.get(&key) {
+                Some(&(pid, since)) if pids.contains(&pid) => (pid, since),
+                _ => (pids.iter().copied().min().unwrap_or_default(), now),
+            };
+            insert.execute(params![key.0 as i32, key.1, key.2, pid as i64, since])?;
+            since_by_key.insert(key, since);
+        }
+    }
+    tx.commit()?;
+    Ok(since_by_key)
+}
+
+/// Update the first-seen map from a listening-port listing, narrowed to
+/// `only` some ports or not. With `attach`, also fill in
+/// [`PortInfo::first_seen`] on each port.
+pub fn update_first_seen(ports: &mut [PortInfo], only: Option<&[u16]>, attach: bool) -> Result<()> {
+    let conn = open_db()?;
+    let since_by_key = track_first_seen(&conn, ports, only, Utc::now().timestamp())?;
+    if attach {
+        for port in ports {
+            port.first_seen = since_by_key
+                .get(&listener_key(port))
+                .map(|&since| UNIX_EPOCH + std::time::Duration::from_secs(since.max(0) as u64));
+        }
+    }
+    Ok(())
+}
+
 pub struct RecordResult {
     pub snapshot_id: i64,
     pub port_count: usize,
@@ -134,8 +295,16 @@ pub struct RecordResult {
 pub struct HistoryQuery {
     pub port: Option<u16>,
     pub process: Option<String>,
+    /// Substring of the recorded bind address, e.g. `0.0.0.0` or `::`.
+    pub address: Option<String>,
+    /// Recorded socket state, spelled as stored (`LISTEN`, `ESTABLISHED`).
+    pub state: Option<String>,
     pub hours: Option<i64>,
+    /// Maximum entries to return; 0 means no limit.
     pub limit: usize,
+    /// Only the newest entry per (port, protocol, process name), for an
+    /// inventory of what was last seen rather than every snapshot.
+    pub latest: bool,
 }
 
 impl Default for HistoryQuery {
@@ -143,8 +312,11 @@ impl Default for HistoryQuery {
         Self {
             port: None,
             process: None,
+            address: None,
+            state: None,
             hours: Some(24),
             limit: 100,
+            latest: false,
         }
     }
 }
@@ -160,14 +332,39 @@ pub struct HistoryEntry {
     pub process_name: String,
     pub container: Option<String>,
     pub state: Option<String>,
+    /// Peer of a recorded connection, and its host name if
+    /// `--resolve-names` found one.
+    pub remote_address: Option<String>,
+    pub remote_host: Option<String>,
+    /// Socket owner; `None` for rows recorded before it was tracked.
+    pub uid: Option<u32>,
+    pub user: Option<String>,
 }
 
 /// Get history matching the query
 pub fn get_history(query: &HistoryQuery) -> Result<Vec<HistoryEntry>> {
+    let mut entries = Vec::new();
+    for_each_history(query, |entry| {
+        entries.push(entry);
+        Ok(())
+    })?;
+    Ok(entries)
+}
+
+/// Call `f` for each history entry matching the query, newest first, as
+/// rows are read from the database. Stops at the first error from `f`.
+pub fn for_each_history(
+    query: &HistoryQuery,
+    mut f: impl FnMut(HistoryEntry) -> Result<()>,
+) -> Result<()> {
     let conn = open_db()?;
 
-    let mut sql = String::from(
-        "SELECT s.timestamp, p.port, p.protocol, p.address, p.pid, p.process_name, p.container, p.state
+    // With a lone MAX() aggregate, SQLite takes the other columns from the
+    // row holding the maximum, i.e. each `--latest` group's newest entry.
+    let newest = if query.latest { ", MAX(s.id)" } else { "" };
+    let mut sql = format!(
+        "SELECT s.timestamp, p.port, p.protocol, p.address, p.pid, p.process_name, p.container, p.state,
+                p.remote_addr, p.remote_host, p.uid, p.user{newest}
          FROM ports p
          JOIN snapshots s ON p.snapshot_id = s.id
          WHERE 1=1"
@@ -185,14 +382,33 @@ pub fn get_history(query: &HistoryQuery) -> Result<Vec<HistoryEntry>> {
         params_vec.push(Box::new(format!("%{}%", process)));
     }
 
+    if let Some(ref address) = query.address {
+        sql.push_str(" AND p.address LIKE ?");
+        params_vec.push(Box::new(format!("%{}%", address)));
+    }
+
+    if let Some(ref state) = query.state {
+        sql.push_str(" AND p.state = ?");
+        params_vec.push(Box::new(state.clone()));
+    }
+
     if let Some(hours) = query.hours {
         let cutoff = Utc::now() - Duration::hours(hours);
         sql.push_str(" AND s.unix_ts >= ?");
         params_vec.push(Box::new(cutoff.timestamp()));
     }
 
-    sql.push_str(" ORDER BY s.unix_ts DESC LIMIT ?");
-    params_vec.push(Box::new(query.limit as i32));
+    if query.latest {
+        sql.push_str(" GROUP BY p.port, p.protocol, p.process_name");
+    }
+
+    // SQLite treats a negative LIMIT as "no limit".
+    sql.push_str(" ORDER BY s.id DESC, p.id ASC LIMIT ?");
+    params_vec.push(Box::new(if query.limit == 0 {
+        -1
+    } else {
+        query.limit as i64
+    }));
 
     let params_refs: Vec<&dyn rusqlite::ToSql> = params_vec.iter().map(|p| p.as_ref()).collect();
 
@@ -212,10 +428,17 @@ pub fn get_history(query: &HistoryQuery) -> Result<Vec<HistoryEntry>> {
             process_name: row.get(5)?,
             container: row.get(6)?,
             state: row.get(7)?,
+            remote_address: row.get(8)?,
+            remote_host: row.get(9)?,
+            uid: row.get(10)?,
+            user: row.get(11)?,
         })
     })?;
 
-    rows.collect::<Result<Vec<_>, _>>().map_err(Into::into)
+    for row in rows {
+        f(row?)?;
+    }
+    Ok(())
 }
 
 /// Get summary statistics
@@ -228,11 +451,19 @@ pub fn get_stats() -> Result<HistoryStats> {
     let total_entries: i64 = conn.query_row("SELECT COUNT(*) FROM ports", [], |row| row.get(0))?;
 
     let oldest: Option<String> = conn
-        .query_row("SELECT MIN(timestamp) FROM snapshots", [], |row| row.get(0))
+        .query_row(
+            "SELECT timestamp FROM snapshots ORDER BY id ASC LIMIT 1",
+            [],
+            |row| row.get(0),
+        )
         .ok();
 
     let newest: Option<String> = conn
-        .query_row("SELECT MAX(timestamp) FROM snapshots", [], |row| row.get(0))
+        .query_row(
+            "SELECT timestamp FROM snapshots ORDER BY id DESC LIMIT 1",
+            [],
+            |row| row.get(0),
+        )
         .ok();
 
     let unique_ports: i64 =
@@ -240,11 +471,38 @@ pub fn get_stats() -> Result<HistoryStats> {
             row.get(0)
         })?;
 
+    let mut tcp_entries = 0;
+    let mut udp_entries = 0;
+    let mut stmt = conn.prepare("SELECT protocol, COUNT(*) FROM ports GROUP BY protocol")?;
+    for row in stmt.query_map([], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+    })? {
+        match row? {
+            (protocol, count) if protocol == "tcp" => tcp_entries = count as usize,
+            (protocol, count) if protocol == "udp" => udp_entries = count as usize,
+            _ => {}
+        }
+    }
+
+    // UDP rows have no state and are left out.
+    let mut stmt = conn.prepare(
+        "SELECT state, COUNT(*) AS n FROM ports WHERE state IS NOT NULL
+         GROUP BY state ORDER BY n DESC, state ASC",
+    )?;
+    let state_counts = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)? as usize))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
     let db_size = db_path()?.metadata().map(|m| m.len()).unwrap_or(0);
 
     Ok(HistoryStats {
         snapshot_count: snapshot_count as usize,
         total_entries: total_entries as usize,
+        tcp_entries,
+        udp_entries,
+        state_counts,
         unique_ports: unique_ports as usize,
         oldest_snapshot: oldest.and_then(|s| {
             DateTime::parse_from_rfc3339(&s)
@@ -263,35 +521,44 @@ pub fn get_stats() -> Result<HistoryStats> {
 pub struct HistoryStats {
     pub snapshot_count: usize,
     pub total_entries: usize,
+    pub tcp_entries: usize,
+    pub udp_entries: usize,
+    /// Entries per recorded socket state, most common first.
+    pub state_counts: Vec<(String, usize)>,
     pub unique_ports: usize,
     pub oldest_snapshot: Option<DateTime<Utc>>,
     pub newest_snapshot: Option<DateTime<Utc>>,
     pub db_size_bytes: u64,
 }
 
-/// Clean up old history entries
+/// Clean up old history entries: the newest snapshot older than the
+/// cutoff and everything recorded before it, so a clock that stepped back
+/// can't leave older snapshots behind newer ones.
 pub fn cleanup(keep_hours: i64) -> Result<CleanupResult> {
     let conn = open_db()?;
     let cutoff = Utc::now() - Duration::hours(keep_hours);
+    let last_id: Option<i64> = conn.query_row(
+        "SELECT MAX(id) FROM snapshots WHERE unix_ts < ?",
+        params![cutoff.timestamp()],
+        |row| row.get(0),
+    )?;
+    let last_id = last_id.unwrap_or(0);
 
     // Count what we're about to delete
     let snapshot_count: i64 = conn.query_row(
-        "SELECT COUNT(*) FROM snapshots WHERE unix_ts < ?",
-        params![cutoff.timestamp()],
+        "SELECT COUNT(*) FROM snapshots WHERE id <= ?",
+        params![last_id],
         |row| row.get(0),
     )?;
 
     let entry_count: i64 = conn.query_row(
-        "SELECT COUNT(*) FROM ports WHERE snapshot_id IN (SELECT id FROM snapshots WHERE unix_ts < ?)",
-        params![cutoff.timestamp()],
+        "SELECT COUNT(*) FROM ports WHERE snapshot_id <= ?",
+        params![last_id],
         |row| row.get(0),
     )?;
 
     // Delete old snapshots (cascades to ports)
-    conn.execute(
-        "DELETE FROM snapshots WHERE unix_ts < ?",
-        params![cutoff.timestamp()],
-    )?;
+    conn.execute("DELETE FROM snapshots WHERE id <= ?", params![last_id])?;
 
     // Vacuum to reclaim space
     conn.execute_batch("VACUUM;")?;
@@ -315,7 +582,7 @@ pub fn get_top_ports(limit: usize) -> Result<Vec<(u16, String, usize)>> {
         "SELECT port, protocol, COUNT(*) as cnt
          FROM ports
          GROUP BY port, protocol
-         ORDER BY cnt DESC
+         ORDER BY cnt DESC, MAX(snapshot_id) DESC
          LIMIT ?",
     )?;
 
@@ -330,6 +597,32 @@ pub fn get_top_ports(limit: usize) -> Result<Vec<(u16, String, usize)>> {
     rows.collect::<Result<Vec<_>, _>>().map_err(Into::into)
 }
 
+/// Get the processes that appear most often across snapshots, as
+/// `(process_name, distinct ports, occurrences)`. A process that keeps
+/// reopening ports across restarts ranks high here.
+pub fn get_top_processes(limit: usize) -> Result<Vec<(String, usize, usize)>> {
+    let conn = open_db()?;
+
+    let mut stmt = conn.prepare(
+        "SELECT process_name, COUNT(DISTINCT port) as ports, COUNT(*) as cnt
+         FROM ports
+         WHERE process_name IS NOT NULL AND process_name != ''
+         GROUP BY process_name
+         ORDER BY cnt DESC, MAX(snapshot_id) DESC
+         LIMIT ?",
+    )?;
+
+    let rows = stmt.query_map(params![limit as i32], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, i64>(1)? as usize,
+            row.get::<_, i64>(2)? as usize,
+        ))
+    })?;
+
+    rows.collect::<Result<Vec<_>, _>>().map_err(Into::into)
+}
+
 /// Get timeline of when a specific port was active
 pub fn get_port_timeline(port: u16, hours: i64) -> Result<Vec<PortTimelineEntry>> {
     let conn = open_db()?;
@@ -340,7 +633,7 @@ pub fn get_port_timeline(port: u16, hours: i64) -> Result<Vec<PortTimelineEntry>
          FROM ports p
          JOIN snapshots s ON p.snapshot_id = s.id
          WHERE p.port = ? AND s.unix_ts >= ?
-         ORDER BY s.unix_ts ASC",
+         ORDER BY s.id ASC",
     )?;
 
     let rows = stmt.query_map(params![port as i32, cutoff.timestamp()], |row| {
@@ -361,6 +654,25 @@ pub fn get_port_timeline(port: u16, hours: i64) -> Result<Vec<PortTimelineEntry>
     rows.collect::<Result<Vec<_>, _>>().map_err(Into::into)
 }
 
+/// Timestamps of every snapshot in the last `hours`, oldest first. The
+/// timeline compares these with the port's own rows to find the
+/// snapshots the port was missing from.
+pub fn get_snapshot_times(hours: i64) -> Result<Vec<DateTime<Utc>>> {
+    let conn = open_db()?;
+    let cutoff = Utc::now() - Duration::hours(hours);
+
+    let mut stmt =
+        conn.prepare("SELECT timestamp FROM snapshots WHERE unix_ts >= ? ORDER BY id ASC")?;
+    let rows = stmt.query_map(params![cutoff.timestamp()], |row| {
+        let ts_str: String = row.get(0)?;
+        Ok(DateTime::parse_from_rfc3339(&ts_str)
+            .map(|dt| dt.with_timezone(&Utc))
+            .unwrap_or_else(|_| Utc::now()))
+    })?;
+
+    rows.collect::<Result<Vec<_>, _>>().map_err(Into::into)
+}
+
 #[derive(Debug)]
 pub struct PortTimelineEntry {
     pub timestamp: DateTime<Utc>,
@@ -370,11 +682,13 @@ pub struct PortTimelineEntry {
     pub state: Option<String>,
 }
 
-/// Action for a diff entry: port appeared or disappeared.
-#[derive(Debug)]
+/// Action for a diff entry: port appeared, disappeared, or is held by a
+/// different process.
+#[derive(Debug, PartialEq, Eq)]
 pub enum DiffAction {
     Appeared,
     Disappeared,
+    Changed,
 }
 
 /// A port that changed between two snapshots.
@@ -383,18 +697,22 @@ pub struct DiffEntry {
     pub port: u16,
     pub protocol: String,
     pub process_name: String,
+    /// For `Changed`: the process name(s) in the older snapshot.
+    pub previous_process_name: Option<String>,
     pub action: DiffAction,
 }
 
 /// Compare the latest snapshot against one `snapshots_ago` snapshots earlier.
 ///
-/// Returns ports that appeared (present in latest but not older) and disappeared
-/// (present in older but not latest), ordered by action then port.
+/// Returns ports that appeared (present in latest but not older), disappeared
+/// (present in older but not latest), and changed (present in both, but
+/// held by a different set of process names), ordered by action then port.
+/// Processes on one port are joined with `,` in `Changed` entries.
 pub fn get_diff(snapshots_ago: usize) -> Result<Vec<DiffEntry>> {
     let conn = open_db()?;
 
     // Get the (snapshots_ago + 1) most recent snapshot IDs, ordered desc
-    let mut stmt = conn.prepare("SELECT id FROM snapshots ORDER BY unix_ts DESC LIMIT ?")?;
+    let mut stmt = conn.prepare("SELECT id FROM snapshots ORDER BY id DESC LIMIT ?")?;
     let ids: Vec<i64> = stmt
         .query_map(params![(snapshots_ago + 1) as i64], |r| r.get(0))?
         .collect::<Result<_, _>>()?;
@@ -425,6 +743,7 @@ pub fn get_diff(snapshots_ago: usize) -> Result<Vec<DiffEntry>> {
                 port: r.get::<_, i32>(0)? as u16,
                 protocol: r.get(1)?,
                 process_name: r.get(2)?,
+                previous_process_name: None,
                 action: DiffAction::Appeared,
             })
         })?
@@ -449,13 +768,47 @@ pub fn get_diff(snapshots_ago: usize) -> Result<Vec<DiffEntry>> {
                 port: r.get::<_, i32>(0)? as u16,
                 protocol: r.get(1)?,
                 process_name: r.get(2)?,
+                previous_process_name: None,
                 action: DiffAction::Disappeared,
             })
         })?
         .collect::<Result<_, _>>()?;
 
+    // Ports in both → Changed when the sorted set of process names differs
+    let mut stmt = conn.prepare(
+        "WITH names AS (
+             SELECT snapshot_id, port, protocol, GROUP_CONCAT(name, ',') AS names
+             FROM (
+                 SELECT DISTINCT snapshot_id, port, protocol,
+                        COALESCE(process_name, '') AS name
+                 FROM ports
+                 WHERE snapshot_id IN (?1, ?2)
+                 ORDER BY snapshot_id, port, protocol, name
+             )
+             GROUP BY snapshot_id, port, protocol
+         )
+         SELECT n.port, n.protocol, n.names, o.names
+         FROM names n
+         JOIN names o
+           ON o.snapshot_id = ?2 AND o.port = n.port AND o.protocol = n.protocol
+         WHERE n.snapshot_id = ?1 AND n.names != o.names
+         ORDER BY n.port ASC",
+    )?;
+    let changed: Vec<DiffEntry> = stmt
+        .query_map(params![latest_id, older_id], |r| {
+            Ok(DiffEntry {
+                port: r.get::<_, i32>(0)? as u16,
+                protocol: r.get(1)?,
+                process_name: r.get(2)?,
+                previous_process_name: Some(r.get(3)?),
+                action: DiffAction::Changed,
+            })
+        })?
+        .collect::<Result<_, _>>()?;
+
     let mut entries = appeared;
     entries.extend(disappeared);
+    entries.extend(changed);
     Ok(entries)
 }
 
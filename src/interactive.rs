@@ -1,10 +1,10 @@
 use std::collections::HashMap;
 
 use anyhow::{bail, Result};
-use dialoguer::{theme::ColorfulTheme, Select};
+use dialoguer::{theme::ColorfulTheme, FuzzySelect};
 
 use crate::ancestry::ProcessAncestry;
-use crate::commands::kill::kill_process;
+use crate::commands::kill::{kill_process, Signal};
 use crate::types::PortInfo;
 
 pub fn select_and_kill(
@@ -34,8 +34,12 @@ pub fn select_and_kill(
         })
         .collect();
 
-    let selection = Select::with_theme(&ColorfulTheme::default())
-        .with_prompt("Select a port to kill (↑/↓ or j/k to navigate, Enter to select, q to quit)")
+    // Typing narrows the list by any part of the line: port, protocol,
+    // PID, process name, framework, or source.
+    let selection = FuzzySelect::with_theme(&ColorfulTheme::default())
+        .with_prompt(
+            "Select a port to kill (type to filter, ↑/↓ to navigate, Enter to select, Esc to quit)",
+        )
         .items(&items)
         .default(0)
         .interact_opt()?;
@@ -47,7 +51,7 @@ pub fn select_and_kill(
                 "Killing PID {} ({}) on port {}",
                 port.pid, port.process_name, port.port
             );
-            kill_process(port.pid)?;
+            kill_process(port.pid, Signal::SIGTERM)?;
             eprintln!("Killed PID {}", port.pid);
             Ok(())
         }
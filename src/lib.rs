@@ -21,18 +21,40 @@
 //!
 //! ## Library API
 //!
-//! This crate ships a binary (`ports`); the library exists only so that
-//! `main.rs` can stay a thin shim. The surface is intentionally minimal —
-//! only [`Cli`] and [`run`] are exported — and is **not** part of the
-//! crate's public API for semver purposes. Internal modules are
+//! [`snapshot`] and [`connections`] enumerate sockets the same way the
+//! `ports` binary does — service names, command lines, Docker containers,
+//! and frameworks filled in — sorted by port, then protocol, then PID.
+//! They fail with a [`PortsError`] whose variant says what went wrong.
+//!
+//! The stable public surface, which follows semver, is:
+//!
+//! - [`snapshot`] and [`connections`]
+//! - [`PortsError`]
+//! - [`PortInfo`] and the types in its fields: [`Protocol`],
+//!   [`SocketState`], [`Risk`], and [`HealthWarning`]
+//!
+//! ```no_run
+//! fn main() -> anyhow::Result<()> {
+//!     for p in portls::snapshot()? {
+//!         println!("{}/{} {} (pid {})", p.port, p.protocol, p.process_name, p.pid);
+//!     }
+//!     Ok(())
+//! }
+//! ```
+//!
+//! [`Cli`], [`run`], and [`exit_code`] exist so that `main.rs` can stay a
+//! thin shim and are **not** covered by semver. Internal modules are
 //! crate-private and may change shape or disappear in any release.
-//! Depending on `portls` as a library is not a supported integration.
 
+pub(crate) mod allowlist;
 pub(crate) mod ancestry;
 pub(crate) mod cli;
 pub(crate) mod commands;
+pub(crate) mod config;
+pub(crate) mod dns;
 #[cfg(feature = "docker")]
 pub(crate) mod docker;
+pub(crate) mod error;
 pub(crate) mod filter;
 pub(crate) mod framework;
 #[cfg(feature = "history")]
@@ -48,7 +70,12 @@ pub(crate) mod types;
 #[cfg(feature = "watch")]
 pub(crate) mod watch;
 
+pub use ancestry::HealthWarning;
 pub use cli::Cli;
+pub use error::PortsError;
+/// The verdict in [`PortInfo::risk`], re-exported so callers can match on it.
+pub use types::Risk;
+pub use types::{PortInfo, Protocol, SocketState};
 
 use std::env;
 use std::fs;
@@ -59,7 +86,114 @@ use anyhow::{Context, Result};
 use clap::CommandFactory;
 use clap_complete::{generate, Shell};
 
-pub fn run(cli: Cli) -> Result<()> {
+/// List listening sockets on this host, fully enriched.
+///
+/// The Docker daemon is only contacted when a Docker forwarder process
+/// (`docker-proxy`, `rootlesskit`, ...) is listening; if it can't be
+/// reached, container names are left as `None` rather than failing.
+pub fn snapshot() -> Result<Vec<PortInfo>, PortsError> {
+    let mut ports = platform::get_listening_ports()?.ports;
+    PortInfo::sort_vec(&mut ports, None);
+    Ok(ports)
+}
+
+/// List established connections on this host, fully enriched.
+///
+/// Supported on Linux and macOS, and via `netstat` elsewhere when the
+/// `netstat` feature is on; fails with [`PortsError::UnsupportedPlatform`]
+/// otherwise.
+pub fn connections() -> Result<Vec<PortInfo>, PortsError> {
+    let mut ports = platform::get_connections()?.ports;
+    PortInfo::sort_vec(&mut ports, None);
+    Ok(ports)
+}
+
+/// Process exit status for an error returned by [`run`]: 2 when `ports
+/// kill` signalled only some of its targets, 1 for everything else.
+pub fn exit_code(err: &anyhow::Error) -> u8 {
+    if err.is::<commands::kill::PartialFailure>() {
+        2
+    } else {
+        1
+    }
+}
+
+/// Whether `err` is stdout's reader going away, as in `ports | head`.
+/// That's the reader's choice, not a failure worth reporting.
+pub fn is_broken_pipe(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<io::Error>()
+        .is_some_and(|e| e.kind() == io::ErrorKind::BrokenPipe)
+}
+
+pub fn run(mut cli: Cli) -> Result<()> {
+    // Before the config file is read, so a broken config can't hide it.
+    if cli.version {
+        output::json::init_compact(cli.compact);
+        return commands::version::execute(cli.json);
+    }
+    // Re-exec before anything prints; the elevated run redoes the rest.
+    if cli.sudo {
+        platform::reexec_under_sudo()?;
+    }
+    if cli.tcp {
+        cli.protocol = Some(cli::ProtocolFilter::Tcp);
+    } else if cli.udp {
+        cli.protocol = Some(cli::ProtocolFilter::Udp);
+    }
+    // PORTS_* environment variables, then the config file, fill in
+    // whatever the command line left unset.
+    let given = (cli.connections, cli.json);
+    config::from_env().apply(&mut cli);
+    config::load()?.apply(&mut cli);
+    check_default_conflicts(&cli, given)?;
+    // Checked here rather than by clap so PORTS_CONNECTIONS satisfies it.
+    if cli.throughput && !cli.connections {
+        anyhow::bail!("--throughput needs --connections");
+    }
+    // Grouping by remote host only makes sense over connections.
+    cli.connections |= cli.by_remote;
+    output::init_color(cli.color.unwrap_or(cli::ColorChoice::Auto));
+    output::init_max_width(cli.max_width);
+    output::json::init_compact(cli.compact);
+    output::init_output(cli.output.clone());
+    // Full-screen and prompt-driven modes own the terminal; a stray stderr
+    // line would corrupt them.
+    let tty_ui = cli.interactive || matches!(cli.command, Some(cli::Commands::Top { .. }));
+    platform::set_verbose(cli.verbose && !tty_ui);
+    if cli.all_netns && !cfg!(target_os = "linux") {
+        anyhow::bail!("--all-netns is only supported on Linux");
+    }
+    platform::set_all_netns(cli.all_netns);
+    if cli.kernel && !cfg!(target_os = "linux") {
+        anyhow::bail!("--kernel is only supported on Linux");
+    }
+    platform::set_show_kernel(cli.kernel);
+    platform::set_skip_docker(cli.no_docker);
+    dns::set_enabled(cli.resolve_names);
+    if cli.throughput && !cfg!(target_os = "linux") {
+        anyhow::bail!("--throughput is only supported on Linux");
+    }
+    if cli.docker_only && !cfg!(feature = "docker") {
+        anyhow::bail!(
+            "this binary was built without the `docker` feature, so no port \
+             has a container; rebuild with default features or \
+             `cargo install portls --features docker` to use --docker-only"
+        );
+    }
+    if cli.docker_only && matches!(cli.command, Some(cli::Commands::Top { .. })) {
+        anyhow::bail!("--docker-only is not supported by `top`");
+    }
+    // Only JSON, baselines and history show the socket owner's name.
+    platform::set_resolve_user_names(
+        cli.json
+            || cli.baseline.is_some()
+            || matches!(cli.command, Some(cli::Commands::History { .. })),
+    );
+    #[cfg(feature = "history")]
+    if let Some(path) = cli.db_path.clone() {
+        history::set_db_path(path);
+    }
+
     if cli.interactive {
         return run_interactive(&cli);
     }
@@ -68,24 +202,105 @@ pub fn run(cli: Cli) -> Result<()> {
         return run_watch(&cli);
     }
 
+    if cli.output.is_some() {
+        check_output_target(&cli)?;
+    }
+
+    if let Some(action) = cli.baseline {
+        if !matches!(cli.command, None | Some(cli::Commands::List)) {
+            anyhow::bail!("--baseline only applies to port listings, not subcommands");
+        }
+        return commands::baseline::execute(
+            action,
+            &cli.baseline_file,
+            cli.query.as_deref(),
+            cli.regex,
+            cli.protocol,
+            cli.state,
+            cli.interface.as_deref(),
+            cli.dev,
+            cli.json,
+        );
+    }
+
+    if cli.allow_file.is_some() && !matches!(cli.command, None | Some(cli::Commands::List)) {
+        anyhow::bail!("--allow-file only applies to port listings, not subcommands");
+    }
+    let allowlist = cli
+        .allow_file
+        .as_deref()
+        .map(allowlist::Allowlist::load)
+        .transpose()?;
+
     match &cli.command {
         Some(cli::Commands::List) => commands::list::execute(
             cli.json,
             cli.connections,
             cli.sort,
             cli.protocol,
+            cli.state,
+            cli.interface.as_deref(),
             cli.why,
             cli.dev,
+            cli.docker_only,
+            cli.group_by,
+            cli.wide,
+            cli.dedup,
+            cli.age,
+            cli.summary,
+            cli.with_conn_count,
+            cli.limit,
+            cli.porcelain,
+            cli.security,
+            cli.by_remote,
+            cli.anomaly,
+            allowlist.as_ref(),
+            cli.unexpected_only,
+            cli.throughput,
         ),
         Some(cli::Commands::Kill {
             target,
             force,
             all,
             connections,
-        }) => commands::kill::execute(target, *force, *all, *connections),
-        Some(cli::Commands::Why { target }) => commands::why::execute(target, cli.json),
-        Some(cli::Commands::Top { connections }) => run_top(*connections, cli.dev),
+            include_self,
+            signal,
+        }) => commands::kill::execute(
+            target,
+            *force,
+            *all,
+            *connections,
+            *include_self,
+            *signal,
+            cli.protocol,
+            cli.json,
+        ),
+        Some(cli::Commands::Why {
+            target,
+            newer_than,
+            tree,
+            env,
+        }) => commands::why::execute(
+            target.as_deref(),
+            *newer_than,
+            *tree,
+            env,
+            cli.protocol,
+            cli.json,
+        ),
+        Some(cli::Commands::Open { port }) => {
+            commands::open::execute(*port, cli.protocol, cli.json)
+        }
+        Some(cli::Commands::Top {
+            connections,
+            no_restore,
+        }) => run_top(*connections, cli.dev, cli.protocol, !*no_restore),
         Some(cli::Commands::Completions { shell, print }) => {
+            if cli.json {
+                anyhow::bail!(
+                    "--json is not applicable to completions; use --print or --output <PATH>"
+                );
+            }
             if *print {
                 let mut cmd = Cli::command();
                 if matches!(shell, Shell::Fish) {
@@ -94,7 +309,7 @@ pub fn run(cli: Cli) -> Result<()> {
                     generate(*shell, &mut cmd, "ports", &mut io::stdout());
                 }
             } else {
-                let installed = install_completions(*shell)?;
+                let installed = install_completions(*shell, cli.output.as_deref())?;
                 eprintln!("Installed completions to {}", installed.path.display());
                 if !installed.hint.is_empty() {
                     eprintln!("{}", installed.hint);
@@ -102,7 +317,7 @@ pub fn run(cli: Cli) -> Result<()> {
             }
             Ok(())
         }
-        Some(cli::Commands::History { action }) => run_history(action, cli.json),
+        Some(cli::Commands::History { action }) => run_history(action, cli.state, cli.json),
         None => match &cli.query {
             Some(query) => commands::query::execute(
                 query,
@@ -110,22 +325,108 @@ pub fn run(cli: Cli) -> Result<()> {
                 cli.connections,
                 cli.sort,
                 cli.protocol,
+                cli.state,
+                cli.interface.as_deref(),
                 cli.regex,
                 cli.why,
                 cli.dev,
+                cli.docker_only,
+                cli.group_by,
+                cli.wide,
+                cli.dedup,
+                cli.age,
+                cli.summary,
+                cli.with_conn_count,
+                cli.limit,
+                cli.porcelain,
+                cli.security,
+                cli.by_remote,
+                cli.anomaly,
+                allowlist.as_ref(),
+                cli.unexpected_only,
+                cli.throughput,
             ),
             None => commands::list::execute(
                 cli.json,
                 cli.connections,
                 cli.sort,
                 cli.protocol,
+                cli.state,
+                cli.interface.as_deref(),
                 cli.why,
                 cli.dev,
+                cli.docker_only,
+                cli.group_by,
+                cli.wide,
+                cli.dedup,
+                cli.age,
+                cli.summary,
+                cli.with_conn_count,
+                cli.limit,
+                cli.porcelain,
+                cli.security,
+                cli.by_remote,
+                cli.anomaly,
+                allowlist.as_ref(),
+                cli.unexpected_only,
+                cli.throughput,
             ),
         },
     }
 }
 
+/// `--output` takes the documents scripts consume: a listing or `why`
+/// as JSON or porcelain. `completions` reads it as its install path.
+/// Clap only sees the command line, so redo its conflicts with
+/// `--connections` and `--json` when a default turned them on instead.
+/// `given` is their command-line values.
+fn check_default_conflicts(cli: &Cli, given: (bool, bool)) -> Result<()> {
+    let (given_connections, given_json) = given;
+    if cli.connections && !given_connections {
+        let clash = [
+            ("--security", cli.security),
+            ("--anomaly", cli.anomaly),
+            ("--allow-file", cli.allow_file.is_some()),
+            ("--with-conn-count", cli.with_conn_count),
+            ("--baseline", cli.baseline.is_some()),
+        ]
+        .into_iter()
+        .find_map(|(flag, set)| set.then_some(flag));
+        if let Some(flag) = clash {
+            anyhow::bail!(
+                "{flag} cannot be used with --connections, which PORTS_CONNECTIONS \
+                 or the config file turned on"
+            );
+        }
+    }
+    if cli.json && !given_json && cli.porcelain {
+        anyhow::bail!("--porcelain cannot be used with --json, which the config file turned on");
+    }
+    Ok(())
+}
+
+fn check_output_target(cli: &Cli) -> Result<()> {
+    match &cli.command {
+        Some(cli::Commands::Completions { .. }) => Ok(()),
+        Some(cli::Commands::Why { .. }) if !cli.json => {
+            anyhow::bail!("--output writes `why --json` output; add --json")
+        }
+        Some(cli::Commands::Open { .. }) if !cli.json => {
+            anyhow::bail!("--output writes `open --json` output; add --json")
+        }
+        Some(cli::Commands::Open { .. }) => Ok(()),
+        None | Some(cli::Commands::List | cli::Commands::Why { .. }) if cli.baseline.is_none() => {
+            if !cli.json && !cli.porcelain {
+                anyhow::bail!("--output writes --json or --porcelain output; add one of them");
+            }
+            Ok(())
+        }
+        _ => {
+            anyhow::bail!("--output only applies to port listings, `why`, `open` and `completions`")
+        }
+    }
+}
+
 #[cfg(feature = "tui")]
 fn run_interactive(cli: &Cli) -> Result<()> {
     use types::PortInfo;
@@ -138,10 +439,15 @@ fn run_interactive(cli: &Cli) -> Result<()> {
         platform::get_listening_ports()?
     };
 
-    let mut ports = PortInfo::filter_protocol(listing.ports, cli.protocol);
+    let ports = PortInfo::filter_protocol(listing.ports, cli.protocol);
+    let ports = PortInfo::filter_state(ports, cli.state);
+    let mut ports = PortInfo::filter_interface(ports, cli.interface.as_deref())?;
     if cli.dev {
         filter::retain_dev_only(&mut ports);
     }
+    if cli.docker_only {
+        ports.retain(|p| p.container.is_some());
+    }
 
     if let Some(query) = &cli.query {
         ports = PortInfo::filter_by_query(ports, query, cli.regex)?;
@@ -186,22 +492,59 @@ fn run_watch(cli: &Cli) -> Result<()> {
         Some(cli::Commands::Why { .. }) => {
             anyhow::bail!("Cannot use --watch with why command");
         }
+        Some(cli::Commands::Open { .. }) => {
+            anyhow::bail!("Cannot use --watch with open command");
+        }
         Some(cli::Commands::History { .. }) => {
             anyhow::bail!("Cannot use --watch with history command");
         }
         None => cli.query.clone(),
     };
 
+    if cli.notify && !cfg!(feature = "notify") {
+        anyhow::bail!(
+            "this binary was built without the `notify` feature; \
+             rebuild with `cargo install portls --features notify` to use --notify"
+        );
+    }
+
+    let format = cli.format.unwrap_or(if cli.json {
+        cli::WatchFormat::Json
+    } else {
+        cli::WatchFormat::Table
+    });
+    if format == cli::WatchFormat::JsonDiff && cli.why {
+        anyhow::bail!("--why is not supported with --format json-diff");
+    }
+    let manual = cli.manual || cli.interval == 0.0;
+    if manual && !cfg!(feature = "tui") {
+        anyhow::bail!(
+            "this binary was built without the `tui` feature; \
+             manual refresh (--manual or --interval 0) reads keys through it. \
+             Rebuild with default features or `cargo install portls --features tui`"
+        );
+    }
+
     watch::run(watch::WatchOptions {
         interval: std::time::Duration::from_secs_f64(cli.interval),
-        json: cli.json,
+        iterations: cli.iterations.filter(|&n| n > 0),
+        json: format == cli::WatchFormat::Json,
+        json_diff: format == cli::WatchFormat::JsonDiff,
         filter,
         connections: cli.connections,
         sort: cli.sort,
         protocol: cli.protocol,
+        state: cli.state,
+        interface: cli.interface.clone(),
         use_regex: cli.regex,
         why: cli.why,
         dev: cli.dev,
+        docker_only: cli.docker_only,
+        wide: cli.wide,
+        bell: cli.bell,
+        notify: cli.notify,
+        diff_only: cli.diff_only,
+        manual,
     })
 }
 
@@ -214,12 +557,22 @@ fn run_watch(_cli: &Cli) -> Result<()> {
 }
 
 #[cfg(feature = "tui")]
-fn run_top(connections: bool, dev: bool) -> Result<()> {
-    top::run(connections, dev)
+fn run_top(
+    connections: bool,
+    dev: bool,
+    protocol: Option<cli::ProtocolFilter>,
+    restore: bool,
+) -> Result<()> {
+    top::run(connections, dev, protocol, restore)
 }
 
 #[cfg(not(feature = "tui"))]
-fn run_top(_connections: bool, _dev: bool) -> Result<()> {
+fn run_top(
+    _connections: bool,
+    _dev: bool,
+    _protocol: Option<cli::ProtocolFilter>,
+    _restore: bool,
+) -> Result<()> {
     anyhow::bail!(
         "this binary was built without the `tui` feature; \
          the `top` subcommand requires it. Rebuild with default features \
@@ -228,26 +581,55 @@ fn run_top(_connections: bool, _dev: bool) -> Result<()> {
 }
 
 #[cfg(feature = "history")]
-fn run_history(action: &cli::HistoryAction, json: bool) -> Result<()> {
+fn run_history(
+    action: &cli::HistoryAction,
+    state: Option<cli::StateFilter>,
+    json: bool,
+) -> Result<()> {
+    // `--state` is global so `history show` can share `ports --state`'s
+    // values; every other history action would silently ignore it.
+    if state.is_some() && !matches!(action, cli::HistoryAction::Show { .. }) {
+        anyhow::bail!("--state only applies to port listings and `history show`");
+    }
     match action {
         cli::HistoryAction::Record { connections } => commands::history::record(*connections, json),
         cli::HistoryAction::Show {
             port,
             process,
+            address,
             hours,
             limit,
-        } => commands::history::show(*port, process.clone(), Some(*hours), *limit, json),
+            latest,
+            format,
+        } => commands::history::show(
+            *port,
+            process.clone(),
+            address.clone(),
+            state,
+            Some(*hours),
+            *limit,
+            *latest,
+            format.unwrap_or(if json {
+                cli::HistoryFormat::Json
+            } else {
+                cli::HistoryFormat::Table
+            }),
+        ),
         cli::HistoryAction::Timeline { port, hours } => {
             commands::history::timeline(*port, *hours, json)
         }
         cli::HistoryAction::Stats => commands::history::stats(json),
         cli::HistoryAction::Clean { keep } => commands::history::cleanup(*keep, json),
-        cli::HistoryAction::Diff { ago } => commands::history::diff(*ago, json),
+        cli::HistoryAction::Diff { ago, grouped } => commands::history::diff(*ago, *grouped, json),
     }
 }
 
 #[cfg(not(feature = "history"))]
-fn run_history(_action: &cli::HistoryAction, _json: bool) -> Result<()> {
+fn run_history(
+    _action: &cli::HistoryAction,
+    _state: Option<cli::StateFilter>,
+    _json: bool,
+) -> Result<()> {
     anyhow::bail!(
         "this binary was built without the `history` feature; \
          the `history` subcommand requires it. Rebuild with default features \
@@ -303,14 +685,19 @@ fn install_path(shell: Shell) -> Result<PathBuf> {
         || {
             format!(
                 "auto-install of completions is not supported for {shell}. \
-                 Use `ports completions {shell} --print` and redirect manually."
+                 Use `ports completions {shell} --output <PATH>`, or `--print` \
+                 and redirect manually."
             )
         },
     )
 }
 
-fn install_completions(shell: Shell) -> Result<Installed> {
-    let path = install_path(shell)?;
+/// Write completions to `output`, or to the shell's standard user dir.
+fn install_completions(shell: Shell, output: Option<&Path>) -> Result<Installed> {
+    let path = match output {
+        Some(path) => path.to_path_buf(),
+        None => install_path(shell)?,
+    };
     if let Some(parent) = path.parent() {
         fs::create_dir_all(parent).with_context(|| format!("creating {}", parent.display()))?;
     }
@@ -355,6 +742,20 @@ mod tests {
     use clap_complete::Shell;
     use std::path::Path;
 
+    #[test]
+    fn partial_kill_failure_exits_with_2() {
+        let partial = anyhow::Error::new(commands::kill::PartialFailure {
+            failed: 1,
+            total: 3,
+        });
+        assert_eq!(partial.to_string(), "Failed to kill 1 of 3 process(es)");
+        assert_eq!(exit_code(&partial), 2);
+        assert_eq!(
+            exit_code(&anyhow::anyhow!("Failed to kill any processes")),
+            1
+        );
+    }
+
     #[test]
     fn fish_completions_suppress_files() {
         let mut cmd = Cli::command();
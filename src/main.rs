@@ -1,7 +1,16 @@
-use anyhow::Result;
+use std::process::ExitCode;
+
 use clap::Parser;
 
-fn main() -> Result<()> {
+fn main() -> ExitCode {
     let cli = portls::Cli::parse();
-    portls::run(cli)
+    match portls::run(cli) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) if portls::is_broken_pipe(&err) => ExitCode::SUCCESS,
+        // Same rendering as returning the error from `main`.
+        Err(err) => {
+            eprintln!("Error: {err:?}");
+            ExitCode::from(portls::exit_code(&err))
+        }
+    }
 }
@@ -0,0 +1,100 @@
+//! `--output`: write a document to a file that readers only ever see whole.
+//!
+//! The document goes to a temporary file next to the target, which is
+//! renamed over it once everything was written. A rename within one
+//! directory is atomic, so a script polling the file gets either the old
+//! document or the new one, never half of either.
+
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+pub struct AtomicFile {
+    path: PathBuf,
+    tmp: PathBuf,
+    /// `None` once committed.
+    writer: Option<BufWriter<File>>,
+}
+
+impl AtomicFile {
+    /// Start writing a replacement for `path`. Nothing at `path` changes
+    /// until [`commit`](Self::commit).
+    pub fn create(path: &Path) -> Result<Self> {
+        let tmp = temp_path(path);
+        let file = File::create(&tmp).with_context(|| format!("writing {}", path.display()))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            tmp,
+            writer: Some(BufWriter::new(file)),
+        })
+    }
+
+    /// Flush everything to disk and move it into place.
+    pub fn commit(mut self) -> Result<()> {
+        let writer = self.writer.take().expect("not yet committed");
+        let file = writer
+            .into_inner()
+            .map_err(io::IntoInnerError::into_error)
+            .and_then(|file| file.sync_all().map(|()| file))
+            .with_context(|| format!("writing {}", self.tmp.display()))?;
+        drop(file);
+        fs::rename(&self.tmp, &self.path)
+            .with_context(|| format!("replacing {}", self.path.display()))
+    }
+}
+
+impl Write for AtomicFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.writer.as_mut().expect("not yet committed").write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.as_mut().expect("not yet committed").flush()
+    }
+}
+
+/// An abandoned write (an error mid-document) leaves the target alone and
+/// cleans up after itself.
+impl Drop for AtomicFile {
+    fn drop(&mut self) {
+        if self.writer.take().is_some() {
+            let _ = fs::remove_file(&self.tmp);
+        }
+    }
+}
+
+/// `dir/.name.<pid>.tmp`: hidden, in the target's directory so the rename
+/// doesn't cross filesystems, and unique per process.
+fn temp_path(path: &Path) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    path.with_file_name(format!(".{name}.{}.tmp", std::process::id()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn target_only_changes_on_commit() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ports.json");
+        fs::write(&path, "old\n").unwrap();
+
+        let mut file = AtomicFile::create(&path).unwrap();
+        writeln!(file, "new").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "old\n");
+        file.commit().unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new\n");
+
+        let mut file = AtomicFile::create(&path).unwrap();
+        write!(file, "partial").unwrap();
+        drop(file);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new\n");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+}
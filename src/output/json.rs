@@ -1,49 +1,149 @@
 use std::collections::HashMap;
+use std::io::{self, Write};
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use serde::Serialize;
 use serde_json::{json, Value};
 
 use crate::ancestry::ProcessAncestry;
-use crate::types::{DockerStatus, PortInfo};
+use crate::types::{DockerStatus, PortInfo, RemoteGroup, UNKNOWN_SERVICE};
 
-pub fn print_ports(ports: &[PortInfo], docker_status: &DockerStatus) {
-    let output = wrap(ports_to_values(ports), docker_status);
-    println!(
-        "{}",
-        serde_json::to_string_pretty(&output).expect("Failed to serialize to JSON")
-    );
+static COMPACT: OnceLock<bool> = OnceLock::new();
+
+/// Apply `--compact`: single-line JSON instead of the pretty-printed
+/// default.
+pub fn init_compact(compact: bool) {
+    let _ = COMPACT.set(compact);
+}
+
+/// Serialize for output, honoring `--compact`. Every JSON printer goes
+/// through here so the flag applies uniformly.
+pub fn to_string<T: Serialize + ?Sized>(value: &T) -> serde_json::Result<String> {
+    if COMPACT.get().copied().unwrap_or(false) {
+        serde_json::to_string(value)
+    } else {
+        serde_json::to_string_pretty(value)
+    }
+}
+
+pub fn print_ports(
+    out: &mut dyn Write,
+    ports: &[PortInfo],
+    docker_status: &DockerStatus,
+) -> io::Result<()> {
+    let output = ports_document(ports, docker_status);
+    writeln!(out, "{}", to_string(&output)?)
+}
+
+/// The `ports --json` document, for writing somewhere other than stdout.
+pub fn ports_document(ports: &[PortInfo], docker_status: &DockerStatus) -> Value {
+    wrap(ports_to_values(ports), docker_status)
+}
+
+/// `--summary --json`: `[{"service": "http", "count": 2}, ...]`, in the
+/// table's order (an object would sort the names).
+pub fn print_service_summary(out: &mut dyn Write, ports: &[PortInfo]) -> io::Result<()> {
+    writeln!(out, "{}", to_string(&service_summary(ports))?)
+}
+
+fn service_summary(ports: &[PortInfo]) -> Value {
+    PortInfo::count_by_service(ports)
+        .into_iter()
+        .map(|(name, count)| {
+            let service = name.unwrap_or_else(|| UNKNOWN_SERVICE.to_string());
+            serde_json::json!({ "service": service, "count": count })
+        })
+        .collect()
+}
+
+/// `--by-remote --json`: an array of `{"remote", "count", "local_ports"}`.
+pub fn print_remote_groups(out: &mut dyn Write, groups: &[RemoteGroup]) -> io::Result<()> {
+    writeln!(out, "{}", to_string(groups)?)
+}
+
+/// Element of the `ports` array whenever ancestry is requested: the
+/// port's own fields, flattened, plus an `ancestry` object when one
+/// could be traced. Shared by `ports --why --json` and
+/// `ports why <target> --json` so both emit the same schema.
+#[derive(Serialize)]
+struct PortWithAncestry<'a> {
+    #[serde(flatten)]
+    port: &'a PortInfo,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ancestry: Option<&'a ProcessAncestry>,
+    /// `ports why --env` variables that are set, as an object.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    env: Option<serde_json::Map<String, Value>>,
 }
 
 /// Print ports as JSON with ancestry data merged in.
 pub fn print_ports_why(
+    out: &mut dyn Write,
     ports: &[PortInfo],
     ancestry_map: &HashMap<u32, ProcessAncestry>,
     docker_status: &DockerStatus,
-) {
-    #[derive(Serialize)]
-    struct PortWithAncestry<'a> {
-        #[serde(flatten)]
-        port: &'a PortInfo,
-        #[serde(skip_serializing_if = "Option::is_none")]
-        ancestry: Option<&'a ProcessAncestry>,
-    }
+) -> io::Result<()> {
+    print_ports_why_env(out, ports, ancestry_map, &HashMap::new(), docker_status)
+}
 
-    let enriched: Vec<Value> = ports
+/// [`print_ports_why`] plus the `ports why --env` variables of each
+/// process whose environment could be read.
+pub fn print_ports_why_env(
+    out: &mut dyn Write,
+    ports: &[PortInfo],
+    ancestry_map: &HashMap<u32, ProcessAncestry>,
+    env_map: &HashMap<u32, Vec<(String, String)>>,
+    docker_status: &DockerStatus,
+) -> io::Result<()> {
+    let output = wrap(
+        ports_with_ancestry(ports, ancestry_map, env_map),
+        docker_status,
+    );
+    writeln!(out, "{}", to_string(&output)?)
+}
+
+/// `ports open --json`: the bind result per protocol (`free`, `in_use`,
+/// `permission_denied`) and the processes holding the port, with ancestry.
+pub fn print_open(
+    out: &mut dyn Write,
+    port: u16,
+    status: &[(String, &str)],
+    holders: &[PortInfo],
+    ancestry_map: &HashMap<u32, ProcessAncestry>,
+) -> io::Result<()> {
+    let status: serde_json::Map<String, Value> = status
+        .iter()
+        .map(|(protocol, status)| (protocol.clone(), Value::from(*status)))
+        .collect();
+    let output = json!({
+        "port": port,
+        "status": status,
+        "holders": ports_with_ancestry(holders, ancestry_map, &HashMap::new()),
+    });
+    writeln!(out, "{}", to_string(&output)?)
+}
+
+fn ports_with_ancestry(
+    ports: &[PortInfo],
+    ancestry_map: &HashMap<u32, ProcessAncestry>,
+    env_map: &HashMap<u32, Vec<(String, String)>>,
+) -> Vec<Value> {
+    ports
         .iter()
         .map(|p| {
             serde_json::to_value(PortWithAncestry {
                 port: p,
                 ancestry: ancestry_map.get(&p.pid),
+                env: env_map.get(&p.pid).map(|vars| {
+                    vars.iter()
+                        .map(|(key, value)| (key.clone(), Value::from(value.as_str())))
+                        .collect()
+                }),
             })
             .expect("Failed to serialize port+ancestry to JSON")
         })
-        .collect();
-
-    let output = wrap(enriched, docker_status);
-    println!(
-        "{}",
-        serde_json::to_string_pretty(&output).expect("Failed to serialize to JSON")
-    );
+        .collect()
 }
 
 fn ports_to_values(ports: &[PortInfo]) -> Vec<Value> {
@@ -53,13 +153,166 @@ fn ports_to_values(ports: &[PortInfo]) -> Vec<Value> {
         .collect()
 }
 
-/// Wrap the per-port array in the top-level object that carries docker
-/// reachability. Two flat fields (`docker_status`, `docker_reason`)
-/// rather than a nested enum so `jq '.docker_status'` is one hop.
+/// Version of the top-level JSON envelope. Bump when a field is
+/// removed or changes meaning; new optional fields don't need a bump.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Wrap the per-port array in the top-level object that carries the
+/// schema version, capture time, and docker reachability. Two flat
+/// docker fields (`docker_status`, `docker_reason`) rather than a
+/// nested enum so `jq '.docker_status'` is one hop.
 fn wrap(ports: Vec<Value>, status: &DockerStatus) -> Value {
     json!({
+        "version": SCHEMA_VERSION,
+        "generated_at": rfc3339_utc(SystemTime::now()),
         "ports": ports,
         "docker_status": status.as_tag(),
         "docker_reason": status.reason(),
     })
 }
+
+/// Print one `--watch --format json-diff` event: the ports that appeared
+/// and disappeared since the previous refresh. Always a single line, so
+/// the stream can be consumed line by line like NDJSON.
+#[cfg(feature = "watch")]
+pub fn print_watch_diff(
+    out: &mut dyn Write,
+    appeared: &[&PortInfo],
+    disappeared: &[PortInfo],
+) -> io::Result<()> {
+    let event = json!({
+        "timestamp": rfc3339_utc(SystemTime::now()),
+        "appeared": appeared,
+        "disappeared": disappeared,
+    });
+    writeln!(out, "{}", serde_json::to_string(&event)?)
+}
+
+/// `serialize_with` for optional timestamps in [`PortInfo`], in the same
+/// format as `generated_at`.
+pub(crate) fn serialize_time<S: serde::Serializer>(
+    time: &Option<SystemTime>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match time {
+        Some(time) => serializer.serialize_str(&rfc3339_utc(*time)),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// Format a timestamp as RFC 3339 UTC (`2026-01-03T14:05:09Z`).
+///
+/// Hand-rolled so the envelope doesn't depend on `chrono`, which is
+/// only pulled in by the `history` feature.
+fn rfc3339_utc(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let (days, rem) = (secs / 86_400, secs % 86_400);
+    let (hour, min, sec) = (rem / 3600, rem % 3600 / 60, rem % 60);
+
+    // Civil-from-days (Howard Hinnant), valid for any date after 1970.
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z % 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+
+    format!("{year:04}-{month:02}-{day:02}T{hour:02}:{min:02}:{sec:02}Z")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn rfc3339_utc_epoch() {
+        assert_eq!(rfc3339_utc(UNIX_EPOCH), "1970-01-01T00:00:00Z");
+    }
+
+    #[test]
+    fn rfc3339_utc_known_timestamps() {
+        let at = |secs| UNIX_EPOCH + Duration::from_secs(secs);
+        assert_eq!(rfc3339_utc(at(951_782_400)), "2000-02-29T00:00:00Z");
+        assert_eq!(rfc3339_utc(at(1_767_449_109)), "2026-01-03T14:05:09Z");
+    }
+
+    #[test]
+    fn ports_with_ancestry_flattens_port_and_nests_ancestry() {
+        use crate::ancestry::{Ancestor, SourceType};
+        use crate::types::Protocol;
+
+        let port = PortInfo {
+            port: 8080,
+            protocol: Protocol::Tcp,
+            pid: 42,
+            process_name: "node".to_string(),
+            address: "0.0.0.0:8080".to_string(),
+            ..Default::default()
+        };
+        let ancestry = ProcessAncestry {
+            chain: vec![Ancestor {
+                pid: 42,
+                name: "node".to_string(),
+                ppid: 1,
+            }],
+            source: SourceType::Shell,
+            warnings: Vec::new(),
+            git_context: None,
+            systemd_unit: None,
+            launchd_label: None,
+            started_at: None,
+            uptime_secs: None,
+        };
+        let map = HashMap::from([(42, ancestry)]);
+
+        let env = HashMap::from([(42, vec![("NODE_ENV".to_string(), "production".to_string())])]);
+
+        let values = ports_with_ancestry(&[port.clone(), PortInfo { pid: 7, ..port }], &map, &env);
+
+        assert_eq!(values[0]["port"], 8080);
+        assert_eq!(values[0]["pid"], 42);
+        assert_eq!(values[0]["ancestry"]["source"], "shell");
+        assert_eq!(values[0]["ancestry"]["chain"][0]["name"], "node");
+        assert_eq!(values[0]["env"]["NODE_ENV"], "production");
+        assert!(values[1].get("ancestry").is_none());
+        assert!(values[1].get("env").is_none());
+    }
+
+    #[test]
+    fn wrap_includes_version_and_timestamp() {
+        let v = wrap(Vec::new(), &DockerStatus::NotQueried);
+        assert_eq!(v["version"], SCHEMA_VERSION);
+        assert!(v["generated_at"].as_str().unwrap().ends_with('Z'));
+        assert!(v["ports"].as_array().unwrap().is_empty());
+    }
+
+    #[test]
+    fn service_summary_keeps_count_order() {
+        let port = |port, service: Option<&str>| PortInfo {
+            port,
+            service_name: service.map(str::to_string),
+            ..Default::default()
+        };
+        let ports = [
+            port(80, Some("http")),
+            port(8080, Some("http")),
+            port(22, Some("ssh")),
+            port(3000, None),
+        ];
+        assert_eq!(
+            service_summary(&ports),
+            serde_json::json!([
+                { "service": "http", "count": 2 },
+                { "service": "ssh", "count": 1 },
+                { "service": "<unknown>", "count": 1 },
+            ])
+        );
+    }
+}
@@ -1,2 +1,147 @@
+pub mod file;
 pub mod json;
+pub mod porcelain;
 pub mod table;
+
+use std::borrow::Cow;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use anyhow::{Context, Result};
+use comfy_table::{ContentArrangement, Table};
+
+use crate::cli::ColorChoice;
+
+static COLOR: OnceLock<ColorChoice> = OnceLock::new();
+static MAX_WIDTH: OnceLock<Option<usize>> = OnceLock::new();
+static OUTPUT_PATH: OnceLock<PathBuf> = OnceLock::new();
+
+/// Record `--output`. Call once, before the first document is written.
+pub fn init_output(path: Option<PathBuf>) {
+    if let Some(path) = path {
+        let _ = OUTPUT_PATH.set(path);
+    }
+}
+
+/// Hand `print` stdout, or with `--output` a file that replaces the
+/// target only once the whole document was written (see [`file`]).
+pub fn write_output(print: impl FnOnce(&mut dyn Write) -> io::Result<()>) -> Result<()> {
+    match OUTPUT_PATH.get() {
+        None => {
+            let mut out = io::stdout().lock();
+            print(&mut out)?;
+            out.flush()?;
+            Ok(())
+        }
+        Some(path) => {
+            let mut out = file::AtomicFile::create(path)?;
+            print(&mut out).with_context(|| format!("writing {}", path.display()))?;
+            out.commit()
+        }
+    }
+}
+
+/// Floor for the terminal-derived cell width, so names stay recognizable
+/// on very narrow terminals.
+const MIN_AUTO_WIDTH: usize = 12;
+
+/// Apply `--color` to both styling paths: `colored` for plain lines and
+/// comfy-table for tables. `Auto` leaves each crate's own TTY and
+/// `NO_COLOR` detection in charge.
+pub fn init_color(choice: ColorChoice) {
+    match choice {
+        ColorChoice::Always => colored::control::set_override(true),
+        ColorChoice::Never => colored::control::set_override(false),
+        ColorChoice::Auto => {}
+    }
+    let _ = COLOR.set(choice);
+}
+
+/// A fresh table that honors the `--color` choice.
+pub fn new_table() -> Table {
+    let mut table = Table::new();
+    match COLOR.get() {
+        Some(ColorChoice::Always) => {
+            table.enforce_styling();
+        }
+        Some(ColorChoice::Never) => {
+            table.force_no_tty();
+        }
+        Some(ColorChoice::Auto) | None => {}
+    }
+    table
+}
+
+/// Record `--max-width`. `Some(0)` turns clipping off; `None` derives a
+/// limit from the terminal width (a quarter of it), and leaves piped
+/// output unclipped unless `COLUMNS` says otherwise.
+pub fn init_max_width(flag: Option<usize>) {
+    let width = match flag {
+        Some(0) => None,
+        Some(n) => Some(n),
+        None => terminal_width().map(|w| (usize::from(w) / 4).max(MIN_AUTO_WIDTH)),
+    };
+    let _ = MAX_WIDTH.set(width);
+}
+
+/// Columns available for tables: `COLUMNS` when set to a positive number,
+/// otherwise the terminal's width when stdout is a TTY.
+fn terminal_width() -> Option<u16> {
+    columns_from(std::env::var("COLUMNS").ok()).or_else(|| Table::new().width())
+}
+
+fn columns_from(value: Option<String>) -> Option<u16> {
+    value?.trim().parse().ok().filter(|&w| w > 0)
+}
+
+/// Let comfy-table shrink and wrap columns to fit [`terminal_width`].
+/// Without a known width (piped output) rows keep their natural length.
+pub fn fit_to_terminal(table: &mut Table) {
+    table.set_content_arrangement(ContentArrangement::Dynamic);
+    if let Some(width) = terminal_width() {
+        table.set_width(width);
+    }
+}
+
+/// Clip a free-form table cell (process, container, address) to the
+/// `--max-width` limit. JSON output never goes through this.
+pub fn clip(s: &str) -> Cow<'_, str> {
+    match MAX_WIDTH.get().copied().flatten() {
+        Some(max) => clip_to(s, max),
+        None => Cow::Borrowed(s),
+    }
+}
+
+fn clip_to(s: &str, max: usize) -> Cow<'_, str> {
+    if s.chars().count() <= max {
+        return Cow::Borrowed(s);
+    }
+    let kept: String = s.chars().take(max.saturating_sub(1)).collect();
+    Cow::Owned(format!("{kept}…"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clip_to_adds_ellipsis_only_when_needed() {
+        assert_eq!(clip_to("nginx", 8), "nginx");
+        assert_eq!(clip_to("postgres", 8), "postgres");
+        assert_eq!(
+            clip_to("my-really-long-compose-service-name", 12),
+            "my-really-l…"
+        );
+        assert_eq!(clip_to("ünïcödé-name", 5), "ünïc…");
+    }
+
+    #[test]
+    fn columns_must_be_a_positive_number() {
+        assert_eq!(columns_from(Some("120".to_string())), Some(120));
+        assert_eq!(columns_from(Some(" 80\n".to_string())), Some(80));
+        assert_eq!(columns_from(Some("0".to_string())), None);
+        assert_eq!(columns_from(Some("wide".to_string())), None);
+        assert_eq!(columns_from(None), None);
+    }
+}
@@ -0,0 +1,69 @@
+//! `--porcelain`: a fixed, tab-separated line per port for scripts.
+//!
+//! The format is a stable interface. Every line has the same seven fields
+//! in the same order, `PORT PROTO PID PROCESS ADDRESS REMOTE CONTAINER`,
+//! with `-` for an empty field. There are no colors, headers, or summary.
+//! New fields are never added here; use `--json` for those.
+
+use std::io::{self, Write};
+
+use crate::types::PortInfo;
+
+pub fn print_ports(out: &mut dyn Write, ports: &[PortInfo]) -> io::Result<()> {
+    for port in ports {
+        writeln!(out, "{}", line(port))?;
+    }
+    Ok(())
+}
+
+fn line(port: &PortInfo) -> String {
+    [
+        port.port.to_string(),
+        port.protocol.to_string(),
+        port.pid.to_string(),
+        field(&port.process_name),
+        field(&port.address),
+        field(port.remote_address.as_deref().unwrap_or("")),
+        field(port.container.as_deref().unwrap_or("")),
+    ]
+    .join("\t")
+}
+
+/// `-` for an empty value. Tabs and newlines (possible in process and
+/// container names) become spaces so a row always splits into seven
+/// fields.
+fn field(value: &str) -> String {
+    if value.is_empty() {
+        return "-".to_string();
+    }
+    value.replace(['\t', '\n', '\r'], " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Protocol;
+
+    #[test]
+    fn every_line_has_seven_fields() {
+        let mut port = PortInfo {
+            port: 5432,
+            protocol: Protocol::Tcp,
+            pid: 42,
+            process_name: "post\tgres".to_string(),
+            address: "127.0.0.1:5432".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            line(&port),
+            "5432\ttcp\t42\tpost gres\t127.0.0.1:5432\t-\t-"
+        );
+
+        port.remote_address = Some("10.0.0.7:50412".to_string());
+        port.container = Some("db".to_string());
+        assert_eq!(
+            line(&port),
+            "5432\ttcp\t42\tpost gres\t127.0.0.1:5432\t10.0.0.7:50412\tdb"
+        );
+    }
+}
@@ -1,10 +1,17 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::io::{self, Write};
+use std::time::SystemTime;
 
 use colored::Colorize;
-use comfy_table::{Cell, Color, Table};
+use comfy_table::{Cell, Color};
 
 use crate::ancestry::ProcessAncestry;
-use crate::types::{DockerStatus, PortInfo};
+use crate::output;
+use crate::platform;
+use crate::types::{
+    DockerStatus, PortInfo, Protocol, RemoteGroup, DATA_STORE_PORTS, REMOTE_ADMIN_PORTS,
+    UNKNOWN_SERVICE,
+};
 
 /// Print a yellow stderr warning when the Docker daemon was probed and
 /// found unreachable. Silent for `Ok` and `NotQueried`.
@@ -21,27 +28,312 @@ pub(crate) fn print_warning(status: &DockerStatus) {
     }
 }
 
-pub fn print_ports(ports: &[PortInfo]) {
-    print_ports_inner(ports, &HashSet::new())
+/// Print a dimmed stderr note when running under WSL, where only the
+/// Linux VM's sockets are visible — not ports bound by Windows processes.
+pub(crate) fn print_platform_note() {
+    if platform::is_wsl() {
+        eprintln!(
+            "{}",
+            "note: running under WSL; ports opened by Windows programs are not visible here \
+             (run ports natively on Windows to see them)"
+                .dimmed()
+        );
+    }
+}
+
+/// Print a dimmed stderr note when `--limit` cut the listing short, so a
+/// truncated table isn't mistaken for everything that matched.
+pub(crate) fn print_limit_note(shown: usize, total: usize) {
+    if shown < total {
+        eprintln!("{}", format!("showing {shown} of {total}").dimmed());
+    }
+}
+
+/// With `--security`, say so when nothing was flagged, since the RISK
+/// column is then left out.
+pub(crate) fn print_security_note(ports: &[PortInfo]) {
+    if ports.iter().all(|p| p.risk.is_none()) {
+        eprintln!(
+            "{}",
+            "note: no listener matched a security risk (exposed admin service \
+             or data store, privileged port beyond loopback)"
+                .dimmed()
+        );
+    }
+}
+
+/// With `--anomaly`, a stderr warning per listener whose process isn't
+/// one its port's service normally runs; a dual-stack pair warns once.
+pub(crate) fn print_anomalies(ports: &[PortInfo]) {
+    let mut seen = HashSet::new();
+    let mut flagged = false;
+    for port in ports {
+        if let Some(expected) = &port.expected_processes {
+            flagged = true;
+            if seen.insert((port.port, port.pid)) {
+                eprintln!("{}", anomaly_line(port, expected).yellow());
+            }
+        }
+    }
+    if !flagged {
+        eprintln!(
+            "{}",
+            "note: every listener on a well-known port runs an expected process".dimmed()
+        );
+    }
+}
+
+fn anomaly_line(port: &PortInfo, expected: &[String]) -> String {
+    let expected = match expected {
+        [only] => only.clone(),
+        _ => format!("one of {}", expected.join(", ")),
+    };
+    format!(
+        "warning: port {} bound by `{}` (expected {expected})",
+        port.port, port.process_name
+    )
+}
+
+/// Print a dimmed stderr hint when some rows have an unknown owner, which
+/// almost always means we lacked permission to inspect the process.
+pub(crate) fn print_attribution_hint(ports: &[PortInfo]) {
+    if let Some(hint) = attribution_hint(ports, platform::is_root()) {
+        eprintln!("{}", hint.dimmed());
+    }
+}
+
+/// The sudo suggestion is dropped when we're already root: the owner is
+/// then hidden by something privilege can't fix, such as another PID
+/// namespace.
+fn attribution_hint(ports: &[PortInfo], root: bool) -> Option<String> {
+    let unknown = ports
+        .iter()
+        .filter(|p| p.is_unattributed() && !p.is_kernel())
+        .count();
+    if unknown == 0 {
+        return None;
+    }
+    let note =
+        format!("note: {unknown} socket(s) belong to processes we can't inspect (shown as PID 0)");
+    Some(if root {
+        note
+    } else {
+        format!("{note}; rerun with --sudo for full attribution")
+    })
+}
+
+/// Rough exposure class of a port, used to color the PORT cell.
+#[derive(Debug, PartialEq, Eq)]
+enum PortCategory {
+    /// Remote shells and desktops: ssh, telnet, RDP, VNC, WinRM.
+    RemoteAdmin,
+    /// Databases and caches that rarely belong on a public interface.
+    DataStore,
+    /// Linux's default ephemeral range (32768+), mostly client-side ports.
+    Ephemeral,
+    Other,
+}
+
+fn classify(port: u16) -> PortCategory {
+    if REMOTE_ADMIN_PORTS.contains(&port) {
+        PortCategory::RemoteAdmin
+    } else if DATA_STORE_PORTS.contains(&port) {
+        PortCategory::DataStore
+    } else if port >= 32768 {
+        PortCategory::Ephemeral
+    } else {
+        PortCategory::Other
+    }
+}
+
+/// PORT cell color as a quick exposure heatmap: remote-admin services
+/// reachable beyond loopback in red, data stores in yellow, ephemeral
+/// ports muted. Goes through comfy-table, so `--color` applies.
+fn port_color(port: &PortInfo) -> Color {
+    match classify(port.port) {
+        PortCategory::RemoteAdmin if !port.is_loopback() => Color::Red,
+        PortCategory::RemoteAdmin | PortCategory::DataStore => Color::Yellow,
+        PortCategory::Ephemeral => Color::DarkGrey,
+        PortCategory::Other => Color::Cyan,
+    }
+}
+
+/// `wide` adds columns that are too noisy for the default view: RETR (TCP
+/// retransmits) and EXE (executable path), each shown when any row carries
+/// a value.
+///
+/// `dedup` collapses dual-stack twins (see [`collapse_dual_stack`]) into
+/// one row annotated `(dual-stack)`.
+pub fn print_ports(
+    out: &mut dyn Write,
+    ports: &[PortInfo],
+    wide: bool,
+    dedup: bool,
+) -> io::Result<()> {
+    if dedup {
+        let (kept, dual_stack) = collapse_dual_stack(ports);
+        print_ports_inner(out, &kept, &HashSet::new(), &dual_stack, wide)
+    } else {
+        print_ports_inner(out, ports, &HashSet::new(), &HashSet::new(), wide)
+    }
 }
 
 #[allow(dead_code)] // only used by the `watch` feature
-pub fn print_ports_watch(ports: &[PortInfo], new_ports: &HashSet<&PortInfo>) {
-    print_ports_inner(ports, new_ports)
+pub fn print_ports_watch(
+    out: &mut dyn Write,
+    ports: &[PortInfo],
+    new_ports: &HashSet<&PortInfo>,
+    wide: bool,
+) -> io::Result<()> {
+    print_ports_inner(out, ports, new_ports, &HashSet::new(), wide)
 }
 
-fn print_ports_inner(ports: &[PortInfo], new_ports: &HashSet<&PortInfo>) {
+/// Watch mode: list ports that were present on the previous refresh but
+/// are gone now. They can't be rows of the table (they aren't in the
+/// current listing), so they get their own section beneath it.
+#[allow(dead_code)] // only used by the `watch` feature
+pub fn print_ports_gone(out: &mut dyn Write, gone: &[PortInfo]) -> io::Result<()> {
+    if gone.is_empty() {
+        return Ok(());
+    }
+    writeln!(out, "\n{}", "Gone since last refresh:".red().bold())?;
+    for port in gone {
+        writeln!(out, "  {}", gone_line(port).red().strikethrough())?;
+    }
+    Ok(())
+}
+
+fn gone_line(port: &PortInfo) -> String {
+    let endpoint = match &port.remote_address {
+        Some(remote) => format!("{} -> {}", port.address, remote),
+        None => port.address.clone(),
+    };
+    format!(
+        "{} {} ({}) {}",
+        port_label(port),
+        port.process_name,
+        port.pid,
+        endpoint
+    )
+}
+
+/// Port, protocol, PID, process name, network namespace, and whether the
+/// bind is a wildcard (as opposed to loopback).
+type TwinKey<'a> = (u16, Protocol, u32, &'a str, Option<&'a str>, bool);
+
+/// Collapse listening rows that differ only in address family: same port,
+/// protocol, PID, process, and namespace, one bound on IPv4 and one on IPv6, both
+/// wildcard or both loopback. The first of each pair is kept (so `--sort`
+/// order holds) and its index returned in the set. Connection rows and
+/// specific addresses are never merged.
+fn collapse_dual_stack(ports: &[PortInfo]) -> (Vec<PortInfo>, HashSet<usize>) {
+    let mut kept: Vec<PortInfo> = Vec::with_capacity(ports.len());
+    let mut dual_stack = HashSet::new();
+    // Rows still waiting for a twin: key -> (index in `kept`, is IPv6).
+    let mut unpaired: HashMap<TwinKey, (usize, bool)> = HashMap::new();
+    for port in ports {
+        let scope = if port.remote_address.is_some() {
+            None
+        } else if port.is_wildcard() {
+            Some(true)
+        } else if port.is_loopback() {
+            Some(false)
+        } else {
+            None
+        };
+        let Some(wildcard) = scope else {
+            kept.push(port.clone());
+            continue;
+        };
+        let key = (
+            port.port,
+            port.protocol,
+            port.pid,
+            port.process_name.as_str(),
+            port.netns.as_deref(),
+            wildcard,
+        );
+        let v6 = is_v6(port);
+        match unpaired.get(&key) {
+            Some(&(i, other_v6)) if other_v6 != v6 => {
+                dual_stack.insert(i);
+                unpaired.remove(&key);
+            }
+            _ => {
+                unpaired.insert(key, (kept.len(), v6));
+                kept.push(port.clone());
+            }
+        }
+    }
+    (kept, dual_stack)
+}
+
+/// Compact time since `since` for the AGE column: `<1m`, `42m`, `5h 12m`,
+/// `3d 4h`.
+pub(crate) fn format_age(now: SystemTime, since: SystemTime) -> String {
+    let mins = now.duration_since(since).map_or(0, |d| d.as_secs()) / 60;
+    let (days, hours, mins) = (mins / 1440, mins / 60 % 24, mins % 60);
+    if days > 0 {
+        format!("{days}d {hours}h")
+    } else if hours > 0 {
+        format!("{hours}h {mins}m")
+    } else if mins > 0 {
+        format!("{mins}m")
+    } else {
+        "<1m".to_string()
+    }
+}
+
+/// Compact rate for the RX/s and TX/s columns: `0`, `512 B`, `1.5 KB`,
+/// `12.0 MB`.
+fn format_rate(bytes_per_sec: u64) -> String {
+    const KB: f64 = 1024.0;
+    const MB: f64 = KB * 1024.0;
+    let rate = bytes_per_sec as f64;
+    if bytes_per_sec == 0 {
+        "0".to_string()
+    } else if rate >= MB {
+        format!("{:.1} MB", rate / MB)
+    } else if rate >= KB {
+        format!("{:.1} KB", rate / KB)
+    } else {
+        format!("{bytes_per_sec} B")
+    }
+}
+
+fn print_ports_inner(
+    out: &mut dyn Write,
+    ports: &[PortInfo],
+    new_ports: &HashSet<&PortInfo>,
+    dual_stack: &HashSet<usize>,
+    wide: bool,
+) -> io::Result<()> {
     if ports.is_empty() {
-        println!("{}", "No results found".yellow());
-        return;
+        writeln!(out, "{}", "No results found".yellow())?;
+        return Ok(());
     }
 
     let has_remote = ports.iter().any(|p| p.remote_address.is_some());
     let has_container = ports.iter().any(|p| p.container.is_some());
+    let has_netns = ports.iter().any(|p| p.netns.is_some());
     let has_service = ports.iter().any(|p| p.service_name.is_some());
     let has_framework = ports.iter().any(|p| p.framework.is_some());
+    let has_retransmits = wide && ports.iter().any(|p| p.retransmits.is_some());
+    let has_exe = wide && ports.iter().any(|p| p.exe_path.is_some());
+    let has_age = ports
+        .iter()
+        .any(|p| p.first_seen.is_some() || p.opened_after.is_some());
+    let has_conn_count = ports.iter().any(|p| p.conn_count.is_some());
+    let has_risk = ports.iter().any(|p| p.risk.is_some());
+    let has_allowed = ports.iter().any(|p| p.allowed.is_some());
+    let has_throughput = ports.iter().any(|p| p.rx_bytes_per_sec.is_some());
+    let now = SystemTime::now();
+    // Listening sockets are all LISTEN, so the column only earns its
+    // space in the connections view.
+    let has_state = has_remote && ports.iter().any(|p| p.state.is_some());
 
-    let mut table = Table::new();
+    let mut table = output::new_table();
+    output::fit_to_terminal(&mut table);
 
     // Build header based on what columns we need
     let mut headers = vec!["PORT", "PROTO", "PID", "PROCESS"];
@@ -54,31 +346,77 @@ fn print_ports_inner(ports: &[PortInfo], new_ports: &HashSet<&PortInfo>) {
     if has_container {
         headers.push("CONTAINER");
     }
+    if has_netns {
+        headers.push("NETNS");
+    }
     if has_remote {
         headers.push("LOCAL");
         headers.push("REMOTE");
+        if has_state {
+            headers.push("STATE");
+        }
     } else {
         headers.push("ADDRESS");
     }
+    if has_risk {
+        headers.push("RISK");
+    }
+    if has_allowed {
+        headers.push("ALLOWED");
+    }
+    if has_conn_count {
+        headers.push("CONNS");
+    }
+    if has_throughput {
+        headers.push("RX/s");
+        headers.push("TX/s");
+    }
+    if has_age {
+        headers.push("AGE");
+    }
+    if has_retransmits {
+        headers.push("RETR");
+    }
+    if has_exe {
+        headers.push("EXE");
+    }
     table.set_header(headers);
 
-    for port in ports {
+    for (i, port) in ports.iter().enumerate() {
         let is_new = new_ports.contains(port);
-        let row_color = if is_new { Color::Green } else { Color::Reset };
+        // Retransmitting connections are flagged in yellow with --wide.
+        let retransmitting = has_retransmits && port.retransmits.unwrap_or(0) > 0;
+        let row_color = if is_new {
+            Color::Green
+        } else if retransmitting {
+            Color::Yellow
+        } else {
+            Color::Reset
+        };
         let proto_color = if is_new {
             Color::Green
         } else {
             match port.protocol {
-                crate::types::Protocol::Tcp => Color::Cyan,
-                crate::types::Protocol::Udp => Color::Magenta,
+                Protocol::Tcp => Color::Cyan,
+                Protocol::Udp => Color::Magenta,
             }
         };
 
+        let process_name = output::clip(&port.process_name);
+        let process_cell = if port.warnings.is_empty() {
+            Cell::new(process_name).fg(row_color)
+        } else {
+            Cell::new(format!("{process_name} ⚠")).fg(Color::Red)
+        };
         let mut row = vec![
-            Cell::new(port.port).fg(if is_new { Color::Green } else { Color::Cyan }),
+            Cell::new(port.port).fg(if is_new {
+                Color::Green
+            } else {
+                port_color(port)
+            }),
             Cell::new(port.protocol).fg(proto_color),
             Cell::new(port.pid).fg(row_color),
-            Cell::new(&port.process_name).fg(row_color),
+            process_cell,
         ];
 
         if has_service {
@@ -97,45 +435,315 @@ fn print_ports_inner(ports: &[PortInfo], new_ports: &HashSet<&PortInfo>) {
         }
 
         if has_container {
-            let container = port.container.as_deref().unwrap_or("-");
+            let container = container_label(port);
             // Containers get yellow color for visibility
             let container_color = if port.container.is_some() && !is_new {
                 Color::Yellow
             } else {
                 row_color
             };
-            row.push(Cell::new(container).fg(container_color));
+            row.push(Cell::new(output::clip(&container)).fg(container_color));
+        }
+
+        if has_netns {
+            let netns = port.netns.as_deref().unwrap_or("host");
+            row.push(Cell::new(output::clip(netns)).fg(row_color));
         }
 
-        row.push(Cell::new(&port.address).fg(row_color));
+        let mut address = output::clip(&port.address).into_owned();
+        if dual_stack.contains(&i) {
+            address.push_str(" (dual-stack)");
+        }
+        row.push(Cell::new(address).fg(row_color));
 
         if has_remote {
-            let remote = port.remote_address.as_deref().unwrap_or("-");
-            row.push(Cell::new(remote).fg(row_color));
+            let remote = port.remote_display();
+            let remote = remote.as_deref().unwrap_or("-");
+            row.push(Cell::new(output::clip(remote)).fg(row_color));
+        }
+
+        if has_state {
+            let state = port
+                .state
+                .map_or_else(|| "-".to_string(), |s| s.to_string());
+            row.push(Cell::new(state).fg(row_color));
+        }
+
+        if has_risk {
+            match port.risk {
+                Some(risk) => row.push(Cell::new(risk).fg(Color::Red)),
+                None => row.push(Cell::new("-").fg(row_color)),
+            }
+        }
+
+        if has_allowed {
+            match port.allowed {
+                Some(false) => row.push(Cell::new("no").fg(Color::Red)),
+                Some(true) => row.push(Cell::new("yes").fg(row_color)),
+                None => row.push(Cell::new("-").fg(row_color)),
+            }
+        }
+
+        if has_conn_count {
+            let conns = port
+                .conn_count
+                .map_or_else(|| "-".to_string(), |n| n.to_string());
+            row.push(Cell::new(conns).fg(row_color));
+        }
+
+        if has_throughput {
+            for rate in [port.rx_bytes_per_sec, port.tx_bytes_per_sec] {
+                let rate = rate.map_or_else(|| "-".to_string(), format_rate);
+                row.push(Cell::new(rate).fg(row_color));
+            }
+        }
+
+        if has_age {
+            let age = match (port.first_seen, port.opened_after) {
+                (Some(since), _) => format_age(now, since),
+                (None, Some(after)) => format!("≤{}", format_age(now, after)),
+                (None, None) => "-".to_string(),
+            };
+            row.push(Cell::new(age).fg(row_color));
+        }
+
+        if has_retransmits {
+            let retr = port
+                .retransmits
+                .map_or_else(|| "-".to_string(), |n| n.to_string());
+            row.push(Cell::new(retr).fg(row_color));
+        }
+
+        if has_exe {
+            let exe = port.exe_path.as_deref().unwrap_or("-");
+            row.push(Cell::new(exe).fg(row_color));
         }
 
         table.add_row(row);
     }
 
-    println!("{table}");
+    writeln!(out, "{table}")?;
 
     let count_str = ports.len().to_string();
     if new_ports.is_empty() {
-        println!("\n{} result(s)", count_str.green());
+        writeln!(out, "\n{} result(s)", count_str.green())?;
     } else {
-        println!(
+        writeln!(
+            out,
             "\n{} result(s) ({} new)",
             count_str.green(),
             new_ports.len().to_string().green().bold()
+        )?;
+    }
+    writeln!(out, "{}", summary_line(ports).dimmed())?;
+    if let Some(legend) = health_legend(ports) {
+        writeln!(out, "{}", legend.red())?;
+    }
+    if ports.iter().any(|p| p.opened_after.is_some()) {
+        let note = "≤ AGE: upper bound, how long the owning process has been running";
+        writeln!(out, "{}", note.dimmed())?;
+    }
+    Ok(())
+}
+
+/// One `⚠` legend line naming the warnings present in `ports`, e.g.
+/// `⚠ deleted-binary: 2 process(es)`. `None` when nothing is flagged.
+fn health_legend(ports: &[PortInfo]) -> Option<String> {
+    let mut flagged: Vec<(String, HashSet<u32>)> = Vec::new();
+    for port in ports {
+        for warning in &port.warnings {
+            let name = warning.to_string();
+            match flagged.iter_mut().find(|(n, _)| *n == name) {
+                Some((_, pids)) => {
+                    pids.insert(port.pid);
+                }
+                None => flagged.push((name, HashSet::from([port.pid]))),
+            }
+        }
+    }
+    if flagged.is_empty() {
+        return None;
+    }
+    let parts: Vec<String> = flagged
+        .iter()
+        .map(|(name, pids)| format!("{name}: {} process(es)", pids.len()))
+        .collect();
+    Some(format!("⚠ {}", parts.join(", ")))
+}
+
+/// Print one line per process with its ports collapsed into a
+/// comma-separated list, e.g. `nginx (12345): 80/tcp, 443/tcp, 80/tcp6`.
+///
+/// When an ancestry map is supplied (`--why`), the detected source is
+/// appended in brackets, as the interactive picker does.
+pub fn print_ports_grouped(
+    out: &mut dyn Write,
+    ports: &[PortInfo],
+    ancestry_map: Option<&HashMap<u32, ProcessAncestry>>,
+) -> io::Result<()> {
+    if ports.is_empty() {
+        writeln!(out, "{}", "No results found".yellow())?;
+        return Ok(());
+    }
+
+    let groups = group_by_process(ports);
+    for (pid, entries) in &groups {
+        let first = entries[0];
+        let name = match &first.container {
+            Some(c) => format!("{} ({})", first.process_name.bold(), c.yellow()),
+            None => first.process_name.bold().to_string(),
+        };
+        let labels: Vec<String> = entries.iter().map(|p| port_label(p)).collect();
+        let mut line = format!(
+            "{} ({}): {}",
+            name,
+            pid.to_string().yellow(),
+            labels.join(", ").cyan()
         );
+        if let Some(a) = ancestry_map.and_then(|m| m.get(pid)) {
+            line.push_str(&format!(" [{}]", a.source.to_string().green()));
+        }
+        writeln!(out, "{line}")?;
+    }
+
+    writeln!(
+        out,
+        "\n{} result(s) across {} process(es)",
+        ports.len().to_string().green(),
+        groups.len().to_string().green()
+    )?;
+    writeln!(out, "{}", summary_line(ports).dimmed())?;
+    Ok(())
+}
+
+/// Group ports by PID, keeping processes in order of first appearance
+/// so the active `--sort` still decides the line order.
+fn group_by_process(ports: &[PortInfo]) -> Vec<(u32, Vec<&PortInfo>)> {
+    let mut groups: Vec<(u32, Vec<&PortInfo>)> = Vec::new();
+    let mut index: HashMap<u32, usize> = HashMap::new();
+    for port in ports {
+        match index.get(&port.pid) {
+            Some(&i) => groups[i].1.push(port),
+            None => {
+                index.insert(port.pid, groups.len());
+                groups.push((port.pid, vec![port]));
+            }
+        }
     }
+    groups
+}
+
+/// `80/tcp`, or `80/tcp6` when the socket is bound to an IPv6 address.
+fn port_label(port: &PortInfo) -> String {
+    let suffix = if is_v6(port) { "6" } else { "" };
+    format!("{}/{}{}", port.port, port.protocol, suffix)
+}
+
+/// Whether the socket is bound to an IPv6 address (including IPv4-mapped
+/// ones, which only an IPv6 socket can carry).
+fn is_v6(port: &PortInfo) -> bool {
+    port.address
+        .rsplit_once(':')
+        .map(|(host, _)| host.contains(':'))
+        .unwrap_or(false)
+}
+
+/// `--by-remote`: one row per remote host with its connection count and
+/// the local ports involved, busiest first.
+pub fn print_remote_groups(out: &mut dyn Write, groups: &[RemoteGroup]) -> io::Result<()> {
+    if groups.is_empty() {
+        writeln!(out, "{}", "No connections found".yellow())?;
+        return Ok(());
+    }
+
+    let mut table = output::new_table();
+    output::fit_to_terminal(&mut table);
+    table.set_header(vec!["REMOTE", "CONNS", "LOCAL PORTS"]);
+    for group in groups {
+        table.add_row(vec![
+            Cell::new(&group.remote).fg(Color::Cyan),
+            Cell::new(group.count),
+            Cell::new(local_ports_label(&group.local_ports)),
+        ]);
+    }
+    writeln!(out, "{table}")?;
+
+    let conns: usize = groups.iter().map(|g| g.count).sum();
+    writeln!(
+        out,
+        "\n{} connection(s) to {} remote host(s)",
+        conns.to_string().green(),
+        groups.len().to_string().green()
+    )?;
+    Ok(())
+}
+
+/// Comma-separated local ports, e.g. `80, 443`.
+fn local_ports_label(ports: &BTreeSet<u16>) -> String {
+    ports
+        .iter()
+        .map(u16::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// `--summary`: one line of port counts per service, e.g.
+/// `http: 2, postgres: 1, <unknown>: 5`.
+pub fn print_service_summary(out: &mut dyn Write, ports: &[PortInfo]) -> io::Result<()> {
+    if ports.is_empty() {
+        writeln!(out, "{}", "No results found".yellow())?;
+        return Ok(());
+    }
+    writeln!(out, "{}", service_summary_line(ports))?;
+    Ok(())
+}
+
+fn service_summary_line(ports: &[PortInfo]) -> String {
+    PortInfo::count_by_service(ports)
+        .iter()
+        .map(|(name, count)| format!("{}: {count}", name.as_deref().unwrap_or(UNKNOWN_SERVICE)))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// CONTAINER cell: the name plus the port inside the container when Docker
+/// reported one, e.g. `web:80` for host port 3000 published as `3000->80`.
+fn container_label(port: &PortInfo) -> String {
+    match (&port.container, port.container_port) {
+        (Some(name), Some(inner)) => format!("{name}:{inner}"),
+        (Some(name), None) => name.clone(),
+        (None, _) => "-".to_string(),
+    }
+}
+
+/// One-line breakdown printed under the result count, mirroring the
+/// stats line in `top`.
+fn summary_line(ports: &[PortInfo]) -> String {
+    let tcp = ports.iter().filter(|p| p.protocol == Protocol::Tcp).count();
+    let udp = ports.iter().filter(|p| p.protocol == Protocol::Udp).count();
+    let processes = ports
+        .iter()
+        .filter(|p| !p.is_unattributed())
+        .map(|p| p.pid)
+        .collect::<HashSet<_>>()
+        .len();
+    let containers = ports
+        .iter()
+        .filter_map(|p| p.container.as_deref())
+        .collect::<HashSet<_>>()
+        .len();
+    format!("TCP: {tcp}  UDP: {udp}  Processes: {processes}  Containers: {containers}")
 }
 
 /// Print ports table with an extra SOURCE column from ancestry data.
-pub fn print_ports_why(ports: &[PortInfo], ancestry_map: &HashMap<u32, ProcessAncestry>) {
+pub fn print_ports_why(
+    out: &mut dyn Write,
+    ports: &[PortInfo],
+    ancestry_map: &HashMap<u32, ProcessAncestry>,
+) -> io::Result<()> {
     if ports.is_empty() {
-        println!("{}", "No results found".yellow());
-        return;
+        writeln!(out, "{}", "No results found".yellow())?;
+        return Ok(());
     }
 
     let has_remote = ports.iter().any(|p| p.remote_address.is_some());
@@ -143,7 +751,8 @@ pub fn print_ports_why(ports: &[PortInfo], ancestry_map: &HashMap<u32, ProcessAn
     let has_service = ports.iter().any(|p| p.service_name.is_some());
     let has_framework = ports.iter().any(|p| p.framework.is_some());
 
-    let mut table = Table::new();
+    let mut table = output::new_table();
+    output::fit_to_terminal(&mut table);
 
     let mut headers = vec!["PORT", "PROTO", "PID", "PROCESS", "SOURCE"];
     if has_service {
@@ -170,15 +779,15 @@ pub fn print_ports_why(ports: &[PortInfo], ancestry_map: &HashMap<u32, ProcessAn
             .unwrap_or_else(|| "-".to_string());
 
         let proto_color = match port.protocol {
-            crate::types::Protocol::Tcp => Color::Cyan,
-            crate::types::Protocol::Udp => Color::Magenta,
+            Protocol::Tcp => Color::Cyan,
+            Protocol::Udp => Color::Magenta,
         };
 
         let mut row = vec![
-            Cell::new(port.port).fg(Color::Cyan),
+            Cell::new(port.port).fg(port_color(port)),
             Cell::new(port.protocol).fg(proto_color),
             Cell::new(port.pid),
-            Cell::new(&port.process_name),
+            Cell::new(output::clip(&port.process_name)),
             Cell::new(&source_str).fg(Color::Green),
         ];
 
@@ -198,25 +807,297 @@ pub fn print_ports_why(ports: &[PortInfo], ancestry_map: &HashMap<u32, ProcessAn
         }
 
         if has_container {
-            let container = port.container.as_deref().unwrap_or("-");
+            let container = container_label(port);
             let container_color = if port.container.is_some() {
                 Color::Yellow
             } else {
                 Color::Reset
             };
-            row.push(Cell::new(container).fg(container_color));
+            row.push(Cell::new(output::clip(&container)).fg(container_color));
         }
 
-        row.push(Cell::new(&port.address));
+        row.push(Cell::new(output::clip(&port.address)));
 
         if has_remote {
-            let remote = port.remote_address.as_deref().unwrap_or("-");
-            row.push(Cell::new(remote));
+            let remote = port.remote_display();
+            let remote = remote.as_deref().unwrap_or("-");
+            row.push(Cell::new(output::clip(remote)));
         }
 
         table.add_row(row);
     }
 
-    println!("{table}");
-    println!("\n{} result(s)", ports.len().to_string().green());
+    writeln!(out, "{table}")?;
+    writeln!(out, "\n{} result(s)", ports.len().to_string().green())?;
+    writeln!(out, "{}", summary_line(ports).dimmed())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn render(print: impl FnOnce(&mut dyn Write) -> io::Result<()>) -> String {
+        let mut buf = Vec::new();
+        print(&mut buf).unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    #[test]
+    fn service_column_only_when_a_service_is_known() {
+        let mut ports = vec![make_port(8080, Protocol::Tcp, 10, None)];
+        let text = render(|out| print_ports(out, &ports, false, false));
+        assert!(text.contains("ADDRESS"), "{text}");
+        assert!(!text.contains("SERVICE"), "{text}");
+
+        ports[0].service_name = Some("http-alt".to_string());
+        let text = render(|out| print_ports(out, &ports, false, false));
+        assert!(text.contains("SERVICE"), "{text}");
+        assert!(text.contains("http-alt"), "{text}");
+        assert!(text.contains("result(s)"), "{text}");
+    }
+
+    #[test]
+    fn connection_age_is_marked_as_an_upper_bound() {
+        let mut conn = make_port(51000, Protocol::Tcp, 10, None);
+        conn.remote_address = Some("10.0.0.2:443".to_string());
+        conn.opened_after = Some(SystemTime::now() - Duration::from_secs(2 * 3600));
+        let text = render(|out| print_ports(out, &[conn], false, false));
+        assert!(text.contains("AGE"), "{text}");
+        assert!(text.contains("≤2h 0m"), "{text}");
+        assert!(text.contains("upper bound"), "{text}");
+    }
+
+    #[test]
+    fn empty_listing_prints_no_table() {
+        let text = render(|out| print_ports(out, &[], false, false));
+        assert!(text.contains("No results found"), "{text}");
+        assert!(!text.contains("PORT"), "{text}");
+        assert_eq!(render(|out| print_ports_gone(out, &[])), "");
+    }
+
+    fn make_port(port: u16, protocol: Protocol, pid: u32, container: Option<&str>) -> PortInfo {
+        PortInfo {
+            port,
+            protocol,
+            pid,
+            process_name: "proc".to_string(),
+            address: format!("0.0.0.0:{port}"),
+            container: container.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn group_by_process_keeps_first_appearance_order() {
+        let ports = vec![
+            make_port(443, Protocol::Tcp, 20, None),
+            make_port(80, Protocol::Tcp, 10, None),
+            make_port(80, Protocol::Tcp, 20, None),
+        ];
+        let groups = group_by_process(&ports);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].0, 20);
+        assert_eq!(
+            groups[0].1.iter().map(|p| p.port).collect::<Vec<_>>(),
+            [443, 80]
+        );
+        assert_eq!(groups[1].0, 10);
+    }
+
+    #[test]
+    fn gone_line_includes_remote_for_connections() {
+        let mut port = make_port(5432, Protocol::Tcp, 42, None);
+        port.process_name = "postgres".to_string();
+        assert_eq!(gone_line(&port), "5432/tcp postgres (42) 0.0.0.0:5432");
+        port.remote_address = Some("10.0.0.2:51000".to_string());
+        assert_eq!(
+            gone_line(&port),
+            "5432/tcp postgres (42) 0.0.0.0:5432 -> 10.0.0.2:51000"
+        );
+    }
+
+    #[test]
+    fn port_label_marks_ipv6() {
+        let mut port = make_port(80, Protocol::Tcp, 10, None);
+        assert_eq!(port_label(&port), "80/tcp");
+        port.address = ":::80".to_string();
+        assert_eq!(port_label(&port), "80/tcp6");
+        port.address = "[::1]:80".to_string();
+        assert_eq!(port_label(&port), "80/tcp6");
+        port.address = "*:80".to_string();
+        assert_eq!(port_label(&port), "80/tcp");
+    }
+
+    #[test]
+    fn container_label_appends_the_container_port() {
+        let mut port = make_port(3000, Protocol::Tcp, 10, Some("web"));
+        assert_eq!(container_label(&port), "web");
+        port.container_port = Some(80);
+        assert_eq!(container_label(&port), "web:80");
+        port.container = None;
+        assert_eq!(container_label(&port), "-");
+    }
+
+    #[test]
+    fn summary_line_counts_protocols_processes_and_containers() {
+        let ports = vec![
+            make_port(80, Protocol::Tcp, 10, None),
+            make_port(443, Protocol::Tcp, 10, None),
+            make_port(53, Protocol::Udp, 20, None),
+            make_port(5432, Protocol::Tcp, 30, Some("db")),
+            make_port(5433, Protocol::Tcp, 31, Some("db")),
+            make_port(6379, Protocol::Tcp, 32, Some("cache")),
+        ];
+        assert_eq!(
+            summary_line(&ports),
+            "TCP: 5  UDP: 1  Processes: 5  Containers: 2"
+        );
+    }
+
+    #[test]
+    fn health_legend_counts_flagged_processes() {
+        use crate::ancestry::HealthWarning;
+
+        let mut ports = vec![
+            make_port(80, Protocol::Tcp, 10, None),
+            make_port(443, Protocol::Tcp, 10, None),
+            make_port(22, Protocol::Tcp, 20, None),
+        ];
+        assert_eq!(health_legend(&ports), None);
+
+        ports[0].warnings.push(HealthWarning::DeletedBinary);
+        ports[1].warnings.push(HealthWarning::DeletedBinary);
+        assert_eq!(
+            health_legend(&ports).as_deref(),
+            Some("⚠ deleted-binary: 1 process(es)")
+        );
+    }
+
+    #[test]
+    fn port_color_flags_exposed_remote_admin() {
+        let mut ssh = make_port(22, Protocol::Tcp, 10, None);
+        ssh.address = "0.0.0.0:22".to_string();
+        assert_eq!(port_color(&ssh), Color::Red);
+        ssh.address = "127.0.0.1:22".to_string();
+        assert_eq!(port_color(&ssh), Color::Yellow);
+
+        assert_eq!(classify(5432), PortCategory::DataStore);
+        assert_eq!(classify(49152), PortCategory::Ephemeral);
+        assert_eq!(classify(8080), PortCategory::Other);
+    }
+
+    #[test]
+    fn collapse_dual_stack_merges_only_family_twins() {
+        let at = |port: u16, address: &str| PortInfo {
+            address: address.to_string(),
+            ..make_port(port, Protocol::Tcp, 10, None)
+        };
+        let ports = vec![
+            at(80, "0.0.0.0:80"),
+            at(80, ":::80"),
+            at(5432, "127.0.0.1:5432"),
+            at(5432, "::1:5432"),
+            at(8080, "10.0.0.5:8080"),
+            at(8080, "::1:8080"),
+            at(53, "0.0.0.0:53"),
+            at(53, "127.0.0.1:53"),
+        ];
+
+        let (kept, dual_stack) = collapse_dual_stack(&ports);
+
+        let addrs: Vec<_> = kept.iter().map(|p| p.address.as_str()).collect();
+        assert_eq!(
+            addrs,
+            [
+                "0.0.0.0:80",
+                "127.0.0.1:5432",
+                "10.0.0.5:8080",
+                "::1:8080",
+                "0.0.0.0:53",
+                "127.0.0.1:53"
+            ]
+        );
+        assert_eq!(dual_stack, HashSet::from([0, 1]));
+    }
+
+    #[test]
+    fn collapse_dual_stack_keeps_distinct_processes_apart() {
+        let mut v6 = make_port(80, Protocol::Tcp, 11, None);
+        v6.address = "[::]:80".to_string();
+        let ports = vec![make_port(80, Protocol::Tcp, 10, None), v6];
+
+        let (kept, dual_stack) = collapse_dual_stack(&ports);
+
+        assert_eq!(kept.len(), 2);
+        assert!(dual_stack.is_empty());
+    }
+
+    #[test]
+    fn attribution_hint_counts_unknown_owners() {
+        let mut ports = vec![make_port(80, Protocol::Tcp, 10, None)];
+        assert_eq!(attribution_hint(&ports, false), None);
+        assert!(summary_line(&ports).contains("Processes: 1"));
+
+        ports.push(make_port(22, Protocol::Tcp, 0, None));
+        ports.push(make_port(25, Protocol::Tcp, 0, None));
+        let hint = attribution_hint(&ports, false).unwrap();
+        assert!(hint.starts_with("note: 2 socket(s)"), "{hint}");
+        assert!(hint.contains("--sudo"), "{hint}");
+        let hint = attribution_hint(&ports, true).unwrap();
+        assert!(!hint.contains("sudo"), "{hint}");
+        assert!(summary_line(&ports).contains("Processes: 1"));
+
+        // Kernel sockets have no owner to find, so they aren't counted.
+        let mut kernel = make_port(2049, Protocol::Tcp, 0, None);
+        kernel.process_name = crate::types::KERNEL_NAME.to_string();
+        ports.push(kernel);
+        let hint = attribution_hint(&ports, true).unwrap();
+        assert!(hint.starts_with("note: 2 socket(s)"), "{hint}");
+    }
+
+    #[test]
+    fn format_rate_scales_units() {
+        assert_eq!(format_rate(0), "0");
+        assert_eq!(format_rate(512), "512 B");
+        assert_eq!(format_rate(1536), "1.5 KB");
+        assert_eq!(format_rate(12 * 1024 * 1024), "12.0 MB");
+    }
+
+    #[test]
+    fn format_age_picks_two_largest_units() {
+        let now = SystemTime::now();
+        let ago = |secs| format_age(now, now - Duration::from_secs(secs));
+        assert_eq!(format_age(now, now + Duration::from_secs(5)), "<1m");
+        assert_eq!(ago(59), "<1m");
+        assert_eq!(ago(42 * 60 + 10), "42m");
+        assert_eq!(ago(5 * 3600 + 12 * 60), "5h 12m");
+        assert_eq!(ago(3 * 86400 + 4 * 3600 + 59), "3d 4h");
+    }
+
+    #[test]
+    fn service_summary_line_names_unknown_bucket() {
+        let mut ssh = make_port(22, Protocol::Tcp, 10, None);
+        ssh.service_name = Some("ssh".to_string());
+        let ports = vec![ssh, make_port(3000, Protocol::Tcp, 11, None)];
+        assert_eq!(service_summary_line(&ports), "ssh: 1, <unknown>: 1");
+    }
+
+    #[test]
+    fn anomaly_line_lists_expected_processes() {
+        let nc = make_port(22, Protocol::Tcp, 10, None);
+        let nc = PortInfo {
+            process_name: "nc".to_string(),
+            ..nc
+        };
+        assert_eq!(
+            anomaly_line(&nc, &["sshd".to_string()]),
+            "warning: port 22 bound by `nc` (expected sshd)"
+        );
+        assert_eq!(
+            anomaly_line(&nc, &["sshd".to_string(), "dropbear".to_string()]),
+            "warning: port 22 bound by `nc` (expected one of sshd, dropbear)"
+        );
+    }
 }
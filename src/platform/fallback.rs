@@ -1,28 +1,32 @@
 #[cfg(not(target_os = "linux"))]
-pub fn get_listening_ports() -> anyhow::Result<Vec<crate::types::PortInfo>> {
-    use crate::types::{PortInfo, Protocol};
-    use anyhow::anyhow;
+pub fn get_listening_ports() -> Result<Vec<crate::types::PortInfo>, crate::error::PortsError> {
+    use crate::error::PortsError;
+    use crate::types::{PortInfo, Protocol, SocketState};
 
-    let listeners =
-        listeners::get_all().map_err(|e| anyhow!("Failed to get listening ports: {}", e))?;
+    let listeners = listeners::get_all().map_err(|e| {
+        PortsError::io(
+            "Failed to get listening ports",
+            std::io::Error::other(e.to_string()),
+        )
+    })?;
 
     let ports: Vec<PortInfo> = listeners
         .into_iter()
-        .map(|l: listeners::Listener| PortInfo {
-            port: l.socket.port(),
-            protocol: match l.protocol {
+        .map(|l: listeners::Listener| {
+            let protocol = match l.protocol {
                 listeners::Protocol::TCP => Protocol::Tcp,
                 listeners::Protocol::UDP => Protocol::Udp,
-            },
-            pid: l.process.pid,
-            process_name: l.process.name.clone(),
-            address: l.socket.to_string(),
-            remote_address: None,
-            container: None,
-            service_name: None,
-            command_line: None,
-            cwd: None,
-            framework: None,
+            };
+            PortInfo {
+                port: l.socket.port(),
+                protocol,
+                pid: l.process.pid,
+                process_name: l.process.name.clone(),
+                address: l.socket.to_string(),
+                // Everything the listeners crate reports is listening.
+                state: (protocol == Protocol::Tcp).then_some(SocketState::Listen),
+                ..Default::default()
+            }
         })
         .collect();
 
@@ -31,3 +35,166 @@ pub fn get_listening_ports() -> anyhow::Result<Vec<crate::types::PortInfo>> {
 
 #[cfg(not(any(target_os = "linux", target_os = "macos")))]
 pub fn resolve_process_details(_ports: &mut [crate::types::PortInfo]) {}
+
+/// Established connections from `netstat`, for platforms where neither
+/// `/proc/net` nor `lsof` is available. Windows gets owning PIDs from
+/// `netstat -ano`; elsewhere rows are left unattributed.
+#[cfg(all(
+    feature = "netstat",
+    not(any(target_os = "linux", target_os = "macos"))
+))]
+pub fn get_connections() -> Result<Vec<crate::types::PortInfo>, crate::error::PortsError> {
+    use crate::error::PortsError;
+
+    let args: &[&str] = if cfg!(windows) { &["-ano"] } else { &["-an"] };
+    let output = match std::process::Command::new("netstat").args(args).output() {
+        Ok(output) => output,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Err(PortsError::UnsupportedPlatform(
+                "--connections needs `netstat` on this platform, and it was not found on PATH"
+                    .to_string(),
+            ))
+        }
+        Err(e) => return Err(PortsError::io("Failed to execute netstat", e)),
+    };
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(netstat::parse_output(&stdout))
+}
+
+/// `netstat -an` parsing. Compiled under `test` on every platform so the
+/// BSD and Windows formats are exercised by the Linux CI run.
+#[cfg(any(
+    test,
+    all(
+        feature = "netstat",
+        not(any(target_os = "linux", target_os = "macos"))
+    )
+))]
+mod netstat {
+    use crate::types::{PortInfo, Protocol, SocketState, UNATTRIBUTED_NAME, UNATTRIBUTED_PID};
+
+    /// Keep TCP rows in `ESTABLISHED` and UDP rows with a concrete peer,
+    /// matching what the Linux backend reports for `--connections`.
+    pub(super) fn parse_output(output: &str) -> Vec<PortInfo> {
+        output.lines().filter_map(parse_line).collect()
+    }
+
+    fn parse_line(line: &str) -> Option<PortInfo> {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        let proto = *parts.first()?;
+        // Windows prints `TCP`/`UDP` and `ip:port`; the BSDs print
+        // `tcp4`/`udp6` and `ip.port`.
+        let windows = proto.starts_with("TCP") || proto.starts_with("UDP");
+        let protocol = match proto.to_ascii_lowercase() {
+            p if p.starts_with("tcp") => Protocol::Tcp,
+            p if p.starts_with("udp") => Protocol::Udp,
+            _ => return None,
+        };
+        // BSD rows carry Recv-Q and Send-Q before the addresses.
+        let rest = if windows {
+            &parts[1..]
+        } else {
+            parts.get(3..)?
+        };
+        let (local_host, port) = split_endpoint(rest.first()?, windows)?;
+        let (remote_host, remote_port) = split_endpoint(rest.get(1)?, windows)?;
+
+        let state = match protocol {
+            Protocol::Tcp => {
+                if *rest.get(2)? != "ESTABLISHED" {
+                    return None;
+                }
+                Some(SocketState::Established)
+            }
+            Protocol::Udp => {
+                if remote_port == 0 {
+                    return None;
+                }
+                None
+            }
+        };
+        // `netstat -ano` appends the PID: after the state for TCP, in the
+        // state column for UDP.
+        let pid = if windows {
+            rest.last()
+                .and_then(|p| p.parse().ok())
+                .unwrap_or(UNATTRIBUTED_PID)
+        } else {
+            UNATTRIBUTED_PID
+        };
+
+        Some(PortInfo {
+            port,
+            protocol,
+            pid,
+            process_name: UNATTRIBUTED_NAME.to_string(),
+            address: format!("{local_host}:{port}"),
+            remote_address: Some(format!("{remote_host}:{remote_port}")),
+            state,
+            ..Default::default()
+        })
+    }
+
+    /// Split `host:port` (Windows) or `host.port` (BSD) into its parts.
+    /// Wildcard ports (`*`) read as 0.
+    fn split_endpoint(endpoint: &str, windows: bool) -> Option<(&str, u16)> {
+        let (host, port) = endpoint.rsplit_once(if windows { ':' } else { '.' })?;
+        let port = if port == "*" { 0 } else { port.parse().ok()? };
+        Some((host, port))
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn parses_bsd_netstat() {
+            let output = "\
+Active Internet connections (including servers)
+Proto Recv-Q Send-Q Local Address          Foreign Address        (state)
+tcp4       0      0 192.168.1.5.22         192.168.1.10.54321     ESTABLISHED
+tcp6       0      0 fe80::1%lo0.8080       fe80::1%lo0.50000      ESTABLISHED
+tcp4       0      0 *.22                   *.*                    LISTEN
+tcp4       0      0 10.0.0.2.443           10.0.0.9.61000         TIME_WAIT
+udp4       0      0 10.0.0.2.53000         8.8.8.8.53
+udp4       0      0 *.514                  *.*
+";
+            let ports = parse_output(output);
+            assert_eq!(ports.len(), 3);
+
+            assert_eq!(ports[0].port, 22);
+            assert_eq!(ports[0].address, "192.168.1.5:22");
+            assert_eq!(
+                ports[0].remote_address.as_deref(),
+                Some("192.168.1.10:54321")
+            );
+            assert_eq!(ports[0].state, Some(SocketState::Established));
+            assert!(ports[0].is_unattributed());
+
+            assert_eq!(ports[1].address, "fe80::1%lo0:8080");
+            assert_eq!(ports[2].protocol, Protocol::Udp);
+            assert_eq!(ports[2].remote_address.as_deref(), Some("8.8.8.8:53"));
+            assert_eq!(ports[2].state, None);
+        }
+
+        #[test]
+        fn parses_windows_netstat_with_pids() {
+            let output = "
+Active Connections
+
+  Proto  Local Address          Foreign Address        State           PID
+  TCP    0.0.0.0:135            0.0.0.0:0              LISTENING       1020
+  TCP    192.168.1.5:49712      20.42.65.90:443        ESTABLISHED     4312
+  TCP    [::1]:49670            [::1]:49671            ESTABLISHED     7788
+  UDP    0.0.0.0:5353           *:*                                    2264
+";
+            let ports = parse_output(output);
+            assert_eq!(ports.len(), 2);
+            assert_eq!(ports[0].port, 49712);
+            assert_eq!(ports[0].pid, 4312);
+            assert_eq!(ports[0].remote_address.as_deref(), Some("20.42.65.90:443"));
+            assert_eq!(ports[1].address, "[::1]:49670");
+            assert_eq!(ports[1].pid, 7788);
+        }
+    }
+}
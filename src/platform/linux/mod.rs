@@ -3,18 +3,24 @@
 // calls these items, so dead-code analysis flags them on macOS.
 #![allow(dead_code)]
 
+pub mod netns;
 pub mod proc_fd;
 pub mod proc_parser;
 pub mod process;
+pub mod sock_diag;
 
+use std::collections::HashMap;
 use std::fs;
-use std::net::IpAddr;
+use std::net::{IpAddr, Ipv6Addr};
 
-use anyhow::Result;
+use colored::Colorize;
 
-use crate::types::{PortInfo, Protocol};
-use proc_fd::build_inode_to_process_map;
-use proc_parser::{parse_proc_net_file, RawSocket, SocketState};
+use crate::error::PortsError;
+use crate::types::{PortInfo, Protocol, KERNEL_NAME, UNATTRIBUTED_NAME, UNATTRIBUTED_PID};
+use proc_fd::{build_inode_to_process_map, find_inode_owners};
+use proc_parser::{
+    is_link_local_v6, parse_if_inet6, parse_proc_net_file_verbose, RawSocket, SocketState,
+};
 
 #[derive(Clone, Copy)]
 enum FilterMode {
@@ -23,16 +29,26 @@ enum FilterMode {
     All,
 }
 
-pub fn get_listening_ports() -> Result<Vec<PortInfo>> {
-    get_ports(FilterMode::Listening)
+pub fn get_listening_ports() -> Result<Vec<PortInfo>, PortsError> {
+    get_ports(FilterMode::Listening, None)
 }
 
-pub fn get_all_connections() -> Result<Vec<PortInfo>> {
-    get_ports(FilterMode::All)
+/// Listening sockets on `ports` only; see [`proc_fd::find_inode_owners`].
+pub fn get_listening_ports_on(ports: &[u16]) -> Result<Vec<PortInfo>, PortsError> {
+    get_ports(FilterMode::Listening, Some(ports))
 }
 
-pub fn get_established_connections() -> Result<Vec<PortInfo>> {
-    get_ports(FilterMode::Established)
+pub fn get_all_connections() -> Result<Vec<PortInfo>, PortsError> {
+    get_ports(FilterMode::All, None)
+}
+
+pub fn get_established_connections() -> Result<Vec<PortInfo>, PortsError> {
+    get_ports(FilterMode::Established, None)
+}
+
+/// Established connections on local `ports` only.
+pub fn get_established_connections_on(ports: &[u16]) -> Result<Vec<PortInfo>, PortsError> {
+    get_ports(FilterMode::Established, Some(ports))
 }
 
 fn is_remote_zero(socket: &RawSocket) -> bool {
@@ -43,19 +59,34 @@ fn is_remote_zero(socket: &RawSocket) -> bool {
         }
 }
 
+/// UDP has no LISTEN state, so its role comes from the peer address. A
+/// socket with a bound local port and no peer (`0.0.0.0:0` / `[::]:0`)
+/// accepts datagrams from anyone and counts as listening. One that was
+/// `connect()`ed to a peer — DNS stub resolvers, QUIC clients — only
+/// talks to that peer and counts as a connection, whatever its local
+/// address.
+fn is_udp_listener(socket: &RawSocket) -> bool {
+    socket.local_port != 0 && is_remote_zero(socket)
+}
+
+/// See [`is_udp_listener`].
+fn is_udp_connection(socket: &RawSocket) -> bool {
+    !is_remote_zero(socket)
+}
+
 fn should_include(socket: &RawSocket, mode: FilterMode, is_udp: bool) -> bool {
     match mode {
         FilterMode::All => true,
         FilterMode::Listening => {
             if is_udp {
-                is_remote_zero(socket)
+                is_udp_listener(socket)
             } else {
                 socket.state == SocketState::Listen
             }
         }
         FilterMode::Established => {
             if is_udp {
-                !is_remote_zero(socket)
+                is_udp_connection(socket)
             } else {
                 socket.state == SocketState::Established
             }
@@ -63,50 +94,304 @@ fn should_include(socket: &RawSocket, mode: FilterMode, is_udp: bool) -> bool {
     }
 }
 
-fn get_ports(mode: FilterMode) -> Result<Vec<PortInfo>> {
-    let inode_map = build_inode_to_process_map()?;
+/// Render an address for display. Link-local IPv6 addresses are useless
+/// without their interface, which `/proc/net/tcp6` doesn't carry, so the
+/// zone comes from `/proc/net/if_inet6`: `fe80::1%eth0`, or `fe80::1%?`
+/// when the address isn't assigned to exactly one interface.
+fn format_addr(addr: IpAddr, scopes: &HashMap<Ipv6Addr, Vec<String>>) -> String {
+    match addr {
+        IpAddr::V6(v6) if is_link_local_v6(&v6) => match scopes.get(&v6).map(Vec::as_slice) {
+            Some([iface]) => format!("{v6}%{iface}"),
+            _ => format!("{v6}%?"),
+        },
+        _ => addr.to_string(),
+    }
+}
+
+fn get_ports(mode: FilterMode, only: Option<&[u16]>) -> Result<Vec<PortInfo>, PortsError> {
+    let mut tables = vec![read_net_dir("/proc/net", mode, only)?];
+    // `/proc/<pid>/net` shows the sockets of that process's namespace.
+    // Socket inodes are unique system-wide, so the one inode map still
+    // attributes them. Other namespaces are best effort: their process
+    // may exit, or hide its tables from us, mid-scan.
+    let namespaces = if super::all_netns() {
+        netns::other_namespaces()
+    } else {
+        Vec::new()
+    };
+    let mut ns_tables = Vec::new();
+    for ns in &namespaces {
+        let dir = format!("/proc/{}/net", ns.pid);
+        match read_net_dir(&dir, mode, only) {
+            Ok(table) => ns_tables.push((ns, table)),
+            Err(e) => {
+                if super::verbose() {
+                    eprintln!("{} {e}", "debug:".dimmed());
+                }
+            }
+        }
+    }
+
+    // With only a few ports wanted, look up just the owners of their
+    // sockets instead of mapping every socket on the host.
+    let inode_map = match only {
+        None => build_inode_to_process_map(),
+        Some(_) => {
+            let wanted = tables
+                .iter()
+                .chain(ns_tables.iter().map(|(_, table)| table))
+                .flat_map(|table| table.sockets.iter().map(|(_, socket)| socket.inode))
+                .collect();
+            find_inode_owners(&wanted)
+        }
+    };
+
     let mut ports = Vec::new();
+    for table in tables.drain(..) {
+        table.into_ports(None, &inode_map, &mut ports);
+    }
+    for (ns, table) in ns_tables {
+        table.into_ports(Some(ns), &inode_map, &mut ports);
+    }
+    Ok(ports)
+}
 
-    for (path, protocol) in [
-        ("/proc/net/tcp", Protocol::Tcp),
-        ("/proc/net/tcp6", Protocol::Tcp),
-        ("/proc/net/udp", Protocol::Udp),
-        ("/proc/net/udp6", Protocol::Udp),
+/// The sockets `mode` selects under one `net` directory (`/proc/net` or
+/// `/proc/<pid>/net`), before they're attributed to processes.
+struct NetTable {
+    scopes: HashMap<Ipv6Addr, Vec<String>>,
+    sockets: Vec<(Protocol, RawSocket)>,
+}
+
+/// Read the socket tables under `dir`, keeping the sockets `mode` selects
+/// and, with `only`, just those on one of the given local ports.
+///
+/// Each table is best effort: a missing one (e.g. `tcp6` with IPv6
+/// disabled), one we may not read, or one with no parsable line at all is
+/// skipped and the others are still used. Only when every table that
+/// exists failed is the first failure returned.
+fn read_net_dir(dir: &str, mode: FilterMode, only: Option<&[u16]>) -> Result<NetTable, PortsError> {
+    let scopes = fs::read_to_string(format!("{dir}/if_inet6"))
+        .map(|content| parse_if_inet6(&content))
+        .unwrap_or_default();
+    let mut selected = Vec::new();
+    let mut read_any = false;
+    let mut first_error = None;
+
+    for (file, protocol) in [
+        ("tcp", Protocol::Tcp),
+        ("tcp6", Protocol::Tcp),
+        ("udp", Protocol::Udp),
+        ("udp6", Protocol::Udp),
     ] {
         let is_udp = protocol == Protocol::Udp;
+        let path = format!("{dir}/{file}");
 
-        if let Ok(content) = fs::read_to_string(path) {
-            let sockets = parse_proc_net_file(&content);
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => {
+                skip_table(PortsError::from_io(e, path), &mut first_error);
+                continue;
+            }
+        };
+        let (sockets, failures) = parse_proc_net_file_verbose(&content);
+        if sockets.is_empty() && !failures.is_empty() {
+            let err = PortsError::ProcParse {
+                path: path.into(),
+                detail: failures[0].clone(),
+            };
+            skip_table(err, &mut first_error);
+            continue;
+        }
+        read_any = true;
+        if super::verbose() && !failures.is_empty() {
+            eprintln!(
+                "{} skipped {} unparsable line(s) in {path} (first: {})",
+                "debug:".dimmed(),
+                failures.len(),
+                failures[0]
+            );
+        }
 
-            for socket in sockets {
-                if !should_include(&socket, mode, is_udp) {
-                    continue;
-                }
+        selected.extend(
+            sockets
+                .into_iter()
+                // Inode 0 means no process holds the socket any more
+                // (e.g. TIME_WAIT); there's nothing to attribute.
+                .filter(|socket| should_include(socket, mode, is_udp) && socket.inode != 0)
+                .filter(|socket| only.is_none_or(|ports| ports.contains(&socket.local_port)))
+                .map(|socket| (protocol, socket)),
+        );
+    }
 
-                if let Some(process_info) = inode_map.get(&socket.inode) {
-                    let remote = if is_remote_zero(&socket) {
-                        None
-                    } else {
-                        Some(format!("{}:{}", socket.remote_addr, socket.remote_port))
-                    };
-
-                    ports.push(PortInfo {
-                        port: socket.local_port,
-                        protocol,
-                        pid: process_info.pid,
-                        process_name: process_info.name.clone(),
-                        address: format!("{}:{}", socket.local_addr, socket.local_port),
-                        remote_address: remote,
-                        container: None,
-                        service_name: None,
-                        command_line: None,
-                        cwd: None,
-                        framework: None,
-                    });
-                }
-            }
+    match first_error {
+        Some(err) if !read_any => Err(err),
+        _ => Ok(NetTable {
+            scopes,
+            sockets: selected,
+        }),
+    }
+}
+
+/// Note a table [`read_net_dir`] couldn't use, keeping the first failure.
+fn skip_table(err: PortsError, first_error: &mut Option<PortsError>) {
+    if super::verbose() {
+        eprintln!("{} skipped table: {err}", "debug:".dimmed());
+    }
+    first_error.get_or_insert(err);
+}
+
+impl NetTable {
+    /// Append a row per socket. Rows from another namespace are labelled
+    /// with it.
+    fn into_ports(
+        self,
+        ns: Option<&netns::Netns>,
+        inode_map: &proc_fd::InodeMap,
+        ports: &mut Vec<PortInfo>,
+    ) {
+        let resolve_users = super::resolve_user_names();
+        // With every fd table read, a socket no process holds is the
+        // kernel's own (NFS, WireGuard, ...).
+        let unowned = if super::show_kernel() && inode_map.complete {
+            KERNEL_NAME
+        } else {
+            UNATTRIBUTED_NAME
+        };
+        for (protocol, socket) in self.sockets {
+            let is_udp = protocol == Protocol::Udp;
+            // Sockets of processes we can't inspect are kept with an
+            // unknown owner rather than hidden.
+            let (pid, process_name) = match inode_map.get(&socket.inode) {
+                Some(info) => (info.pid, info.name.clone()),
+                None => (UNATTRIBUTED_PID, unowned.to_string()),
+            };
+            let remote = if is_remote_zero(&socket) {
+                None
+            } else {
+                Some(format!("{}:{}", socket.remote_addr, socket.remote_port))
+            };
+
+            ports.push(PortInfo {
+                port: socket.local_port,
+                protocol,
+                pid,
+                process_name,
+                address: format!(
+                    "{}:{}",
+                    format_addr(socket.local_addr, &self.scopes),
+                    socket.local_port
+                ),
+                remote_address: remote,
+                container_id: ns.and_then(|ns| ns.container_id.clone()),
+                netns: ns.map(|ns| ns.label.clone()),
+                uid: Some(socket.uid),
+                user: resolve_users.then(|| super::user_name(socket.uid)),
+                retransmits: (!is_udp && socket.state != SocketState::Listen)
+                    .then_some(socket.retransmits),
+                state: (!is_udp).then_some(socket.state),
+                ..Default::default()
+            });
         }
     }
+}
 
-    Ok(ports)
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UDP_HEADER: &str = "   sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode ref pointer drops";
+
+    fn udp_sockets(lines: &[&str]) -> Vec<RawSocket> {
+        let content = std::iter::once(UDP_HEADER)
+            .chain(lines.iter().copied())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let sockets = proc_parser::parse_proc_net_file(&content);
+        assert_eq!(sockets.len(), lines.len());
+        sockets
+    }
+
+    #[test]
+    fn unusable_tables_are_skipped_unless_all_fail() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().to_str().unwrap();
+        fs::write(dir.path().join("tcp"), format!("{UDP_HEADER}\ngarbage\n")).unwrap();
+        fs::write(
+            dir.path().join("udp"),
+            format!("{UDP_HEADER}\n  123: 00000000:0035 00000000:0000 07 00000000:00000000 00:00000000 00000000   101        0 20001 2 0000000000000000 0\n"),
+        )
+        .unwrap();
+        // A directory where a table should be: exists but can't be read.
+        fs::create_dir(dir.path().join("udp6")).unwrap();
+
+        let table = read_net_dir(path, FilterMode::Listening, None).unwrap();
+        assert_eq!(table.sockets.len(), 1);
+        assert_eq!(table.sockets[0].0, Protocol::Udp);
+
+        fs::remove_file(dir.path().join("udp")).unwrap();
+        let err = read_net_dir(path, FilterMode::Listening, None)
+            .err()
+            .expect("every table failed");
+        assert!(matches!(err, PortsError::ProcParse { .. }), "{err}");
+    }
+
+    #[test]
+    fn udp_without_peer_is_listening() {
+        // A DNS server on 0.0.0.0:53 and one on 127.0.0.53:53.
+        let sockets = udp_sockets(&[
+            "  123: 00000000:0035 00000000:0000 07 00000000:00000000 00:00000000 00000000   101        0 20001 2 0000000000000000 0",
+            "  124: 3500007F:0035 00000000:0000 07 00000000:00000000 00:00000000 00000000   101        0 20002 2 0000000000000000 0",
+        ]);
+        for socket in &sockets {
+            assert!(should_include(socket, FilterMode::Listening, true));
+            assert!(!should_include(socket, FilterMode::Established, true));
+        }
+    }
+
+    #[test]
+    fn connected_udp_is_a_connection() {
+        // A stub-resolver client 127.0.0.1:54321 -> 127.0.0.53:53, and a
+        // QUIC client on a wildcard local address talking to 1.1.1.1:443.
+        let sockets = udp_sockets(&[
+            "  200: 0100007F:D431 3500007F:0035 01 00000000:00000000 00:00000000 00000000  1000        0 30001 2 0000000000000000 0",
+            "  201: 00000000:E2D8 01010101:01BB 01 00000000:00000000 00:00000000 00000000  1000        0 30002 2 0000000000000000 0",
+        ]);
+        for socket in &sockets {
+            assert!(!should_include(socket, FilterMode::Listening, true));
+            assert!(should_include(socket, FilterMode::Established, true));
+        }
+    }
+
+    #[test]
+    fn udp_without_local_port_is_neither() {
+        let sockets = udp_sockets(&[
+            "  300: 00000000:0000 00000000:0000 07 00000000:00000000 00:00000000 00000000  1000        0 40001 2 0000000000000000 0",
+        ]);
+        assert!(!should_include(&sockets[0], FilterMode::Listening, true));
+        assert!(!should_include(&sockets[0], FilterMode::Established, true));
+        assert!(should_include(&sockets[0], FilterMode::All, true));
+    }
+
+    #[test]
+    fn format_addr_adds_zone_to_link_local() {
+        let mut scopes = HashMap::new();
+        let eth0: Ipv6Addr = "fe80::1".parse().unwrap();
+        let shared: Ipv6Addr = "fe80::2".parse().unwrap();
+        scopes.insert(eth0, vec!["eth0".to_string()]);
+        scopes.insert(shared, vec!["eth1".to_string(), "eth2".to_string()]);
+
+        assert_eq!(format_addr(IpAddr::V6(eth0), &scopes), "fe80::1%eth0");
+        assert_eq!(format_addr(IpAddr::V6(shared), &scopes), "fe80::2%?");
+        assert_eq!(
+            format_addr("fe80::3".parse().unwrap(), &scopes),
+            "fe80::3%?"
+        );
+        assert_eq!(format_addr("::1".parse().unwrap(), &scopes), "::1");
+        assert_eq!(
+            format_addr("127.0.0.1".parse().unwrap(), &scopes),
+            "127.0.0.1"
+        );
+    }
 }
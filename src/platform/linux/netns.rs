@@ -0,0 +1,140 @@
+// See sibling `mod.rs` for why this file allows dead code: the linux platform
+// module is compiled on macOS to keep its unit tests cross-platform, and the
+// production items are then unused outside linux.
+#![allow(dead_code)]
+
+use std::collections::BTreeMap;
+use std::fs;
+
+/// A network namespace other than our own, and one process inside it
+/// whose `/proc/<pid>/net` shows that namespace's sockets.
+#[derive(Debug)]
+pub struct Netns {
+    pub inode: u64,
+    pub pid: u32,
+    /// `ip netns` name, else the short container ID, else `net:[inode]`.
+    pub label: String,
+    /// Short (12-character) container ID, when the process's cgroup
+    /// names one.
+    pub container_id: Option<String>,
+}
+
+/// Inode from a `/proc/<pid>/ns/net` link target, e.g. `net:[4026531840]`.
+pub fn parse_ns_link(link: &str) -> Option<u64> {
+    link.strip_prefix("net:[")?.strip_suffix(']')?.parse().ok()
+}
+
+fn ns_inode(pid: &str) -> Option<u64> {
+    let link = fs::read_link(format!("/proc/{pid}/ns/net")).ok()?;
+    parse_ns_link(link.to_str()?)
+}
+
+/// Every network namespace with a live process in it except our own,
+/// ordered by inode. Namespaces whose processes we can't inspect are
+/// invisible here, so without root this usually finds only our own
+/// user's.
+pub fn other_namespaces() -> Vec<Netns> {
+    let Some(own) = ns_inode("self") else {
+        return Vec::new();
+    };
+    let Ok(proc_dir) = fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+
+    // Lowest PID per namespace, so the pick is stable across runs.
+    let mut first_pid: BTreeMap<u64, u32> = BTreeMap::new();
+    for entry in proc_dir.flatten() {
+        let name = entry.file_name();
+        let Some(pid) = name.to_str().and_then(|s| s.parse::<u32>().ok()) else {
+            continue;
+        };
+        match ns_inode(&pid.to_string()) {
+            Some(inode) if inode != own => {
+                let slot = first_pid.entry(inode).or_insert(pid);
+                *slot = (*slot).min(pid);
+            }
+            _ => {}
+        }
+    }
+
+    let names = named_namespaces();
+    first_pid
+        .into_iter()
+        .map(|(inode, pid)| {
+            let container_id = fs::read_to_string(format!("/proc/{pid}/cgroup"))
+                .ok()
+                .and_then(|content| container_id_from_cgroup(&content));
+            let label = names
+                .get(&inode)
+                .cloned()
+                .or_else(|| container_id.clone())
+                .unwrap_or_else(|| format!("net:[{inode}]"));
+            Netns {
+                inode,
+                pid,
+                label,
+                container_id,
+            }
+        })
+        .collect()
+}
+
+/// `ip netns add` names, keyed by namespace inode. Each name is a bind
+/// mount of the namespace file, so its inode is the namespace's.
+fn named_namespaces() -> BTreeMap<u64, String> {
+    use std::os::unix::fs::MetadataExt;
+
+    let mut names = BTreeMap::new();
+    let Ok(dir) = fs::read_dir("/run/netns") else {
+        return names;
+    };
+    for entry in dir.flatten() {
+        if let (Ok(meta), Some(name)) = (fs::metadata(entry.path()), entry.file_name().to_str()) {
+            names.insert(meta.ino(), name.to_string());
+        }
+    }
+    names
+}
+
+/// Short container ID from `/proc/<pid>/cgroup`. Docker, containerd and
+/// Kubernetes all put the 64-hex-digit ID in the cgroup path, e.g.
+/// `0::/system.slice/docker-<id>.scope` or `12:cpu:/docker/<id>`.
+pub fn container_id_from_cgroup(content: &str) -> Option<String> {
+    content.lines().find_map(|line| {
+        let path = line.splitn(3, ':').nth(2)?;
+        path.split(['/', '-', '.', ':'])
+            .find(|seg| seg.len() == 64 && seg.bytes().all(|b| b.is_ascii_hexdigit()))
+            .map(|id| id[..12].to_string())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_ns_link() {
+        assert_eq!(parse_ns_link("net:[4026531840]"), Some(4026531840));
+        assert_eq!(parse_ns_link("mnt:[4026531840]"), None);
+        assert_eq!(parse_ns_link("net:[]"), None);
+    }
+
+    #[test]
+    fn finds_container_id_in_cgroup() {
+        let id = "3f4e8a1b2c9d0e7f6a5b4c3d2e1f0a9b8c7d6e5f4a3b2c1d0e9f8a7b6c5d4e3f";
+        let v2 = format!("0::/system.slice/docker-{id}.scope\n");
+        let v1 = format!("12:cpu,cpuacct:/docker/{id}\n1:name=systemd:/docker/{id}\n");
+        let k8s = format!("0::/kubepods/besteffort/pod1234/cri-containerd-{id}.scope\n");
+        for content in [v2, v1, k8s] {
+            assert_eq!(
+                container_id_from_cgroup(&content).as_deref(),
+                Some("3f4e8a1b2c9d"),
+                "{content}"
+            );
+        }
+        assert_eq!(
+            container_id_from_cgroup("0::/user.slice/session-3.scope\n"),
+            None
+        );
+    }
+}
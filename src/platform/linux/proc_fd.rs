@@ -3,11 +3,17 @@
 // production items are then unused outside linux.
 #![allow(dead_code)]
 
-use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
 use std::fs;
+use std::io;
 use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::Mutex;
 
-use anyhow::{Context, Result};
+use anyhow::Result;
+
+use crate::types::interpreter_family;
 
 #[derive(Debug, Clone)]
 pub struct ProcessInfo {
@@ -15,50 +21,258 @@ pub struct ProcessInfo {
     pub name: String,
 }
 
-pub fn build_inode_to_process_map() -> Result<HashMap<u64, ProcessInfo>> {
-    let mut map = HashMap::new();
+/// Socket owners keyed by inode.
+#[derive(Debug, Default)]
+pub struct InodeMap {
+    pub owners: HashMap<u64, ProcessInfo>,
+    /// Every process's fd table could be read, and every process holding
+    /// a socket named, so an inode missing from `owners` is held by no
+    /// process at all (a kernel socket) rather than by one we weren't
+    /// allowed to inspect or that exited before its name was read.
+    pub complete: bool,
+}
+
+impl InodeMap {
+    pub fn get(&self, inode: &u64) -> Option<&ProcessInfo> {
+        self.owners.get(inode)
+    }
+}
+
+/// Map socket inodes to the process holding them. Best effort: processes
+/// whose `/proc/<pid>/fd` we can't read (another user's, or gone by the
+/// time we look) are left out rather than failing the whole map, so their
+/// sockets simply go unattributed.
+///
+/// Reading every fd link is the bulk of a listing's cost on a busy host, so
+/// PIDs are split across scoped worker threads, each building its own map.
+/// An inode shared between processes (a forked listener) goes to the
+/// lowest PID, however the scan was split.
+pub fn build_inode_to_process_map() -> InodeMap {
+    let pids = list_pids();
+    let workers = worker_count(pids.len());
+    if workers <= 1 {
+        return map_sockets(&pids);
+    }
 
-    let proc_dir = fs::read_dir("/proc").context("Failed to read /proc")?;
+    let chunk_size = pids.len().div_ceil(workers);
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = pids
+            .chunks(chunk_size)
+            .map(|chunk| scope.spawn(move || map_sockets(chunk)))
+            .collect();
+        let mut map = InodeMap {
+            owners: HashMap::new(),
+            complete: true,
+        };
+        for handle in handles {
+            // A worker panic is a bug; re-raise it rather than silently
+            // leaving its share of sockets unattributed.
+            let chunk = handle
+                .join()
+                .unwrap_or_else(|panic| std::panic::resume_unwind(panic));
+            for (inode, owner) in chunk.owners {
+                claim(&mut map.owners, inode, owner);
+            }
+            map.complete &= chunk.complete;
+        }
+        map
+    })
+}
 
-    for entry in proc_dir.flatten() {
-        let pid_str = entry.file_name();
-        let pid_str = pid_str.to_string_lossy();
+/// The owners of just the `wanted` socket inodes, for lookups that only
+/// care about a few sockets. Workers stop as soon as every inode has been
+/// found, and only processes holding one get their name read, so a match
+/// early in `/proc` skips most of the scan. As in
+/// [`build_inode_to_process_map`], an inode shared by several processes
+/// goes to the lowest PID: a worker only stops once every inode has an
+/// owner below the PID it would read next.
+///
+/// A scan that stops early has found every inode, so `complete` only
+/// matters, and is only exact, when it ran to the end.
+pub fn find_inode_owners(wanted: &HashSet<u64>) -> InodeMap {
+    if wanted.is_empty() {
+        return InodeMap {
+            owners: HashMap::new(),
+            complete: true,
+        };
+    }
+    let pids = list_pids();
+    let workers = worker_count(pids.len()).max(1);
+    let chunk_size = pids.len().div_ceil(workers).max(1);
+    let found = Mutex::new(HashMap::new());
+    // Highest owner PID once every inode has one; no later PID can win.
+    let settled_below = AtomicU32::new(u32::MAX);
+    let incomplete = AtomicBool::new(false);
 
-        if let Ok(pid) = pid_str.parse::<u32>() {
-            if let Ok(process_info) = get_process_sockets(pid) {
-                for inode in process_info.inodes {
-                    map.insert(
-                        inode,
-                        ProcessInfo {
+    std::thread::scope(|scope| {
+        for chunk in pids.chunks(chunk_size) {
+            let (found, settled_below, incomplete) = (&found, &settled_below, &incomplete);
+            scope.spawn(move || {
+                for &pid in chunk {
+                    if pid > settled_below.load(Ordering::Relaxed) {
+                        return;
+                    }
+                    let inodes = match read_socket_inodes(pid) {
+                        Ok(inodes) => inodes,
+                        Err(e) => {
+                            if e.kind() == io::ErrorKind::PermissionDenied {
+                                incomplete.store(true, Ordering::Relaxed);
+                            }
+                            continue;
+                        }
+                    };
+                    let hits: Vec<u64> =
+                        inodes.into_iter().filter(|i| wanted.contains(i)).collect();
+                    if hits.is_empty() {
+                        continue;
+                    }
+                    let Ok(name) = read_process_name(pid) else {
+                        incomplete.store(true, Ordering::Relaxed);
+                        continue;
+                    };
+                    let mut found = found.lock().unwrap();
+                    for inode in hits {
+                        let owner = ProcessInfo {
                             pid,
-                            name: process_info.name.clone(),
-                        },
-                    );
+                            name: name.clone(),
+                        };
+                        claim(&mut found, inode, owner);
+                    }
+                    if found.len() == wanted.len() {
+                        let highest = found.values().map(|p| p.pid).max().unwrap_or(0);
+                        settled_below.fetch_min(highest, Ordering::Relaxed);
+                    }
                 }
-            }
+            });
         }
+    });
+    InodeMap {
+        owners: found.into_inner().unwrap(),
+        complete: !incomplete.into_inner(),
     }
+}
 
-    Ok(map)
+/// Every numeric entry in `/proc`, lowest PID first.
+fn list_pids() -> Vec<u32> {
+    let Ok(proc_dir) = fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+    let mut pids: Vec<u32> = proc_dir
+        .flatten()
+        .filter_map(|entry| entry.file_name().to_str()?.parse().ok())
+        .collect();
+    pids.sort_unstable();
+    pids
 }
 
-struct ProcessSockets {
-    name: String,
-    inodes: Vec<u64>,
+/// Record `owner` as holding `inode` unless a lower PID already does, so
+/// a shared socket resolves the same whatever order processes are read in.
+fn claim(owners: &mut HashMap<u64, ProcessInfo>, inode: u64, owner: ProcessInfo) {
+    match owners.entry(inode) {
+        Entry::Occupied(mut slot) if owner.pid < slot.get().pid => {
+            slot.insert(owner);
+        }
+        Entry::Occupied(_) => {}
+        Entry::Vacant(slot) => {
+            slot.insert(owner);
+        }
+    }
 }
 
-fn get_process_sockets(pid: u32) -> Result<ProcessSockets> {
-    let name = read_process_name(pid)?;
-    let inodes = read_socket_inodes(pid)?;
+fn worker_count(pids: usize) -> usize {
+    std::thread::available_parallelism()
+        .map_or(1, |n| n.get())
+        .min(pids)
+}
 
-    Ok(ProcessSockets { name, inodes })
+/// Socket inodes of `pids`, skipping any process that can't be read. A
+/// process that exited before its fd table was read took its sockets with
+/// it, so that's fine; being refused the table, or losing the process
+/// between its fds and its name, leaves sockets with an unknown owner and
+/// makes the map incomplete.
+fn map_sockets(pids: &[u32]) -> InodeMap {
+    let mut map = InodeMap {
+        owners: HashMap::new(),
+        complete: true,
+    };
+    for &pid in pids {
+        let inodes = match read_socket_inodes(pid) {
+            Ok(inodes) => inodes,
+            Err(e) => {
+                if e.kind() == io::ErrorKind::PermissionDenied {
+                    map.complete = false;
+                }
+                continue;
+            }
+        };
+        if inodes.is_empty() {
+            continue;
+        }
+        let Ok(name) = read_process_name(pid) else {
+            map.complete = false;
+            continue;
+        };
+        for inode in inodes {
+            let owner = ProcessInfo {
+                pid,
+                name: name.clone(),
+            };
+            claim(&mut map.owners, inode, owner);
+        }
+    }
+    map
 }
 
-/// Known interpreters where comm name is more informative than binary name
+/// Interpreters, by binary name without a version suffix (`python3.11` is
+/// `python`), whose binary name says little about what's running.
 const INTERPRETERS: &[&str] = &[
-    "node", "python", "python3", "ruby", "perl", "php", "java", "bash", "sh", "zsh",
+    "node", "python", "ruby", "perl", "php", "java", "bash", "sh", "zsh",
 ];
 
+/// Interpreter options that take the next argument as their value, so it
+/// isn't mistaken for the script. Kept per family because the letters
+/// clash: python's `-I` and `-S` take nothing, ruby's `-I` a directory.
+const OPTIONS_WITH_VALUE: &[(&str, &[&str])] = &[
+    ("python", &["-W", "-X"]),
+    ("node", &["-r", "--require", "--inspect-port"]),
+    ("ruby", &["-I", "-r"]),
+    ("perl", &["-I"]),
+    ("php", &["-S", "-t"]),
+    ("java", &["-cp", "-classpath", "--class-path"]),
+];
+
+fn takes_value(family: &str, arg: &str) -> bool {
+    OPTIONS_WITH_VALUE
+        .iter()
+        .any(|(f, options)| *f == family && options.contains(&arg))
+}
+
+/// The script an interpreter runs, from its raw `/proc/<pid>/cmdline`
+/// (NUL-separated argv): the file name of the first argument that isn't an
+/// option, the module of `python -m`, or the jar of `java -jar`. `None`
+/// for a REPL, inline code (`-c`, `-e`), or a process that rewrote its
+/// argv into one string. `family` is the interpreter's
+/// [`interpreter_family`], which decides the options that take a value.
+fn script_from_cmdline(family: &str, cmdline: &[u8]) -> Option<String> {
+    let cmdline = String::from_utf8_lossy(cmdline);
+    let mut args = cmdline.split('\0').skip(1).filter(|a| !a.is_empty());
+    while let Some(arg) = args.next() {
+        let script = match arg {
+            "-m" | "-jar" | "--" => args.next()?,
+            "-c" | "-e" | "--eval" | "-p" | "--print" => return None,
+            _ if takes_value(family, arg) => {
+                args.next();
+                continue;
+            }
+            _ if arg.starts_with('-') => continue,
+            _ => arg,
+        };
+        let name = Path::new(script).file_name()?.to_string_lossy();
+        return Some(name.into_owned());
+    }
+    None
+}
+
 fn read_process_name(pid: u32) -> Result<String> {
     // Try exe symlink first for the full binary name (comm is limited to 15 chars)
     let exe_path = format!("/proc/{}/exe", pid);
@@ -80,8 +294,19 @@ fn read_process_name(pid: u32) -> Result<String> {
         .map(|s| s.trim().to_string());
 
     match (exe_name, comm_name) {
-        // If exe is an interpreter, prefer comm (e.g., "node" -> "openclaw-gatewa")
-        (Some(exe), Some(comm)) if INTERPRETERS.contains(&exe.as_str()) => Ok(comm),
+        // If exe is an interpreter, name it after the script it runs
+        // (`python:manage.py`), or else prefer comm, which a process can
+        // set itself (e.g., "node" -> "openclaw-gatewa")
+        (Some(exe), comm) if INTERPRETERS.contains(&interpreter_family(&exe)) => {
+            let script = fs::read(format!("/proc/{}/cmdline", pid))
+                .ok()
+                .and_then(|cmdline| script_from_cmdline(interpreter_family(&exe), &cmdline));
+            match (script, comm) {
+                (Some(script), _) => Ok(format!("{}:{script}", interpreter_family(&exe))),
+                (None, Some(comm)) => Ok(comm),
+                (None, None) => Ok(exe),
+            }
+        }
         // Otherwise use exe (full name, no 15 char limit)
         (Some(exe), _) => Ok(exe),
         // Fall back to comm
@@ -91,9 +316,9 @@ fn read_process_name(pid: u32) -> Result<String> {
     }
 }
 
-fn read_socket_inodes(pid: u32) -> Result<Vec<u64>> {
+fn read_socket_inodes(pid: u32) -> io::Result<Vec<u64>> {
     let fd_path = format!("/proc/{}/fd", pid);
-    let fd_dir = fs::read_dir(&fd_path).context("Failed to read fd dir")?;
+    let fd_dir = fs::read_dir(&fd_path)?;
 
     let mut inodes = Vec::new();
 
@@ -124,6 +349,77 @@ mod tests {
     use super::*;
     use std::path::PathBuf;
 
+    #[test]
+    fn shared_inode_goes_to_lowest_pid_in_any_order() {
+        let owner = |pid: u32| ProcessInfo {
+            pid,
+            name: format!("worker-{pid}"),
+        };
+        for order in [[4100, 4200], [4200, 4100]] {
+            let mut owners = HashMap::new();
+            for pid in order {
+                claim(&mut owners, 77, owner(pid));
+            }
+            assert_eq!(owners[&77].pid, 4100);
+            assert_eq!(owners[&77].name, "worker-4100");
+        }
+    }
+
+    #[test]
+    fn script_from_cmdline_finds_script_module_or_jar() {
+        let script = |argv: &[&str]| {
+            let exe = argv[0].rsplit('/').next().unwrap();
+            script_from_cmdline(interpreter_family(exe), argv.join("\0").as_bytes())
+        };
+        assert_eq!(
+            script(&[
+                "/usr/bin/python3",
+                "-u",
+                "/srv/app/manage.py",
+                "runserver",
+                ""
+            ]),
+            Some("manage.py".to_string())
+        );
+        assert_eq!(
+            script(&["python3", "-m", "http.server", "8000"]),
+            Some("http.server".to_string())
+        );
+        assert_eq!(
+            script(&["node", "--require", "ts-node/register", "server.ts"]),
+            Some("server.ts".to_string())
+        );
+        assert_eq!(
+            script(&["java", "-Xmx1g", "-jar", "/opt/app.jar"]),
+            Some("app.jar".to_string())
+        );
+        assert_eq!(
+            script(&["php", "-S", "localhost:8000", "-t", "public", "router.php"]),
+            Some("router.php".to_string())
+        );
+        assert_eq!(
+            script(&["php", "-S", "localhost:8000", "-t", "public"]),
+            None
+        );
+        assert_eq!(
+            script(&["ruby", "-I", "lib", "bin/server.rb"]),
+            Some("server.rb".to_string())
+        );
+        assert_eq!(
+            script(&["node", "--inspect-port", "9230", "app.js"]),
+            Some("app.js".to_string())
+        );
+        // Same letters, no value: python's -I (isolated) and -S (no site).
+        assert_eq!(
+            script(&["python3", "-I", "-S", "app.py"]),
+            Some("app.py".to_string())
+        );
+        assert_eq!(script(&["python3", "-c", "import time"]), None);
+        assert_eq!(script(&["node"]), None);
+        // argv rewritten by the process (`process.title`)
+        assert_eq!(script(&["openclaw-gateway --port 80"]), None);
+    }
+
     #[test]
     fn test_parse_socket_link_valid() {
         let path = PathBuf::from("socket:[12345]");
@@ -148,6 +444,23 @@ mod tests {
         assert_eq!(parse_socket_link(&path), None);
     }
 
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn inode_map_attributes_our_own_socket() {
+        use std::os::fd::AsRawFd;
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let link = fs::read_link(format!("/proc/self/fd/{}", listener.as_raw_fd())).unwrap();
+        let inode = parse_socket_link(&link).unwrap();
+
+        let map = build_inode_to_process_map();
+        assert_eq!(map.owners[&inode].pid, std::process::id());
+
+        let owners = find_inode_owners(&HashSet::from([inode]));
+        assert_eq!(owners.owners.len(), 1);
+        assert_eq!(owners.owners[&inode].pid, std::process::id());
+    }
+
     #[test]
     fn test_parse_socket_link_anon_inode() {
         let path = PathBuf::from("anon_inode:[eventfd]");
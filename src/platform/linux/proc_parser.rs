@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 
 use anyhow::{bail, Context, Result};
@@ -9,24 +10,12 @@ pub struct RawSocket {
     pub remote_addr: IpAddr,
     pub remote_port: u16,
     pub state: SocketState,
+    pub retransmits: u32,
+    pub uid: u32,
     pub inode: u64,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum SocketState {
-    Established,
-    SynSent,
-    SynRecv,
-    FinWait1,
-    FinWait2,
-    TimeWait,
-    Close,
-    CloseWait,
-    LastAck,
-    Listen,
-    Closing,
-    Unknown(u8),
-}
+pub use crate::types::SocketState;
 
 impl SocketState {
     fn from_hex(hex: &str) -> Result<Self> {
@@ -101,6 +90,8 @@ pub fn parse_socket_line(line: &str) -> Result<RawSocket> {
     let local = parts[1];
     let remote = parts[2];
     let state_hex = parts[3];
+    let retransmits_hex = parts[6];
+    let uid_str = parts[7];
     let inode_str = parts[9];
 
     let (local_addr_hex, local_port_hex) = local
@@ -116,16 +107,58 @@ pub fn parse_socket_line(line: &str) -> Result<RawSocket> {
         remote_addr: parse_hex_addr_any(remote_addr_hex)?,
         remote_port: parse_hex_port(remote_port_hex)?,
         state: SocketState::from_hex(state_hex)?,
+        retransmits: u32::from_str_radix(retransmits_hex, 16)
+            .context("Invalid retransmit count")?,
+        uid: uid_str.parse().context("Invalid uid")?,
         inode: inode_str.parse().context("Invalid inode")?,
     })
 }
 
 pub fn parse_proc_net_file(content: &str) -> Vec<RawSocket> {
-    content
-        .lines()
-        .skip(1)
-        .filter_map(|line| parse_socket_line(line).ok())
-        .collect()
+    parse_proc_net_file_verbose(content).0
+}
+
+/// Like [`parse_proc_net_file`], but also returns a description of each
+/// line that failed to parse (`line N: reason`, 1-based, counting the
+/// header) instead of silently dropping it.
+pub fn parse_proc_net_file_verbose(content: &str) -> (Vec<RawSocket>, Vec<String>) {
+    let mut sockets = Vec::new();
+    let mut failures = Vec::new();
+    for (i, line) in content.lines().enumerate().skip(1) {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match parse_socket_line(line) {
+            Ok(socket) => sockets.push(socket),
+            Err(e) => failures.push(format!("line {}: {e:#}", i + 1)),
+        }
+    }
+    (sockets, failures)
+}
+
+/// Map each IPv6 address in `/proc/net/if_inet6` to the interfaces that
+/// carry it. Unlike `/proc/net/tcp6`, addresses here are plain big-endian
+/// hex. Malformed lines are skipped.
+pub fn parse_if_inet6(content: &str) -> HashMap<Ipv6Addr, Vec<String>> {
+    let mut map: HashMap<Ipv6Addr, Vec<String>> = HashMap::new();
+    for line in content.lines() {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        if parts.len() < 6 || parts[0].len() != 32 {
+            continue;
+        }
+        let Ok(bits) = u128::from_str_radix(parts[0], 16) else {
+            continue;
+        };
+        map.entry(Ipv6Addr::from(bits))
+            .or_default()
+            .push(parts[5].to_string());
+    }
+    map
+}
+
+/// `fe80::/10`. `Ipv6Addr::is_unicast_link_local` needs a newer toolchain.
+pub fn is_link_local_v6(addr: &Ipv6Addr) -> bool {
+    (addr.segments()[0] & 0xffc0) == 0xfe80
 }
 
 #[cfg(test)]
@@ -202,9 +235,19 @@ mod tests {
         );
         assert_eq!(result.remote_port, 54321);
         assert_eq!(result.state, SocketState::Established);
+        assert_eq!(result.retransmits, 0);
         assert_eq!(result.inode, 12346);
     }
 
+    #[test]
+    fn test_parse_socket_line_retransmits_are_hex() {
+        let line = "   1: 0100007F:1F90 0501A8C0:D431 01 00000000:00000000 01:00000032 0000001A   500        0 12346 1 0000000000000000 100 0 0 10 0";
+
+        let result = parse_socket_line(line).unwrap();
+
+        assert_eq!(result.retransmits, 26);
+    }
+
     #[test]
     fn test_parse_socket_line_ipv6_listening() {
         let line = "   0: 00000000000000000000000001000000:1F90 00000000000000000000000000000000:0000 0A 00000000:00000000 00:00000000 00000000   500        0 12347 1 0000000000000000 100 0 0 10 0";
@@ -241,6 +284,34 @@ mod tests {
         assert_eq!(result.len(), 2);
         assert_eq!(result[0].local_port, 8080);
         assert_eq!(result[1].local_port, 80);
+        assert_eq!(result[0].uid, 500);
+        assert_eq!(result[1].uid, 0);
+    }
+
+    #[test]
+    fn test_parse_proc_net_file_verbose_reports_bad_lines() {
+        let content = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 0100007F:1F90 00000000:0000 0A 00000000:00000000 00:00000000 00000000   500        0 12345 1 0000000000000000 100 0 0 10 0
+   1: 0100007F:ZZZZ 00000000:0000 0A 00000000:00000000 00:00000000 00000000   500        0 12346 1 0000000000000000 100 0 0 10 0
+   2: truncated
+
+";
+
+        let (sockets, failures) = parse_proc_net_file_verbose(content);
+
+        assert_eq!(sockets.len(), 1);
+        assert_eq!(sockets[0].local_port, 8080);
+        assert_eq!(failures.len(), 2);
+        assert!(
+            failures[0].starts_with("line 3: Invalid port hex"),
+            "{}",
+            failures[0]
+        );
+        assert!(
+            failures[1].starts_with("line 4: Invalid socket line"),
+            "{}",
+            failures[1]
+        );
     }
 
     #[test]
@@ -278,4 +349,30 @@ mod tests {
         let result = parse_hex_addr_any("00000000000000000000000001000000").unwrap();
         assert_eq!(result, IpAddr::V6(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 1)));
     }
+
+    #[test]
+    fn test_parse_if_inet6() {
+        let content = "fe8000000000000000fc00fffe000001 04 40 20 80     eth0
+00000000000000000000000000000001 01 80 10 80       lo
+fe800000000000000000000000000001 05 40 20 80     eth1
+fe800000000000000000000000000001 06 40 20 80     eth2
+garbage";
+
+        let map = parse_if_inet6(content);
+
+        let eth0: Ipv6Addr = "fe80::fc:ff:fe00:1".parse().unwrap();
+        assert_eq!(map[&eth0], vec!["eth0".to_string()]);
+        assert_eq!(map[&Ipv6Addr::LOCALHOST], vec!["lo".to_string()]);
+        let shared: Ipv6Addr = "fe80::1".parse().unwrap();
+        assert_eq!(map[&shared], vec!["eth1".to_string(), "eth2".to_string()]);
+        assert_eq!(map.len(), 3);
+    }
+
+    #[test]
+    fn test_is_link_local_v6() {
+        assert!(is_link_local_v6(&"fe80::1".parse().unwrap()));
+        assert!(is_link_local_v6(&"febf::1".parse().unwrap()));
+        assert!(!is_link_local_v6(&"fec0::1".parse().unwrap()));
+        assert!(!is_link_local_v6(&Ipv6Addr::LOCALHOST));
+    }
 }
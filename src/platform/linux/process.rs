@@ -1,9 +1,10 @@
-//! Linux-specific process resolution: cmdline and cwd
-//! from `/proc/{pid}/`.
+//! Linux-specific process resolution: cmdline, cwd,
+//! executable path, and start time from `/proc/{pid}/`.
 
 use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use crate::types::PortInfo;
 
@@ -60,7 +61,20 @@ pub fn read_cwd(pid: u32) -> Option<PathBuf> {
     fs::read_link(path).ok()
 }
 
-/// Populate `command_line` and `cwd` on each `PortInfo`
+/// Read the executable path of a process from the
+/// `/proc/{pid}/exe` symlink.
+///
+/// Returns `None` on permission error or missing process.
+/// A binary that was replaced or unlinked after launch
+/// reads back with a ` (deleted)` suffix, which is kept.
+pub fn read_exe(pid: u32) -> Option<String> {
+    let path = format!("/proc/{}/exe", pid);
+    fs::read_link(path)
+        .ok()
+        .map(|exe| exe.to_string_lossy().into_owned())
+}
+
+/// Populate `command_line`, `cwd`, and `exe_path` on each `PortInfo`
 /// entry by reading from `/proc`.
 ///
 /// Collects unique PIDs first to avoid redundant reads
@@ -68,12 +82,14 @@ pub fn read_cwd(pid: u32) -> Option<PathBuf> {
 pub fn resolve_process_details(ports: &mut [PortInfo]) {
     let mut cmdlines: HashMap<u32, Option<String>> = HashMap::new();
     let mut cwds: HashMap<u32, Option<PathBuf>> = HashMap::new();
+    let mut exes: HashMap<u32, Option<String>> = HashMap::new();
 
     // Collect unique PIDs.
     for port in ports.iter() {
         if port.pid != 0 {
             cmdlines.entry(port.pid).or_insert(None);
             cwds.entry(port.pid).or_insert(None);
+            exes.entry(port.pid).or_insert(None);
         }
     }
 
@@ -84,6 +100,9 @@ pub fn resolve_process_details(ports: &mut [PortInfo]) {
     for (&pid, slot) in cwds.iter_mut() {
         *slot = read_cwd(pid);
     }
+    for (&pid, slot) in exes.iter_mut() {
+        *slot = read_exe(pid);
+    }
 
     // Distribute results.
     for port in ports.iter_mut() {
@@ -93,13 +112,132 @@ pub fn resolve_process_details(ports: &mut [PortInfo]) {
         if let Some(cwd) = cwds.get(&port.pid) {
             port.cwd = cwd.clone();
         }
+        if let Some(exe) = exes.get(&port.pid) {
+            port.exe_path = exe.clone();
+        }
+    }
+}
+
+/// Values of `keys` in raw `/proc/{pid}/environ` bytes
+/// (NUL-separated `KEY=value` pairs), in the order asked for.
+///
+/// Keys that aren't set are left out. If a key appears
+/// twice, the first value wins, as with `getenv`.
+pub fn parse_environ(bytes: &[u8], keys: &[String]) -> Vec<(String, String)> {
+    let mut vars: HashMap<&[u8], &[u8]> = HashMap::new();
+    for entry in bytes.split(|&b| b == 0) {
+        if let Some(eq) = entry.iter().position(|&b| b == b'=') {
+            vars.entry(&entry[..eq]).or_insert(&entry[eq + 1..]);
+        }
     }
+    keys.iter()
+        .filter_map(|key| {
+            let value = vars.get(key.as_bytes())?;
+            Some((key.clone(), String::from_utf8_lossy(value).into_owned()))
+        })
+        .collect()
+}
+
+/// Read the requested environment variables of a process.
+///
+/// Returns `None` on permission error (another user's
+/// process) or missing process.
+pub fn read_environ(pid: u32, keys: &[String]) -> Option<Vec<(String, String)>> {
+    let bytes = fs::read(format!("/proc/{}/environ", pid)).ok()?;
+    Some(parse_environ(&bytes, keys))
+}
+
+/// Start time of a process in clock ticks since boot: field 22
+/// of `/proc/{pid}/stat`.
+///
+/// Fields are counted from the last `)`, since the command
+/// name before it may itself contain spaces or parentheses.
+pub fn parse_stat_starttime(stat: &str) -> Option<u64> {
+    let rest = &stat[stat.rfind(')')? + 1..];
+    // `rest` starts at field 3 (state).
+    rest.split_whitespace().nth(22 - 3)?.parse().ok()
+}
+
+/// Boot time in seconds since the epoch, from the `btime`
+/// line of `/proc/stat`.
+pub fn parse_btime(stat: &str) -> Option<u64> {
+    stat.lines()
+        .find_map(|line| line.strip_prefix("btime "))?
+        .trim()
+        .parse()
+        .ok()
+}
+
+fn clock_ticks_per_sec() -> u64 {
+    use nix::unistd::{sysconf, SysconfVar};
+
+    sysconf(SysconfVar::CLK_TCK)
+        .ok()
+        .flatten()
+        .and_then(|ticks| u64::try_from(ticks).ok())
+        .filter(|&ticks| ticks > 0)
+        .unwrap_or(100)
+}
+
+/// Start time of each PID, from `/proc/{pid}/stat` and the
+/// boot time in `/proc/stat`.
+///
+/// PIDs that exited or can't be read are left out.
+pub fn read_start_times(pids: &[u32]) -> HashMap<u32, SystemTime> {
+    let Some(btime) = fs::read_to_string("/proc/stat")
+        .ok()
+        .and_then(|stat| parse_btime(&stat))
+    else {
+        return HashMap::new();
+    };
+    let boot = UNIX_EPOCH + Duration::from_secs(btime);
+    let ticks = clock_ticks_per_sec();
+
+    pids.iter()
+        .filter_map(|&pid| {
+            let stat = fs::read_to_string(format!("/proc/{pid}/stat")).ok()?;
+            let start = parse_stat_starttime(&stat)?;
+            Some((pid, boot + Duration::from_millis(start * 1000 / ticks)))
+        })
+        .collect()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_parse_stat_starttime_skips_tricky_comm() {
+        let stat = "4242 (my (weird) srv) S 1 4242 4242 0 -1 4194560 1090 0 0 0 \
+                    3 1 0 0 20 0 1 0 123456 10240000 500 18446744073709551615";
+        assert_eq!(parse_stat_starttime(stat), Some(123456));
+        assert_eq!(parse_stat_starttime("4242 (truncated) S 1"), None);
+    }
+
+    #[test]
+    fn test_parse_environ_picks_requested_keys() {
+        let environ = b"PATH=/usr/bin\0NODE_ENV=production\0PORT=3000\0EMPTY=\0PORT=9999\0";
+        let keys: Vec<String> = ["PORT", "NODE_ENV", "RAILS_ENV", "EMPTY"]
+            .map(String::from)
+            .to_vec();
+        assert_eq!(
+            parse_environ(environ, &keys),
+            [
+                ("PORT".to_string(), "3000".to_string()),
+                ("NODE_ENV".to_string(), "production".to_string()),
+                ("EMPTY".to_string(), String::new()),
+            ]
+        );
+        assert!(parse_environ(b"", &keys).is_empty());
+    }
+
+    #[test]
+    fn test_parse_btime() {
+        let stat = "cpu  1 2 3 4\nintr 5\nbtime 1760000000\nprocesses 99\n";
+        assert_eq!(parse_btime(stat), Some(1760000000));
+        assert_eq!(parse_btime("cpu 1 2 3\n"), None);
+    }
+
     #[test]
     fn test_parse_cmdline_normal() {
         let input = b"/usr/bin/node\0server.js\0--port\x003000\0";
@@ -153,12 +291,7 @@ mod tests {
                 pid: 0, // PID 0 should be skipped
                 process_name: "kernel".into(),
                 address: "0.0.0.0:80".into(),
-                remote_address: None,
-                container: None,
-                service_name: None,
-                command_line: None,
-                cwd: None,
-                framework: None,
+                ..Default::default()
             },
             PortInfo {
                 port: 443,
@@ -166,12 +299,7 @@ mod tests {
                 pid: 0,
                 process_name: "kernel".into(),
                 address: "0.0.0.0:443".into(),
-                remote_address: None,
-                container: None,
-                service_name: None,
-                command_line: None,
-                cwd: None,
-                framework: None,
+                ..Default::default()
             },
         ];
 
@@ -181,5 +309,12 @@ mod tests {
         assert!(ports[0].cwd.is_none());
         assert!(ports[1].command_line.is_none());
         assert!(ports[1].cwd.is_none());
+        assert!(ports[1].exe_path.is_none());
+    }
+
+    #[test]
+    fn test_read_exe_resolves_own_binary() {
+        let exe = read_exe(std::process::id()).unwrap();
+        assert_eq!(exe, std::env::current_exe().unwrap().to_string_lossy(),);
     }
 }
@@ -0,0 +1,242 @@
+use std::collections::HashMap;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+const SOCK_DIAG_BY_FAMILY: u16 = 20;
+const NLM_F_REQUEST: u16 = 0x1;
+const NLM_F_DUMP: u16 = 0x300;
+const NLMSG_ERROR: u16 = 2;
+const NLMSG_DONE: u16 = 3;
+const NLMSG_HDR_LEN: usize = 16;
+
+const AF_INET: u8 = 2;
+const AF_INET6: u8 = 10;
+const IPPROTO_TCP: u8 = 6;
+const INET_DIAG_INFO: u16 = 2;
+/// Every TCP state but LISTEN, which never carries data.
+const STATES: u32 = 0x1fff & !(1 << 10);
+
+/// `struct inet_diag_msg`, before its attributes.
+const DIAG_MSG_LEN: usize = 72;
+/// `tcpi_bytes_acked` and `tcpi_bytes_received` in `struct tcp_info`
+/// (Linux 4.1+).
+const BYTES_ACKED: usize = 120;
+const BYTES_RECEIVED: usize = 128;
+
+/// Running byte totals of one TCP connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TcpCounters {
+    /// Bytes sent and acknowledged by the peer.
+    pub sent: u64,
+    pub received: u64,
+}
+
+/// Local port and remote address, the latter formatted as in
+/// `PortInfo::remote_address`.
+pub type ConnKey = (u16, String);
+
+/// Byte counters of every TCP connection in our network namespace, from
+/// the kernel's `sock_diag` netlink interface. `/proc/net/tcp` only shows
+/// queue sizes; the running totals live in `tcp_info`, which a dump with
+/// `INET_DIAG_INFO` returns per socket.
+#[cfg(target_os = "linux")]
+pub fn tcp_counters() -> io::Result<HashMap<ConnKey, TcpCounters>> {
+    use std::os::fd::AsRawFd;
+
+    use nix::sys::socket::{
+        recv, sendto, socket, AddressFamily, MsgFlags, NetlinkAddr, SockFlag, SockProtocol,
+        SockType,
+    };
+
+    let fd = socket(
+        AddressFamily::Netlink,
+        SockType::Raw,
+        SockFlag::SOCK_CLOEXEC,
+        SockProtocol::NetlinkSockDiag,
+    )?;
+    let kernel = NetlinkAddr::new(0, 0);
+    let mut counters = HashMap::new();
+    let mut buf = vec![0u8; 32 * 1024];
+    for family in [AF_INET, AF_INET6] {
+        sendto(
+            fd.as_raw_fd(),
+            &dump_request(family),
+            &kernel,
+            MsgFlags::empty(),
+        )?;
+        loop {
+            let n = recv(fd.as_raw_fd(), &mut buf, MsgFlags::empty())?;
+            if n == 0 || parse_replies(&buf[..n], &mut counters)? {
+                break;
+            }
+        }
+    }
+    Ok(counters)
+}
+
+/// A `SOCK_DIAG_BY_FAMILY` dump of every non-listening TCP socket of
+/// `family`, asking for `tcp_info`.
+fn dump_request(family: u8) -> Vec<u8> {
+    const LEN: usize = NLMSG_HDR_LEN + 56;
+    let mut req = Vec::with_capacity(LEN);
+    // struct nlmsghdr: len, type, flags, seq, pid
+    req.extend_from_slice(&(LEN as u32).to_ne_bytes());
+    req.extend_from_slice(&SOCK_DIAG_BY_FAMILY.to_ne_bytes());
+    req.extend_from_slice(&(NLM_F_REQUEST | NLM_F_DUMP).to_ne_bytes());
+    req.extend_from_slice(&[0; 8]);
+    // struct inet_diag_req_v2: family, protocol, ext, pad, states, and an
+    // all-zero inet_diag_sockid, which matches every socket
+    req.extend_from_slice(&[family, IPPROTO_TCP, 1 << (INET_DIAG_INFO - 1), 0]);
+    req.extend_from_slice(&STATES.to_ne_bytes());
+    req.extend_from_slice(&[0; 48]);
+    req
+}
+
+/// Add the sockets in one `recv` worth of dump replies to `out`. `Ok(true)`
+/// once the dump is complete.
+fn parse_replies(buf: &[u8], out: &mut HashMap<ConnKey, TcpCounters>) -> io::Result<bool> {
+    let mut rest = buf;
+    while rest.len() >= NLMSG_HDR_LEN {
+        let len = u32::from_ne_bytes(rest[0..4].try_into().unwrap()) as usize;
+        let kind = u16::from_ne_bytes(rest[4..6].try_into().unwrap());
+        if len < NLMSG_HDR_LEN || len > rest.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "truncated sock_diag reply",
+            ));
+        }
+        let payload = &rest[NLMSG_HDR_LEN..len];
+        match kind {
+            NLMSG_DONE => return Ok(true),
+            NLMSG_ERROR => {
+                let errno = payload
+                    .get(..4)
+                    .map_or(0, |b| i32::from_ne_bytes(b.try_into().unwrap()));
+                if errno == 0 {
+                    return Ok(true);
+                }
+                return Err(io::Error::from_raw_os_error(-errno));
+            }
+            SOCK_DIAG_BY_FAMILY => parse_diag_msg(payload, out),
+            _ => {}
+        }
+        rest = &rest[align(len).min(rest.len())..];
+    }
+    Ok(false)
+}
+
+/// One `struct inet_diag_msg` and its attributes. Sockets without a
+/// `tcp_info` long enough to hold the byte counters are skipped.
+fn parse_diag_msg(msg: &[u8], out: &mut HashMap<ConnKey, TcpCounters>) {
+    if msg.len() < DIAG_MSG_LEN {
+        return;
+    }
+    // struct inet_diag_sockid: sport, dport (big-endian), src[16], dst[16]
+    let id = &msg[4..52];
+    let sport = u16::from_be_bytes([id[0], id[1]]);
+    let dport = u16::from_be_bytes([id[2], id[3]]);
+    let dst = &id[20..36];
+    let remote = match msg[0] {
+        AF_INET => IpAddr::V4(Ipv4Addr::new(dst[0], dst[1], dst[2], dst[3])),
+        AF_INET6 => IpAddr::V6(Ipv6Addr::from(<[u8; 16]>::try_from(dst).unwrap())),
+        _ => return,
+    };
+
+    let mut attrs = &msg[DIAG_MSG_LEN..];
+    while attrs.len() >= 4 {
+        let len = u16::from_ne_bytes([attrs[0], attrs[1]]) as usize;
+        let kind = u16::from_ne_bytes([attrs[2], attrs[3]]);
+        if len < 4 || len > attrs.len() {
+            return;
+        }
+        if kind == INET_DIAG_INFO {
+            let info = &attrs[4..len];
+            if let (Some(sent), Some(received)) =
+                (read_u64(info, BYTES_ACKED), read_u64(info, BYTES_RECEIVED))
+            {
+                out.insert(
+                    (sport, format!("{remote}:{dport}")),
+                    TcpCounters { sent, received },
+                );
+            }
+        }
+        attrs = &attrs[align(len).min(attrs.len())..];
+    }
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> Option<u64> {
+    let field = bytes.get(offset..offset + 8)?;
+    Some(u64::from_ne_bytes(field.try_into().unwrap()))
+}
+
+/// Netlink messages and attributes are padded to 4 bytes.
+fn align(len: usize) -> usize {
+    (len + 3) & !3
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nlmsg(kind: u16, payload: &[u8]) -> Vec<u8> {
+        let mut msg = Vec::new();
+        msg.extend_from_slice(&((NLMSG_HDR_LEN + payload.len()) as u32).to_ne_bytes());
+        msg.extend_from_slice(&kind.to_ne_bytes());
+        msg.extend_from_slice(&[0; 10]);
+        msg.extend_from_slice(payload);
+        msg
+    }
+
+    fn diag_msg(sport: u16, dst: [u8; 4], dport: u16, sent: u64, received: u64) -> Vec<u8> {
+        let mut msg = vec![AF_INET, 1, 0, 0];
+        msg.extend_from_slice(&sport.to_be_bytes());
+        msg.extend_from_slice(&dport.to_be_bytes());
+        msg.extend_from_slice(&[127, 0, 0, 1]);
+        msg.extend_from_slice(&[0; 12]);
+        msg.extend_from_slice(&dst);
+        msg.extend_from_slice(&[0; 12]);
+        msg.extend_from_slice(&[0; 12]); // interface, cookie
+        msg.extend_from_slice(&[0; 20]); // expires .. inode
+        let mut info = vec![0u8; 136];
+        info[BYTES_ACKED..BYTES_ACKED + 8].copy_from_slice(&sent.to_ne_bytes());
+        info[BYTES_RECEIVED..].copy_from_slice(&received.to_ne_bytes());
+        msg.extend_from_slice(&((4 + info.len()) as u16).to_ne_bytes());
+        msg.extend_from_slice(&INET_DIAG_INFO.to_ne_bytes());
+        msg.extend_from_slice(&info);
+        msg
+    }
+
+    #[test]
+    fn dump_request_is_one_whole_message() {
+        let req = dump_request(AF_INET6);
+        assert_eq!(req.len(), 72);
+        assert_eq!(u32::from_ne_bytes(req[0..4].try_into().unwrap()), 72);
+        assert_eq!(&req[16..19], &[AF_INET6, IPPROTO_TCP, 2]);
+    }
+
+    #[test]
+    fn parses_byte_counters_until_done() {
+        let mut buf = nlmsg(
+            SOCK_DIAG_BY_FAMILY,
+            &diag_msg(8080, [10, 0, 0, 2], 51000, 1500, 42),
+        );
+        let mut out = HashMap::new();
+        assert!(!parse_replies(&buf, &mut out).unwrap());
+        buf.extend(nlmsg(NLMSG_DONE, &[0; 4]));
+        assert!(parse_replies(&buf, &mut out).unwrap());
+        assert_eq!(
+            out.get(&(8080, "10.0.0.2:51000".to_string())),
+            Some(&TcpCounters {
+                sent: 1500,
+                received: 42
+            })
+        );
+    }
+
+    #[test]
+    fn error_reply_is_an_error() {
+        let buf = nlmsg(NLMSG_ERROR, &(-13i32).to_ne_bytes());
+        let err = parse_replies(&buf, &mut HashMap::new()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+    }
+}
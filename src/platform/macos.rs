@@ -6,18 +6,21 @@
 use std::collections::HashMap;
 use std::path::PathBuf;
 use std::process::Command;
+use std::time::{Duration, SystemTime};
 
-use anyhow::{Context, Result};
+use crate::error::PortsError;
+use crate::types::{PortInfo, Protocol, SocketState};
 
-use crate::types::{PortInfo, Protocol};
-
-pub fn get_connections() -> Result<Vec<PortInfo>> {
+pub fn get_connections() -> Result<Vec<PortInfo>, PortsError> {
     let output = Command::new("lsof")
         .args(["-i", "-n", "-P"])
         .output()
-        .context("Failed to execute lsof")?;
+        .map_err(|e| PortsError::io("Failed to execute lsof", e))?;
 
-    let stdout = String::from_utf8(output.stdout).context("Invalid UTF-8 from lsof")?;
+    let stdout = String::from_utf8(output.stdout).map_err(|_| PortsError::ProcParse {
+        path: "lsof output".into(),
+        detail: "invalid UTF-8".to_string(),
+    })?;
 
     Ok(parse_lsof_output(&stdout))
 }
@@ -31,15 +34,25 @@ fn parse_lsof_output(output: &str) -> Vec<PortInfo> {
         .collect()
 }
 
+/// Parse one `lsof -i -n -P` line:
+///
+/// ```text
+/// COMMAND PID USER FD TYPE DEVICE SIZE/OFF NODE NAME [STATE]
+/// ```
+///
+/// NAME is `local` or `local->remote`; hosts may be IPv4, bracketed IPv6,
+/// `*`, or a name like `localhost`. TCP lines end with a `(STATE)`
+/// column; UDP lines have none.
 fn parse_lsof_line(line: &str) -> Option<PortInfo> {
     let parts: Vec<&str> = line.split_whitespace().collect();
 
-    if parts.len() < 10 {
+    if parts.len() < 9 {
         return None;
     }
 
     let command = parts[0];
     let pid: u32 = parts[1].parse().ok()?;
+    let user = parts[2];
     let protocol_type = parts[7];
     let name = parts[8];
 
@@ -55,10 +68,18 @@ fn parse_lsof_line(line: &str) -> Option<PortInfo> {
 
     let port = extract_local_port(name)?;
 
-    let (local_addr, remote_address) = if let Some((local, remote)) = name.split_once("->") {
-        (local.to_string(), Some(remote.to_string()))
-    } else {
-        (name.to_string(), None)
+    let (local_addr, remote_address) = match name.split_once("->") {
+        // Unconnected UDP sockets may print a wildcard peer.
+        Some((local, "*:*")) => (local.to_string(), None),
+        Some((local, remote)) => {
+            split_host_port(remote)?;
+            (local.to_string(), Some(remote.to_string()))
+        }
+        None => (name.to_string(), None),
+    };
+    let state = match protocol {
+        Protocol::Tcp => parts.get(9).and_then(|s| parse_lsof_state(s)),
+        Protocol::Udp => None,
     };
 
     Some(PortInfo {
@@ -68,23 +89,46 @@ fn parse_lsof_line(line: &str) -> Option<PortInfo> {
         process_name: command.to_string(),
         address: local_addr,
         remote_address,
-        container: None,
-        service_name: None,
-        command_line: None,
-        cwd: None,
-        framework: None,
+        user: Some(user.to_string()),
+        state,
+        ..Default::default()
+    })
+}
+
+/// Parse the parenthesized state `lsof` appends to TCP lines, e.g.
+/// `(ESTABLISHED)`. Returns `None` for anything unrecognized.
+fn parse_lsof_state(s: &str) -> Option<SocketState> {
+    let name = s.strip_prefix('(')?.strip_suffix(')')?;
+    Some(match name {
+        "ESTABLISHED" => SocketState::Established,
+        "SYN_SENT" => SocketState::SynSent,
+        "SYN_RECEIVED" => SocketState::SynRecv,
+        "FIN_WAIT_1" => SocketState::FinWait1,
+        "FIN_WAIT_2" => SocketState::FinWait2,
+        "TIME_WAIT" => SocketState::TimeWait,
+        "CLOSED" => SocketState::Close,
+        "CLOSE_WAIT" => SocketState::CloseWait,
+        "LAST_ACK" => SocketState::LastAck,
+        "LISTEN" => SocketState::Listen,
+        "CLOSING" => SocketState::Closing,
+        _ => return None,
     })
 }
 
 fn extract_local_port(name: &str) -> Option<u16> {
-    let local_part = if name.contains("->") {
-        name.split("->").next()?
-    } else {
-        name.trim_end_matches(|c: char| c == ')' || c == '(' || c.is_alphabetic())
-    };
+    let local_part = name.split("->").next()?;
+    let (_, port) = split_host_port(local_part)?;
+    port.parse().ok()
+}
 
-    let port_str = local_part.rsplit(':').next()?;
-    port_str.parse().ok()
+/// Split `host:port` at the last colon, checking that bracketed IPv6
+/// hosts are closed: `[::1]:5432` -> (`[::1]`, `5432`).
+fn split_host_port(endpoint: &str) -> Option<(&str, &str)> {
+    let (host, port) = endpoint.rsplit_once(':')?;
+    if host.is_empty() || (host.starts_with('[') != host.ends_with(']')) {
+        return None;
+    }
+    Some((host, port))
 }
 
 /// Parse `ps -o pid=,args=` output into a PID-to-command map.
@@ -174,6 +218,31 @@ fn batch_resolve_cmdlines(pids: &[u32]) -> HashMap<u32, String> {
     }
 }
 
+/// Resolve executable paths for all PIDs in a single `ps`
+/// call. On macOS `comm` is the full path of the executable.
+///
+/// Returns an empty map on subprocess failure.
+fn batch_resolve_exes(pids: &[u32]) -> HashMap<u32, String> {
+    if pids.is_empty() {
+        return HashMap::new();
+    }
+    let csv: String = pids
+        .iter()
+        .map(|p| p.to_string())
+        .collect::<Vec<_>>()
+        .join(",");
+    let output = Command::new("ps")
+        .args(["-p", &csv, "-o", "pid=,comm="])
+        .output();
+    match output {
+        Ok(o) => {
+            let stdout = String::from_utf8_lossy(&o.stdout);
+            parse_ps_output(&stdout)
+        }
+        Err(_) => HashMap::new(),
+    }
+}
+
 /// Resolve working directories for all PIDs in one `lsof`.
 ///
 /// Returns an empty map on subprocess failure.
@@ -225,6 +294,7 @@ pub fn resolve_process_details(ports: &mut [PortInfo]) {
 
     let cmdlines = batch_resolve_cmdlines(&pids);
     let cwds = batch_resolve_cwds(&pids);
+    let exes = batch_resolve_exes(&pids);
 
     for port in ports.iter_mut() {
         if let Some(cmd) = cmdlines.get(&port.pid) {
@@ -233,13 +303,75 @@ pub fn resolve_process_details(ports: &mut [PortInfo]) {
         if let Some(cwd) = cwds.get(&port.pid) {
             port.cwd = Some(cwd.clone());
         }
+        if let Some(exe) = exes.get(&port.pid) {
+            port.exe_path = Some(exe.clone());
+        }
     }
 }
 
+/// Parse a `ps -o etime` elapsed time, `[[dd-]hh:]mm:ss`.
+fn parse_etime(etime: &str) -> Option<Duration> {
+    let (days, clock) = match etime.trim().split_once('-') {
+        Some((days, clock)) => (days.parse::<u64>().ok()?, clock),
+        None => (0, etime.trim()),
+    };
+    let parts = clock
+        .split(':')
+        .map(|part| part.parse::<u64>().ok())
+        .collect::<Option<Vec<_>>>()?;
+    let (hours, mins, secs) = match parts[..] {
+        [mins, secs] => (0, mins, secs),
+        [hours, mins, secs] => (hours, mins, secs),
+        _ => return None,
+    };
+    Some(Duration::from_secs(
+        ((days * 24 + hours) * 60 + mins) * 60 + secs,
+    ))
+}
+
+/// Start times for all PIDs in a single `ps` call, derived from
+/// each process's elapsed running time.
+///
+/// Returns an empty map on subprocess failure.
+pub fn process_start_times(pids: &[u32]) -> HashMap<u32, SystemTime> {
+    if pids.is_empty() {
+        return HashMap::new();
+    }
+    let csv: String = pids
+        .iter()
+        .map(|p| p.to_string())
+        .collect::<Vec<_>>()
+        .join(",");
+    let Ok(output) = Command::new("ps")
+        .args(["-p", &csv, "-o", "pid=,etime="])
+        .output()
+    else {
+        return HashMap::new();
+    };
+    let now = SystemTime::now();
+    parse_ps_output(&String::from_utf8_lossy(&output.stdout))
+        .into_iter()
+        .filter_map(|(pid, etime)| Some((pid, now - parse_etime(&etime)?)))
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_parse_etime() {
+        assert_eq!(parse_etime("00:07"), Some(Duration::from_secs(7)));
+        assert_eq!(parse_etime("   05:03"), Some(Duration::from_secs(303)));
+        assert_eq!(parse_etime("01:00:00"), Some(Duration::from_secs(3600)));
+        assert_eq!(
+            parse_etime("2-03:04:05"),
+            Some(Duration::from_secs(2 * 86400 + 3 * 3600 + 4 * 60 + 5))
+        );
+        assert_eq!(parse_etime("7"), None);
+        assert_eq!(parse_etime("x-00:01"), None);
+    }
+
     #[test]
     fn test_parse_lsof_line_established() {
         let line = "node      12345 user   23u  IPv4 0x1234567890abcdef      0t0  TCP 127.0.0.1:3000->192.168.1.5:54321 (ESTABLISHED)";
@@ -252,6 +384,8 @@ mod tests {
         assert_eq!(result.protocol, Protocol::Tcp);
         assert_eq!(result.address, "127.0.0.1:3000");
         assert_eq!(result.remote_address, Some("192.168.1.5:54321".to_string()));
+        assert_eq!(result.state, Some(SocketState::Established));
+        assert_eq!(result.user.as_deref(), Some("user"));
     }
 
     #[test]
@@ -263,6 +397,7 @@ mod tests {
 
         assert_eq!(result.port, 3000);
         assert!(result.remote_address.is_none());
+        assert_eq!(result.state, Some(SocketState::Listen));
     }
 
     #[test]
@@ -277,6 +412,16 @@ node      12345 user   24u  IPv4 0x456      0t0  TCP *:3000 (LISTEN)";
         assert!(result[0].remote_address.is_some());
     }
 
+    #[test]
+    fn test_parse_lsof_state() {
+        assert_eq!(
+            parse_lsof_state("(CLOSE_WAIT)"),
+            Some(SocketState::CloseWait)
+        );
+        assert_eq!(parse_lsof_state("(BOUND)"), None);
+        assert_eq!(parse_lsof_state("LISTEN"), None);
+    }
+
     #[test]
     fn test_extract_local_port_established() {
         assert_eq!(
@@ -293,6 +438,55 @@ node      12345 user   24u  IPv4 0x456      0t0  TCP *:3000 (LISTEN)";
     #[test]
     fn test_extract_local_port_ipv6() {
         assert_eq!(extract_local_port("[::1]:5432"), Some(5432));
+        assert_eq!(extract_local_port("[::1:5432"), None);
+    }
+
+    #[test]
+    fn test_parse_lsof_line_host_name() {
+        let line = "postgres    501 user    7u  IPv4 0x1234      0t0  TCP localhost:5432->localhost:60012 (ESTABLISHED)";
+
+        let result = parse_lsof_line(line).unwrap();
+
+        assert_eq!(result.port, 5432);
+        assert_eq!(result.address, "localhost:5432");
+        assert_eq!(result.remote_address.as_deref(), Some("localhost:60012"));
+    }
+
+    #[test]
+    fn test_parse_lsof_line_ipv6_remote() {
+        let line = "node      12345 user   25u  IPv6 0x1234      0t0  TCP [::1]:3000->[fe80::1%lo0]:54321 (ESTABLISHED)";
+
+        let result = parse_lsof_line(line).unwrap();
+
+        assert_eq!(result.port, 3000);
+        assert_eq!(result.address, "[::1]:3000");
+        assert_eq!(
+            result.remote_address.as_deref(),
+            Some("[fe80::1%lo0]:54321")
+        );
+        assert_eq!(result.state, Some(SocketState::Established));
+    }
+
+    #[test]
+    fn test_parse_lsof_line_udp_without_state() {
+        let listening = "mDNSRespo   321 _mdns   8u  IPv4 0x1234      0t0  UDP *:5353";
+        let result = parse_lsof_line(listening).unwrap();
+        assert_eq!(result.protocol, Protocol::Udp);
+        assert_eq!(result.port, 5353);
+        assert!(result.remote_address.is_none());
+        assert_eq!(result.state, None);
+
+        let connected = "dig       777 user    5u  IPv6 0x5678      0t0  UDP [::1]:53000->[::1]:53";
+        let result = parse_lsof_line(connected).unwrap();
+        assert_eq!(result.port, 53000);
+        assert_eq!(result.remote_address.as_deref(), Some("[::1]:53"));
+        assert_eq!(result.state, None);
+
+        let wildcard_peer = "syslogd   88 root    5u  IPv4 0x9abc      0t0  UDP *:514->*:*";
+        assert!(parse_lsof_line(wildcard_peer)
+            .unwrap()
+            .remote_address
+            .is_none());
     }
 
     // --- Parser tests (no #[cfg] gate: pure string parsing) ---
@@ -368,12 +562,7 @@ node      12345 user   24u  IPv4 0x456      0t0  TCP *:3000 (LISTEN)";
             pid: our_pid,
             process_name: "test".to_string(),
             address: "127.0.0.1:9999".to_string(),
-            remote_address: None,
-            container: None,
-            service_name: None,
-            command_line: None,
-            cwd: None,
-            framework: None,
+            ..Default::default()
         }];
         resolve_process_details(&mut ports);
         let has_detail = ports[0].command_line.is_some() || ports[0].cwd.is_some();
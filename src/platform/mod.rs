@@ -2,10 +2,16 @@
 //!
 //! Uses native `/proc/net` parsing on Linux, `lsof` on macOS.
 
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::OnceLock;
+use std::time::SystemTime;
+
 use anyhow::Result;
 
-use crate::framework;
+use crate::error::PortsError;
 use crate::types::{DockerStatus, PortInfo};
+use crate::{dns, framework};
 
 #[cfg(any(target_os = "linux", test))]
 pub mod linux;
@@ -29,7 +35,8 @@ fn resolve_services(mut ports: Vec<PortInfo>) -> Vec<PortInfo> {
     ports
 }
 
-/// Populate `command_line` and `cwd` on each `PortInfo`.
+/// Populate `command_line`, `cwd`, `exe_path`, and the health
+/// `warnings` derived from them on each `PortInfo`.
 ///
 /// Dispatches by target OS, not by which module produced the
 /// ports. On macOS, listening ports come from the fallback
@@ -44,6 +51,9 @@ fn enrich_process_details(mut ports: Vec<PortInfo>) -> Vec<PortInfo> {
     #[cfg(not(any(target_os = "linux", target_os = "macos")))]
     fallback::resolve_process_details(&mut ports);
 
+    for p in &mut ports {
+        p.resolve_health_warnings();
+    }
     ports
 }
 
@@ -52,40 +62,401 @@ fn enrich_process_details(mut ports: Vec<PortInfo>) -> Vec<PortInfo> {
 /// Order is intentional: well-known service names first (cheap), then
 /// per-process details (PID-fanout), then Docker container names (cache
 /// hit fast, miss slow), then framework detection (consumes everything
-/// upstream). Docker is the only step that yields a status worth
-/// surfacing — the rest can't fail in a way users need to know about.
+/// upstream), then peer host names with `--resolve-names`. Docker is the
+/// only step that yields a status worth surfacing — the rest can't fail
+/// in a way users need to know about.
 fn enrich(ports: Vec<PortInfo>) -> PortListing {
     let ports = resolve_services(ports);
     let ports = enrich_process_details(ports);
-    let (ports, docker_status) = PortInfo::enrich_with_docker(ports);
-    let ports = framework::resolve_frameworks(ports);
+    let (ports, docker_status) = if skip_docker() {
+        (ports, DockerStatus::NotQueried)
+    } else {
+        PortInfo::enrich_with_docker(ports)
+    };
+    let mut ports = framework::resolve_frameworks(ports);
+    if dns::enabled() {
+        dns::resolve_remote_hosts(&mut ports);
+    }
     PortListing {
         ports,
         docker_status,
     }
 }
 
+// Only the `/proc` parsers have diagnostics to print; like the `linux`
+// module, the getter is built on Linux and for tests.
+#[cfg(any(target_os = "linux", test))]
+static VERBOSE: OnceLock<bool> = OnceLock::new();
+
+/// Turn on stderr diagnostics from the platform parsers (`--verbose`).
+/// Call once, before the first enumeration. A no-op outside Linux.
+pub fn set_verbose(on: bool) {
+    #[cfg(any(target_os = "linux", test))]
+    let _ = VERBOSE.set(on);
+    #[cfg(not(any(target_os = "linux", test)))]
+    let _ = on;
+}
+
+/// Whether `--verbose` diagnostics are on. Defaults to off, so library
+/// callers and tests stay quiet.
+#[cfg(any(target_os = "linux", test))]
+pub(crate) fn verbose() -> bool {
+    VERBOSE.get().copied().unwrap_or(false)
+}
+
+#[cfg(any(target_os = "linux", test))]
+static ALL_NETNS: OnceLock<bool> = OnceLock::new();
+
+/// Also enumerate sockets in network namespaces other than our own
+/// (`--all-netns`, Linux only). Call once, before the first enumeration.
+pub fn set_all_netns(on: bool) {
+    #[cfg(any(target_os = "linux", test))]
+    let _ = ALL_NETNS.set(on);
+    #[cfg(not(any(target_os = "linux", test)))]
+    let _ = on;
+}
+
+#[cfg(any(target_os = "linux", test))]
+pub(crate) fn all_netns() -> bool {
+    ALL_NETNS.get().copied().unwrap_or(false)
+}
+
+#[cfg(any(target_os = "linux", test))]
+static SHOW_KERNEL: OnceLock<bool> = OnceLock::new();
+
+/// Label sockets no process holds as `[kernel]` instead of `?` when every
+/// process could be inspected (`--kernel`, Linux only). Call once, before
+/// the first enumeration.
+pub fn set_show_kernel(on: bool) {
+    #[cfg(any(target_os = "linux", test))]
+    let _ = SHOW_KERNEL.set(on);
+    #[cfg(not(any(target_os = "linux", test)))]
+    let _ = on;
+}
+
+#[cfg(any(target_os = "linux", test))]
+pub(crate) fn show_kernel() -> bool {
+    SHOW_KERNEL.get().copied().unwrap_or(false)
+}
+
+static SKIP_DOCKER: OnceLock<bool> = OnceLock::new();
+
+/// Never ask the Docker daemon for container names (`--no-docker`), even
+/// when a forwarder process holds a port. Call once, before the first
+/// enumeration.
+pub fn set_skip_docker(on: bool) {
+    let _ = SKIP_DOCKER.set(on);
+}
+
+fn skip_docker() -> bool {
+    SKIP_DOCKER.get().copied().unwrap_or(false)
+}
+
+// Only the Linux parser looks names up (lsof prints them already), so the
+// getter is compiled wherever the `linux` module is.
+#[cfg(any(target_os = "linux", test))]
+static RESOLVE_USER_NAMES: OnceLock<bool> = OnceLock::new();
+
+/// Whether to look up a name for each socket owner's uid. The CLI turns
+/// this off when nothing it prints shows the name, so a plain table never
+/// waits on a slow directory service. Call once, before the first
+/// enumeration. A no-op outside Linux.
+pub fn set_resolve_user_names(on: bool) {
+    #[cfg(any(target_os = "linux", test))]
+    let _ = RESOLVE_USER_NAMES.set(on);
+    #[cfg(not(any(target_os = "linux", test)))]
+    let _ = on;
+}
+
+/// Defaults to on, so library callers get names.
+#[cfg(any(target_os = "linux", test))]
+pub(crate) fn resolve_user_names() -> bool {
+    RESOLVE_USER_NAMES.get().copied().unwrap_or(true)
+}
+
+/// When each of `pids` started. PIDs that exited or can't be inspected are
+/// left out, as is everything on platforms without `/proc` or `ps`.
+pub fn process_start_times(pids: &[u32]) -> HashMap<u32, SystemTime> {
+    #[cfg(target_os = "linux")]
+    return linux::process::read_start_times(pids);
+
+    #[cfg(target_os = "macos")]
+    return macos::process_start_times(pids);
+
+    #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+    {
+        let _ = pids;
+        HashMap::new()
+    }
+}
+
+/// The values of `keys` in a process's environment, in the order asked
+/// for; unset keys are left out. `None` when the environment can't be
+/// read: another user's process without root, or a platform other than
+/// Linux, where no reliable source exists.
+pub fn process_env(pid: u32, keys: &[String]) -> Option<Vec<(String, String)>> {
+    if pid == crate::types::UNATTRIBUTED_PID {
+        return None;
+    }
+
+    #[cfg(target_os = "linux")]
+    return linux::process::read_environ(pid, keys);
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = keys;
+        None
+    }
+}
+
+/// Whether we're running under the Windows Subsystem for Linux.
+///
+/// Checked once per process. WSL kernels report "microsoft" (WSL2) or
+/// "Microsoft" (WSL1) in their release string.
+pub fn is_wsl() -> bool {
+    static IS_WSL: OnceLock<bool> = OnceLock::new();
+    *IS_WSL.get_or_init(|| {
+        cfg!(target_os = "linux")
+            && std::fs::read_to_string("/proc/sys/kernel/osrelease")
+                .is_ok_and(|release| osrelease_is_wsl(&release))
+    })
+}
+
+fn osrelease_is_wsl(release: &str) -> bool {
+    release.to_lowercase().contains("microsoft")
+}
+
+/// Whether we're running with root privileges (effective UID 0).
+#[cfg(unix)]
+pub fn is_root() -> bool {
+    nix::unistd::geteuid().is_root()
+}
+
+#[cfg(not(unix))]
+pub fn is_root() -> bool {
+    false
+}
+
+/// Login name for `uid`, or the uid itself when it has none. Goes
+/// through `getpwuid_r`, so users served by NSS (LDAP, SSSD) resolve too,
+/// and each uid is looked up once per process.
+#[cfg(all(unix, any(target_os = "linux", test)))]
+pub(crate) fn user_name(uid: u32) -> String {
+    use std::sync::{Mutex, PoisonError};
+
+    static NAMES: OnceLock<Mutex<HashMap<u32, String>>> = OnceLock::new();
+    let mut names = NAMES
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    names
+        .entry(uid)
+        .or_insert_with(|| {
+            nix::unistd::User::from_uid(nix::unistd::Uid::from_raw(uid))
+                .ok()
+                .flatten()
+                .map_or_else(|| uid.to_string(), |user| user.name)
+        })
+        .clone()
+}
+
+/// Re-run the current command under `sudo`, with the same arguments minus
+/// `--sudo`, so every socket can be attributed to its process. Only returns
+/// on failure; a no-op (`Ok`) when we're already root.
+#[cfg(unix)]
+pub fn reexec_under_sudo() -> Result<()> {
+    use anyhow::Context;
+    use std::os::unix::process::CommandExt;
+
+    if is_root() {
+        return Ok(());
+    }
+    let exe = std::env::current_exe().context("Failed to locate the ports binary")?;
+    let args = std::env::args_os().skip(1).filter(|a| a != "--sudo");
+    let err = std::process::Command::new("sudo")
+        .arg(exe)
+        .args(args)
+        .exec();
+    Err(err).context("Failed to run sudo")
+}
+
+#[cfg(not(unix))]
+pub fn reexec_under_sudo() -> Result<()> {
+    anyhow::bail!("--sudo is only supported on Linux and macOS")
+}
+
+/// IP addresses configured on the network interface `name`, via
+/// `getifaddrs`. Errors if no interface has that name.
+#[cfg(unix)]
+pub fn interface_addresses(name: &str) -> Result<Vec<IpAddr>> {
+    use anyhow::Context;
+    use nix::ifaddrs::getifaddrs;
+
+    let mut found = false;
+    let mut addrs = Vec::new();
+    for ifa in getifaddrs().context("Failed to list network interfaces")? {
+        if ifa.interface_name != name {
+            continue;
+        }
+        found = true;
+        let Some(addr) = ifa.address else { continue };
+        if let Some(v4) = addr.as_sockaddr_in() {
+            addrs.push(IpAddr::V4(v4.ip()));
+        } else if let Some(v6) = addr.as_sockaddr_in6() {
+            addrs.push(IpAddr::V6(v6.ip()));
+        }
+    }
+    if !found {
+        anyhow::bail!("No network interface named '{}'", name);
+    }
+    Ok(addrs)
+}
+
+#[cfg(not(unix))]
+pub fn interface_addresses(_name: &str) -> Result<Vec<IpAddr>> {
+    anyhow::bail!("--interface is only supported on Linux and macOS")
+}
+
+/// How long `--throughput` waits between its two readings.
+#[cfg(target_os = "linux")]
+const THROUGHPUT_SAMPLE: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// Fill in per-connection send and receive rates (`--throughput`, Linux
+/// only). The TCP byte counters are read twice, half a second apart, and
+/// each connection's growth is divided by the time in between. UDP rows,
+/// rows from other namespaces, and connections that opened or closed in
+/// the meantime are left without a rate.
+#[cfg(target_os = "linux")]
+pub fn sample_throughput(ports: &mut [PortInfo]) -> Result<()> {
+    use anyhow::Context;
+    use std::time::Instant;
+
+    let read = || linux::sock_diag::tcp_counters().context("Failed to read TCP byte counters");
+    let before = read()?;
+    let start = Instant::now();
+    std::thread::sleep(THROUGHPUT_SAMPLE);
+    let after = read()?;
+    let secs = start.elapsed().as_secs_f64();
+    let rate = |from: u64, to: u64| (to.saturating_sub(from) as f64 / secs) as u64;
+
+    for port in ports.iter_mut() {
+        if port.protocol != crate::types::Protocol::Tcp || port.netns.is_some() {
+            continue;
+        }
+        let Some(remote) = &port.remote_address else {
+            continue;
+        };
+        let key = (port.port, remote.clone());
+        if let (Some(a), Some(b)) = (before.get(&key), after.get(&key)) {
+            port.tx_bytes_per_sec = Some(rate(a.sent, b.sent));
+            port.rx_bytes_per_sec = Some(rate(a.received, b.received));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn sample_throughput(_ports: &mut [PortInfo]) -> Result<()> {
+    anyhow::bail!("--throughput is only supported on Linux")
+}
+
 #[cfg(target_os = "linux")]
-pub fn get_listening_ports() -> Result<PortListing> {
+pub fn get_listening_ports() -> Result<PortListing, PortsError> {
     linux::get_listening_ports().map(enrich)
 }
 
 #[cfg(not(target_os = "linux"))]
-pub fn get_listening_ports() -> Result<PortListing> {
+pub fn get_listening_ports() -> Result<PortListing, PortsError> {
     fallback::get_listening_ports().map(enrich)
 }
 
+/// [`get_listening_ports`] narrowed to sockets on one of `ports`, for
+/// lookups like `ports 8080`. On Linux this skips most of the
+/// `/proc/<pid>/fd` scan by resolving only the owners of those sockets;
+/// elsewhere it lists everything and filters before enrichment.
+pub fn get_listening_ports_on(ports: &[u16]) -> Result<PortListing, PortsError> {
+    #[cfg(target_os = "linux")]
+    let listed = linux::get_listening_ports_on(ports);
+
+    #[cfg(not(target_os = "linux"))]
+    let listed = fallback::get_listening_ports().map(|all| retain_on(all, ports));
+
+    listed.map(enrich)
+}
+
+/// [`get_connections`] narrowed to local `ports`; see
+/// [`get_listening_ports_on`].
+pub fn get_connections_on(ports: &[u16]) -> Result<PortListing, PortsError> {
+    #[cfg(target_os = "linux")]
+    let listed = linux::get_established_connections_on(ports);
+
+    #[cfg(target_os = "macos")]
+    let listed = macos::get_connections().map(|all| retain_on(all, ports));
+
+    #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+    return get_connections().map(|mut listing| {
+        listing.ports.retain(|p| ports.contains(&p.port));
+        listing
+    });
+
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    listed.map(enrich)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn retain_on(mut all: Vec<PortInfo>, ports: &[u16]) -> Vec<PortInfo> {
+    all.retain(|p| ports.contains(&p.port));
+    all
+}
+
 #[cfg(target_os = "linux")]
-pub fn get_connections() -> Result<PortListing> {
+pub fn get_connections() -> Result<PortListing, PortsError> {
     linux::get_established_connections().map(enrich)
 }
 
 #[cfg(target_os = "macos")]
-pub fn get_connections() -> Result<PortListing> {
+pub fn get_connections() -> Result<PortListing, PortsError> {
     macos::get_connections().map(enrich)
 }
 
-#[cfg(not(any(target_os = "linux", target_os = "macos")))]
-pub fn get_connections() -> Result<PortListing> {
-    anyhow::bail!("--connections is only supported on Linux and macOS")
+#[cfg(all(
+    feature = "netstat",
+    not(any(target_os = "linux", target_os = "macos"))
+))]
+pub fn get_connections() -> Result<PortListing, PortsError> {
+    fallback::get_connections().map(enrich)
+}
+
+#[cfg(all(
+    not(feature = "netstat"),
+    not(any(target_os = "linux", target_os = "macos"))
+))]
+pub fn get_connections() -> Result<PortListing, PortsError> {
+    Err(PortsError::UnsupportedPlatform(
+        "this binary was built without the `netstat` feature; \
+         --connections on this platform requires it. Rebuild with default features \
+         or `cargo install portls --features netstat`"
+            .to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn osrelease_detects_wsl1_and_wsl2() {
+        assert!(osrelease_is_wsl("4.4.0-19041-Microsoft\n"));
+        assert!(osrelease_is_wsl("5.15.153.1-microsoft-standard-WSL2\n"));
+        assert!(!osrelease_is_wsl("6.8.0-45-generic\n"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn user_name_falls_back_to_uid() {
+        assert_eq!(user_name(0), "root");
+        assert_eq!(user_name(4_000_000_000), "4000000000");
+        // Served from the cache the second time.
+        assert_eq!(user_name(4_000_000_000), "4000000000");
+    }
 }
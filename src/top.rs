@@ -1,9 +1,12 @@
 //! Interactive real-time port viewer (htop-style), built on ratatui.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
 use std::time::{Duration, Instant};
 
 use anyhow::Result;
+use clap::ValueEnum;
 use crossterm::event::{self, Event, KeyCode, KeyModifiers};
 use crossterm::terminal;
 use ratatui::backend::CrosstermBackend;
@@ -14,38 +17,132 @@ use ratatui::widgets::{Block, Borders, Cell, Clear, Paragraph, Row, Table, Table
 use ratatui::Terminal;
 
 use crate::ancestry::{self, ProcessAncestry};
-use crate::cli::SortField;
-use crate::commands::kill::kill_process;
+use crate::cli::{ProtocolFilter, SortField};
+use crate::commands::kill::{kill_process, parse_signal, SIGNAL_NAMES};
 use crate::types::{PortInfo, Protocol};
 use crate::{filter, framework, platform, project};
 
+/// Where `top` remembers its view between runs, next to the history
+/// database.
+const SAVED_VIEW_FILE: &str = "top-state.toml";
+
 #[derive(Clone, Copy, PartialEq, Eq)]
 enum ViewMode {
     Listening,
     Connections,
 }
 
+impl ViewMode {
+    fn as_str(self) -> &'static str {
+        match self {
+            ViewMode::Listening => "listening",
+            ViewMode::Connections => "connections",
+        }
+    }
+}
+
+/// The view and sort the last `top` session quit with. Either half is
+/// `None` when the file doesn't say or says something this version
+/// doesn't know.
+#[derive(Default)]
+struct SavedView {
+    mode: Option<ViewMode>,
+    sort: Option<SortField>,
+}
+
+impl SavedView {
+    fn parse(text: &str) -> Self {
+        #[derive(serde::Deserialize)]
+        struct Raw {
+            mode: Option<String>,
+            sort: Option<String>,
+        }
+        let Ok(raw) = toml::from_str::<Raw>(text) else {
+            return Self::default();
+        };
+        let mode = raw.mode.and_then(|m| match m.as_str() {
+            "listening" => Some(ViewMode::Listening),
+            "connections" => Some(ViewMode::Connections),
+            _ => None,
+        });
+        let sort = raw.sort.and_then(|s| SortField::from_str(&s, true).ok());
+        Self { mode, sort }
+    }
+
+    fn format(mode: ViewMode, sort: SortField) -> String {
+        let sort = sort
+            .to_possible_value()
+            .map(|v| v.get_name().to_string())
+            .unwrap_or_default();
+        format!("mode = \"{}\"\nsort = \"{sort}\"\n", mode.as_str())
+    }
+
+    fn path() -> Option<PathBuf> {
+        let data_dir = dirs::data_local_dir().or_else(dirs::home_dir)?;
+        Some(data_dir.join("ports").join(SAVED_VIEW_FILE))
+    }
+
+    /// Best effort: a missing or unreadable file is an empty view.
+    fn load() -> Self {
+        Self::path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .map(|text| Self::parse(&text))
+            .unwrap_or_default()
+    }
+
+    /// Best effort: failing to remember the view never fails `top`.
+    fn save(mode: ViewMode, sort: SortField) {
+        let Some(path) = Self::path() else { return };
+        if let Some(dir) = path.parent() {
+            let _ = fs::create_dir_all(dir);
+        }
+        let _ = fs::write(path, Self::format(mode, sort));
+    }
+}
+
+/// What identifies a row across refreshes: `(port, protocol, pid)`.
+type PortKey = (u16, Protocol, u32);
+
+fn port_key(p: &PortInfo) -> PortKey {
+    (p.port, p.protocol, p.pid)
+}
+
 struct TopState {
     mode: ViewMode,
     sort: SortField,
     scroll_offset: usize,
+    /// Characters hidden from the left of the text columns (address,
+    /// remote, process), moved with ←/→.
+    h_offset: usize,
     selected: usize,
     /// Track which ports we've seen before (for highlighting new ones).
-    seen_ports: HashMap<(u16, Protocol, u32), Instant>,
+    seen_ports: HashMap<PortKey, Instant>,
     /// When true, show kill confirmation overlay.
     confirm_kill: bool,
+    /// Index into [`SIGNAL_NAMES`] of the signal the kill overlay sends;
+    /// reset to TERM each time the overlay opens.
+    kill_signal: usize,
     /// Transient message shown in header (e.g. "Killed PID 1234").
     status_msg: Option<(String, Instant)>,
-    /// PID for which ancestry detail popup is shown.
+    /// PID for which ancestry detail popup is shown. The popup follows
+    /// the selection and stays open across refreshes until Esc/Enter.
     detail_pid: Option<u32>,
-    /// Cached ancestry for the detail popup.
+    /// Ancestry for the detail popup, re-read on every refresh.
     detail_ancestry: Option<ProcessAncestry>,
     /// Only show developer-relevant processes.
     dev: bool,
+    /// The global `--protocol` filter.
+    protocol: Option<ProtocolFilter>,
+    /// When true, show the key binding overlay.
+    show_help: bool,
+    /// Rows pinned with `f`, kept above the rest whatever the sort.
+    pinned: HashSet<PortKey>,
+    /// Show only pinned rows (`F`).
+    pinned_only: bool,
 }
 
 impl TopState {
-    fn new(connections: bool, dev: bool) -> Self {
+    fn new(connections: bool, dev: bool, protocol: Option<ProtocolFilter>) -> Self {
         Self {
             mode: if connections {
                 ViewMode::Connections
@@ -54,18 +151,32 @@ impl TopState {
             },
             sort: SortField::Port,
             scroll_offset: 0,
+            h_offset: 0,
             selected: 0,
             seen_ports: HashMap::new(),
             confirm_kill: false,
+            kill_signal: 0,
             status_msg: None,
             detail_pid: None,
             detail_ancestry: None,
             dev,
+            protocol,
+            show_help: false,
+            pinned: HashSet::new(),
+            pinned_only: false,
         }
     }
 }
 
-pub fn run(connections: bool, dev: bool) -> Result<()> {
+/// `restore` reads the view and sort the last session quit with, and
+/// saves this session's on quit. An explicit `--connections` wins over the
+/// saved view mode.
+pub fn run(
+    connections: bool,
+    dev: bool,
+    protocol: Option<ProtocolFilter>,
+    restore: bool,
+) -> Result<()> {
     crossterm::terminal::enable_raw_mode()?;
     let mut stdout = std::io::stdout();
     crossterm::execute!(
@@ -77,7 +188,7 @@ pub fn run(connections: bool, dev: bool) -> Result<()> {
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let result = run_loop(&mut terminal, connections, dev);
+    let result = run_loop(&mut terminal, connections, dev, protocol, restore);
 
     crossterm::terminal::disable_raw_mode()?;
     crossterm::execute!(
@@ -93,8 +204,19 @@ fn run_loop(
     terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
     connections: bool,
     dev: bool,
+    protocol: Option<ProtocolFilter>,
+    restore: bool,
 ) -> Result<()> {
-    let mut state = TopState::new(connections, dev);
+    let mut state = TopState::new(connections, dev, protocol);
+    if restore {
+        let saved = SavedView::load();
+        if let (Some(mode), false) = (saved.mode, connections) {
+            state.mode = mode;
+        }
+        if let Some(sort) = saved.sort {
+            state.sort = sort;
+        }
+    }
     let poll_timeout = Duration::from_millis(100);
     let refresh_interval = Duration::from_secs(1);
     let mut last_refresh = Instant::now()
@@ -108,12 +230,14 @@ fn run_loop(
         let now = Instant::now();
 
         // Refresh data every second
-        if now.duration_since(last_refresh) >= refresh_interval {
+        let refreshed = now.duration_since(last_refresh) >= refresh_interval;
+        if refreshed {
+            let selected_key = ports.get(state.selected).map(port_key);
             ports = fetch_ports(&state)?;
+            state.selected = reselect(&ports, selected_key, state.selected);
             // Update seen_ports: insert any port not yet tracked
             for p in &ports {
-                let key = (p.port, p.protocol, p.pid);
-                state.seen_ports.entry(key).or_insert(now);
+                state.seen_ports.entry(port_key(p)).or_insert(now);
             }
             last_refresh = now;
         }
@@ -131,6 +255,10 @@ fn run_loop(
             state.selected = max_sel;
         }
 
+        if state.detail_pid.is_some() {
+            sync_detail(&mut state, &ports, refreshed);
+        }
+
         // Draw
         let now = Instant::now(); // refresh after potential data fetch
         terminal.draw(|frame| {
@@ -140,25 +268,46 @@ fn run_loop(
         // Handle input with a short poll
         if event::poll(poll_timeout)? {
             if let Event::Key(key) = event::read()? {
-                if state.confirm_kill {
+                if state.show_help {
+                    match key.code {
+                        KeyCode::Char('q') => break,
+                        KeyCode::Char('?') | KeyCode::Char('h') | KeyCode::Esc => {
+                            state.show_help = false;
+                        }
+                        _ => {}
+                    }
+                } else if state.confirm_kill {
                     match key.code {
                         KeyCode::Char('y') | KeyCode::Char('Y') => {
                             if let Some(port) = ports.get(state.selected) {
                                 let pid = port.pid;
-                                let msg = match kill_process(pid) {
-                                    Ok(()) => format!("Killed PID {}", pid),
+                                let name = SIGNAL_NAMES[state.kill_signal];
+                                let msg = match parse_signal(name)
+                                    .and_then(|signal| kill_process(pid, signal))
+                                {
+                                    Ok(()) => format!("Sent SIG{} to PID {}", name, pid),
                                     Err(e) => format!("Failed to kill PID {}: {}", pid, e),
                                 };
                                 state.status_msg = Some((msg, Instant::now()));
                             }
                             state.confirm_kill = false;
                         }
+                        KeyCode::Right | KeyCode::Tab => {
+                            state.kill_signal = (state.kill_signal + 1) % SIGNAL_NAMES.len();
+                        }
+                        KeyCode::Left | KeyCode::BackTab => {
+                            state.kill_signal =
+                                (state.kill_signal + SIGNAL_NAMES.len() - 1) % SIGNAL_NAMES.len();
+                        }
                         _ => {
                             state.confirm_kill = false;
                         }
                     }
-                } else if state.detail_pid.is_some() {
-                    // Dismiss detail popup on any key.
+                } else if state.detail_pid.is_some()
+                    && matches!(key.code, KeyCode::Esc | KeyCode::Enter)
+                {
+                    // Other keys, navigation included, work underneath the
+                    // popup, which then follows the selection.
                     state.detail_pid = None;
                     state.detail_ancestry = None;
                 } else {
@@ -168,13 +317,13 @@ fn run_loop(
                             break
                         }
 
+                        KeyCode::Char('?') | KeyCode::Char('h') => state.show_help = true,
+
                         // Show ancestry detail for selected process.
                         KeyCode::Enter => {
                             if let Some(port) = ports.get(state.selected) {
-                                let pid = port.pid;
-                                state.detail_pid = Some(pid);
-                                state.detail_ancestry =
-                                    ancestry::get_ancestry(pid, &port.process_name);
+                                state.detail_pid = Some(port.pid);
+                                sync_detail(&mut state, &ports, true);
                             }
                         }
 
@@ -185,17 +334,64 @@ fn run_loop(
                                 ViewMode::Connections => ViewMode::Listening,
                             };
                             state.scroll_offset = 0;
+                            state.h_offset = 0;
                             state.selected = 0;
                         }
 
+                        // Horizontal scroll of the text columns.
+                        KeyCode::Left => {
+                            state.h_offset = state.h_offset.saturating_sub(H_SCROLL_STEP);
+                        }
+                        KeyCode::Right => {
+                            let widest = ports.iter().map(widest_text).max().unwrap_or(0);
+                            state.h_offset = (state.h_offset + H_SCROLL_STEP).min(widest);
+                        }
+
                         // Sort
                         KeyCode::Char('p') => state.sort = SortField::Port,
                         KeyCode::Char('i') => state.sort = SortField::Pid,
                         KeyCode::Char('n') => state.sort = SortField::Name,
+                        KeyCode::Char('t') => state.sort = SortField::Protocol,
+                        KeyCode::Char('a') => state.sort = SortField::Address,
+                        KeyCode::Char('c') => state.sort = SortField::Container,
+
+                        // Force refresh, bypassing the Docker cache TTL.
+                        KeyCode::Char('r') => {
+                            #[cfg(feature = "docker")]
+                            crate::docker::clear_cache();
+                            last_refresh =
+                                now.checked_sub(refresh_interval).unwrap_or(last_refresh);
+                        }
+
+                        // Pin the selected row; the next refresh moves it up.
+                        KeyCode::Char('f') => {
+                            if let Some(port) = ports.get(state.selected) {
+                                let key = port_key(port);
+                                let verb = if state.pinned.remove(&key) {
+                                    "Unpinned"
+                                } else {
+                                    state.pinned.insert(key);
+                                    "Pinned"
+                                };
+                                let msg = format!(
+                                    "{verb} {}/{} (PID {})",
+                                    port.port, port.protocol, port.pid
+                                );
+                                state.status_msg = Some((msg, Instant::now()));
+                                last_refresh =
+                                    now.checked_sub(refresh_interval).unwrap_or(last_refresh);
+                            }
+                        }
+                        KeyCode::Char('F') => {
+                            state.pinned_only = !state.pinned_only;
+                            last_refresh =
+                                now.checked_sub(refresh_interval).unwrap_or(last_refresh);
+                        }
 
                         // Kill
                         KeyCode::Char('k') if !ports.is_empty() => {
                             state.confirm_kill = true;
+                            state.kill_signal = 0;
                         }
 
                         // Navigation
@@ -228,9 +424,74 @@ fn run_loop(
         }
     }
 
+    if restore {
+        SavedView::save(state.mode, state.sort);
+    }
     Ok(())
 }
 
+/// Index of the previously selected row in a fresh listing, so the
+/// cursor stays on the same process when rows move. When that row is
+/// gone, the old index is kept (and clamped later).
+fn reselect(ports: &[PortInfo], key: Option<PortKey>, selected: usize) -> usize {
+    key.and_then(|key| ports.iter().position(|p| port_key(p) == key))
+        .unwrap_or(selected)
+}
+
+/// Point the open detail popup at the selected process. Ancestry is
+/// re-read when the selection moves to another PID and on every refresh
+/// (through the ancestry cache), so state changes such as a new warning
+/// show up without reopening the popup.
+fn sync_detail(state: &mut TopState, ports: &[PortInfo], refreshed: bool) {
+    let Some(port) = ports.get(state.selected) else {
+        return;
+    };
+    if refreshed || state.detail_pid != Some(port.pid) {
+        state.detail_pid = Some(port.pid);
+        state.detail_ancestry = ancestry::get_ancestry(port.pid, &port.process_name);
+    }
+}
+
+/// Characters shifted per ←/→ press.
+const H_SCROLL_STEP: usize = 8;
+
+/// Text shown in the PROCESS column: name plus container and framework.
+fn process_display(port: &PortInfo) -> String {
+    match (&port.container, &port.framework) {
+        (Some(c), Some(f)) => format!("{} ({}) [{}]", port.process_name, c, f),
+        (Some(c), None) => format!("{} ({})", port.process_name, c),
+        (None, Some(f)) => format!("{} [{}]", port.process_name, f),
+        (None, None) => port.process_name.clone(),
+    }
+}
+
+/// Length of the longest horizontally scrollable cell in a row, which
+/// bounds how far → can scroll.
+fn widest_text(port: &PortInfo) -> usize {
+    [
+        port.address.chars().count(),
+        port.remote_display().map_or(0, |r| r.chars().count()),
+        process_display(port).chars().count(),
+    ]
+    .into_iter()
+    .max()
+    .unwrap_or(0)
+}
+
+/// Drop the first `offset` characters, marking the cut with `…` so a
+/// scrolled cell can't be mistaken for the full value.
+fn scroll_text(s: &str, offset: usize) -> String {
+    if offset == 0 {
+        return s.to_string();
+    }
+    let rest: String = s.chars().skip(offset).collect();
+    if rest.is_empty() {
+        String::new()
+    } else {
+        format!("…{rest}")
+    }
+}
+
 fn fetch_ports(state: &TopState) -> Result<Vec<PortInfo>> {
     project::clear_cache();
     framework::clear_cache();
@@ -242,14 +503,25 @@ fn fetch_ports(state: &TopState) -> Result<Vec<PortInfo>> {
         ViewMode::Listening => platform::get_listening_ports()?,
         ViewMode::Connections => platform::get_connections()?,
     };
-    let mut ports = listing.ports;
+    let mut ports = PortInfo::filter_protocol(listing.ports, state.protocol);
     if state.dev {
         filter::retain_dev_only(&mut ports);
     }
     PortInfo::sort_vec(&mut ports, Some(state.sort));
+    arrange_pinned(&mut ports, &state.pinned, state.pinned_only);
     Ok(ports)
 }
 
+/// Move pinned rows to the top, keeping the sort order within both
+/// groups. With `pinned_only`, drop the rest.
+fn arrange_pinned(ports: &mut Vec<PortInfo>, pinned: &HashSet<PortKey>, pinned_only: bool) {
+    if pinned_only {
+        ports.retain(|p| pinned.contains(&port_key(p)));
+    } else {
+        ports.sort_by_key(|p| !pinned.contains(&port_key(p)));
+    }
+}
+
 fn draw(
     frame: &mut ratatui::Frame,
     state: &mut TopState,
@@ -279,6 +551,10 @@ fn draw(
         SortField::Port => "port",
         SortField::Pid => "pid",
         SortField::Name => "name",
+        SortField::Protocol => "protocol",
+        SortField::Address => "address",
+        SortField::Container => "container",
+        SortField::Unsorted => "none",
     };
 
     let header_text = if let Some((ref msg, _)) = state.status_msg {
@@ -287,12 +563,23 @@ fn draw(
             Style::default().fg(Color::Yellow),
         )])
     } else {
+        let mut scrolled = if state.h_offset > 0 {
+            format!(", scrolled +{}", state.h_offset)
+        } else {
+            String::new()
+        };
+        if state.pinned_only {
+            scrolled.push_str(", pinned only");
+        } else if !state.pinned.is_empty() {
+            scrolled.push_str(&format!(", {} pinned", state.pinned.len()));
+        }
         Line::from(vec![Span::styled(
             format!(
-                "ports top - {} ({} entries, sorted by {})",
+                "ports top - {} ({} entries, sorted by {}{})",
                 mode_str,
                 ports.len(),
-                sort_str
+                sort_str,
+                scrolled
             ),
             Style::default().fg(Color::Cyan),
         )])
@@ -349,41 +636,40 @@ fn draw(
         .take(visible_rows)
         .map(|(i, port)| {
             let is_selected = i == state.selected;
-            let key = (port.port, port.protocol, port.pid);
+            let key = port_key(port);
             let is_new = state
                 .seen_ports
                 .get(&key)
                 .map(|t| now.duration_since(*t) < new_threshold)
                 .unwrap_or(true);
 
-            let process_display = match (&port.container, &port.framework) {
-                (Some(c), Some(f)) => format!("{} ({}) [{}]", port.process_name, c, f),
-                (Some(c), None) => {
-                    format!("{} ({})", port.process_name, c)
-                }
-                (None, Some(f)) => {
-                    format!("{} [{}]", port.process_name, f)
-                }
-                (None, None) => port.process_name.clone(),
+            let h = state.h_offset;
+            let port_label = if state.pinned.contains(&key) {
+                format!("★ {}", port.port)
+            } else {
+                port.port.to_string()
             };
+            let process = scroll_text(&process_display(port), h);
+            let address = scroll_text(&port.address, h);
 
             let cells: Vec<Cell> = if is_connections {
-                let remote = port.remote_address.as_deref().unwrap_or("-");
+                let remote = port.remote_display();
+                let remote = remote.as_deref().unwrap_or("-");
                 vec![
                     Cell::from(port.protocol.to_string()),
-                    Cell::from(port.port.to_string()),
+                    Cell::from(port_label),
                     Cell::from(port.pid.to_string()),
-                    Cell::from(port.address.clone()),
-                    Cell::from(remote.to_string()),
-                    Cell::from(process_display),
+                    Cell::from(address),
+                    Cell::from(scroll_text(remote, h)),
+                    Cell::from(process),
                 ]
             } else {
                 vec![
                     Cell::from(port.protocol.to_string()),
-                    Cell::from(port.port.to_string()),
+                    Cell::from(port_label),
                     Cell::from(port.pid.to_string()),
-                    Cell::from(port.address.clone()),
-                    Cell::from(process_display),
+                    Cell::from(address),
+                    Cell::from(process),
                 ]
             };
 
@@ -405,7 +691,7 @@ fn draw(
     let widths = if is_connections {
         vec![
             Constraint::Length(6),
-            Constraint::Length(6),
+            Constraint::Length(8),
             Constraint::Length(7),
             Constraint::Length(22),
             Constraint::Length(22),
@@ -414,7 +700,7 @@ fn draw(
     } else {
         vec![
             Constraint::Length(6),
-            Constraint::Length(6),
+            Constraint::Length(8),
             Constraint::Length(7),
             Constraint::Length(22),
             Constraint::Fill(1),
@@ -429,12 +715,17 @@ fn draw(
     // ── Footer ────────────────────────────────────────────────────────────
     let footer_text = if state.confirm_kill {
         Line::from(vec![Span::styled(
-            "Kill selected process? [y]es / any key to cancel",
+            "Kill selected process? [y]es  ←→:Change signal  any other key to cancel",
             Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
         )])
+    } else if state.detail_pid.is_some() {
+        Line::from(vec![Span::styled(
+            "Esc/Enter:Close info  ↑↓/j/K:Inspect another process  k:Kill  q:Quit",
+            Style::default().fg(Color::DarkGray),
+        )])
     } else {
         Line::from(vec![Span::styled(
-            "q:Quit  Tab:Toggle  p/i/n:Sort  ↑↓/j/K:Nav  PgUp/PgDn:Page  Enter:Info  k:Kill",
+            "q:Quit  ?:Help  Tab:Toggle  Enter:Info  k:Kill  f:Pin  F:Pinned only  p/i/n/t/a/c:Sort  ←→:Scroll",
             Style::default().fg(Color::DarkGray),
         )])
     };
@@ -444,8 +735,8 @@ fn draw(
     if state.confirm_kill {
         if let Some(port) = ports.get(state.selected) {
             let popup_text = format!(
-                "Kill PID {} ({}) on port {}?  [y]es / any key to cancel",
-                port.pid, port.process_name, port.port
+                "Send SIG{} to PID {} ({}) on port {}?  [y]es / any key to cancel",
+                SIGNAL_NAMES[state.kill_signal], port.pid, port.process_name, port.port
             );
             let popup_area = centered_rect(60, 3, area);
             frame.render_widget(Clear, popup_area);
@@ -544,17 +835,194 @@ fn draw(
             popup_area,
         );
     }
+
+    // ── Help overlay ─────────────────────────────────────────────────────────
+    if state.show_help {
+        let lines = help_lines();
+        let popup_area = centered_rect(60, lines.len() as u16 + 2, area);
+        frame.render_widget(Clear, popup_area);
+        frame.render_widget(
+            Paragraph::new(lines).block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Keys - ?/Esc to close")
+                    .title_alignment(Alignment::Center),
+            ),
+            popup_area,
+        );
+    }
 }
 
-/// Returns a centered `Rect` with the given percentage width and fixed height.
+/// Returns a centered `Rect` with the given percentage width and fixed
+/// height, clamped to `r`.
 fn centered_rect(percent_x: u16, height: u16, r: Rect) -> Rect {
     let popup_width = r.width * percent_x / 100;
+    let height = height.min(r.height);
     let x = r.x + (r.width.saturating_sub(popup_width)) / 2;
-    let y = r.y + r.height / 2;
+    let y = r.y + (r.height - height) / 2;
     Rect {
         x,
         y,
         width: popup_width,
-        height: height.min(r.height),
+        height,
+    }
+}
+
+/// Key bindings shown by the `?` overlay, grouped by category. Keep in
+/// step with the key handling in `run_loop`.
+const HELP: &[(&str, &[(&str, &str)])] = &[
+    (
+        "Navigation",
+        &[
+            ("↑ ↓ / K j", "Move selection"),
+            ("PgUp PgDn", "Move a page"),
+            ("Home End", "First / last row"),
+            ("← →", "Scroll address, remote, and process columns"),
+        ],
+    ),
+    (
+        "Sort",
+        &[
+            ("p / i / n", "Port / PID / process name"),
+            ("t / a / c", "Protocol / address / container"),
+        ],
+    ),
+    (
+        "Actions",
+        &[
+            ("Enter", "Live process info (Esc/Enter closes)"),
+            ("k", "Kill selected process (←→ picks the signal)"),
+            ("f", "Pin / unpin selected row (pinned rows stay on top)"),
+            ("r", "Refresh now, bypassing the Docker cache"),
+        ],
+    ),
+    (
+        "View",
+        &[
+            ("Tab", "Toggle listening / connections"),
+            ("F", "Toggle showing only pinned rows"),
+            ("? / h", "Toggle this help"),
+            ("q / Esc", "Quit"),
+        ],
+    ),
+];
+
+fn help_lines() -> Vec<Line<'static>> {
+    let mut lines = Vec::new();
+    for (i, (category, keys)) in HELP.iter().enumerate() {
+        if i > 0 {
+            lines.push(Line::from(""));
+        }
+        lines.push(Line::from(Span::styled(
+            *category,
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        )));
+        for (key, action) in *keys {
+            lines.push(Line::from(vec![
+                Span::styled(format!("  {key:<12}"), Style::default().fg(Color::Cyan)),
+                Span::raw(*action),
+            ]));
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_port(pid: u32) -> PortInfo {
+        PortInfo {
+            port: 8000 + pid as u16,
+            protocol: Protocol::Tcp,
+            pid,
+            process_name: "proc".to_string(),
+            address: "127.0.0.1:8000".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn saved_view_round_trips() {
+        let text = SavedView::format(ViewMode::Connections, SortField::Pid);
+        assert_eq!(text, "mode = \"connections\"\nsort = \"pid\"\n");
+        let saved = SavedView::parse(&text);
+        assert!(saved.mode == Some(ViewMode::Connections));
+        assert!(matches!(saved.sort, Some(SortField::Pid)));
+    }
+
+    #[test]
+    fn unknown_saved_values_are_ignored() {
+        let saved = SavedView::parse("mode = \"sideways\"\nsort = \"name\"\n");
+        assert!(saved.mode.is_none());
+        assert!(matches!(saved.sort, Some(SortField::Name)));
+
+        let saved = SavedView::parse("not toml at all [");
+        assert!(saved.mode.is_none() && saved.sort.is_none());
+    }
+
+    #[test]
+    fn detail_popup_follows_selection() {
+        let ports = vec![make_port(1), make_port(2)];
+        let mut state = TopState::new(false, false, None);
+        state.detail_pid = Some(1);
+
+        state.selected = 1;
+        sync_detail(&mut state, &ports, false);
+        assert_eq!(state.detail_pid, Some(2));
+
+        // An empty listing keeps the popup on the last process.
+        sync_detail(&mut state, &[], true);
+        assert_eq!(state.detail_pid, Some(2));
+    }
+
+    #[test]
+    fn selection_follows_the_row_across_refreshes() {
+        let port = |port: u16, pid: u32| PortInfo {
+            port,
+            pid,
+            ..make_port(pid)
+        };
+        let key = Some(port_key(&port(443, 2)));
+
+        // A new row sorts in ahead of the selection.
+        let after = vec![port(22, 4), port(80, 1), port(443, 2), port(8080, 3)];
+        assert_eq!(reselect(&after, key, 1), 2);
+
+        // The selected process exited: keep the position.
+        let after = vec![port(80, 1), port(8080, 3)];
+        assert_eq!(reselect(&after, key, 1), 1);
+    }
+
+    #[test]
+    fn pinned_rows_lead_in_sort_order() {
+        let mut ports = vec![make_port(1), make_port(2), make_port(3), make_port(4)];
+        let pinned = HashSet::from([port_key(&ports[3]), port_key(&ports[1])]);
+
+        arrange_pinned(&mut ports, &pinned, false);
+        let pids: Vec<u32> = ports.iter().map(|p| p.pid).collect();
+        assert_eq!(pids, [2, 4, 1, 3]);
+
+        arrange_pinned(&mut ports, &pinned, true);
+        let pids: Vec<u32> = ports.iter().map(|p| p.pid).collect();
+        assert_eq!(pids, [2, 4]);
+    }
+
+    #[test]
+    fn centered_rect_stays_inside_area() {
+        let area = Rect::new(0, 0, 80, 10);
+        let popup = centered_rect(50, 4, area);
+        assert_eq!(popup, Rect::new(20, 3, 40, 4));
+        let tall = centered_rect(50, 30, area);
+        assert_eq!((tall.y, tall.height), (0, 10));
+    }
+
+    #[test]
+    fn scroll_text_marks_hidden_prefix() {
+        assert_eq!(scroll_text("192.168.1.5:54321", 0), "192.168.1.5:54321");
+        assert_eq!(scroll_text("192.168.1.5:54321", 8), "…1.5:54321");
+        assert_eq!(scroll_text("node", 8), "");
     }
 }
@@ -1,23 +1,30 @@
 //! Core data types for port information.
 
+use std::collections::{BTreeSet, HashMap};
 use std::fmt;
 use std::hash::{Hash, Hasher};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::ops::RangeInclusive;
 use std::path::PathBuf;
+use std::time::SystemTime;
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 use regex::Regex;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
-use crate::cli::{ProtocolFilter, SortField};
+use crate::ancestry::HealthWarning;
+use crate::cli::{ProtocolFilter, SortField, StateFilter};
 #[cfg(feature = "docker")]
 use crate::docker;
+use crate::platform;
 
 /// Reachability of the Docker daemon for the most recent enrichment pass.
 ///
 /// Surfaced in JSON output (two flat fields: `docker_status` and
 /// `docker_reason`) and used by table output to decide whether to print
-/// a stderr warning. `NotQueried` means no `docker-proxy` process was
-/// observed and the daemon was never contacted.
+/// a stderr warning. `NotQueried` means no Docker forwarder process
+/// (`docker-proxy`, `rootlesskit`, ...) was observed and the daemon was
+/// never contacted.
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
 // With `docker` off the only construction site is the NotQueried
 // default — Ok and Unreachable are dormant but kept in the type so JSON
@@ -52,18 +59,57 @@ impl DockerStatus {
     }
 }
 
-#[derive(Debug, Clone, Serialize)]
+/// `PortInfo::pid` for a socket whose owning process we aren't allowed to
+/// inspect. PID 0 is never a real userspace process.
+pub(crate) const UNATTRIBUTED_PID: u32 = 0;
+
+/// `--summary` bucket for ports with no well-known service name.
+pub(crate) const UNKNOWN_SERVICE: &str = "<unknown>";
+
+/// `PortInfo::process_name` paired with [`UNATTRIBUTED_PID`].
+pub(crate) const UNATTRIBUTED_NAME: &str = "?";
+
+/// `PortInfo::process_name`, with [`UNATTRIBUTED_PID`], for a socket held
+/// by no process at all (`--kernel`).
+pub(crate) const KERNEL_NAME: &str = "[kernel]";
+
+/// One socket and the process that owns it.
+///
+/// Part of the stable library surface (see the crate docs): fields are
+/// only added, never renamed or removed, within a major version. Optional
+/// fields are `None` when the platform can't supply them. The struct is
+/// `#[non_exhaustive]` so that adding one isn't a breaking change; outside
+/// this crate, start from [`PortInfo::default()`] and set the fields you
+/// need.
+#[derive(Debug, Clone, Default, Serialize)]
+#[non_exhaustive]
 pub struct PortInfo {
+    /// Local port number.
     pub port: u16,
     pub protocol: Protocol,
+    /// Owning process ID, or `0` when the owner couldn't be determined
+    /// (typically another user's process, without root).
     pub pid: u32,
+    /// Short process name, as `ps` would show it; `?` when `pid` is `0`.
     pub process_name: String,
+    /// Local bind address as `ip:port`. Link-local IPv6 addresses carry a
+    /// `%iface` zone on Linux; `lsof` reports wildcards as `*:port`.
     pub address: String,
+    /// Peer address, set for established connections only.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub remote_address: Option<String>,
+    /// Reverse DNS name of the peer (`--resolve-names`), when it has one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remote_host: Option<String>,
     /// Container name if this port is forwarded by Docker.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub container: Option<String>,
+    /// Short (12-character) ID of that container, as `docker ps` shows it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub container_id: Option<String>,
+    /// Port inside the container that this host port is published to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub container_port: Option<u16>,
     /// Well-known service name for this port (e.g. "http", "ssh").
     #[serde(skip_serializing_if = "Option::is_none")]
     pub service_name: Option<String>,
@@ -73,13 +119,94 @@ pub struct PortInfo {
     /// Working directory of the process.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub cwd: Option<PathBuf>,
+    /// Resolved path of the process executable, e.g. `/usr/bin/python3`.
+    /// On Linux a replaced or unlinked binary keeps the kernel's
+    /// ` (deleted)` suffix.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exe_path: Option<String>,
+    /// Process health problems visible from the listing, e.g. a binary
+    /// that was replaced or unlinked after launch.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<HealthWarning>,
     /// Detected framework or runtime (e.g. "Next.js", "Django").
     #[serde(skip_serializing_if = "Option::is_none")]
     pub framework: Option<String>,
+    /// TCP retransmit counter. Linux only, and only for non-listening
+    /// sockets.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retransmits: Option<u32>,
+    /// TCP socket state. `None` for UDP, which has no connection states.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state: Option<SocketState>,
+    /// When this listener was first seen with the same process, as tracked
+    /// across runs. Only filled in with `--age`; RFC 3339 in JSON.
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "crate::output::json::serialize_time"
+    )]
+    pub first_seen: Option<SystemTime>,
+    /// Earliest this connection can have been opened: its process's start
+    /// time, since the kernel doesn't record when a connection was
+    /// established. Only filled in with `--age` on connections; RFC 3339
+    /// in JSON.
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "crate::output::json::serialize_time"
+    )]
+    pub opened_after: Option<SystemTime>,
+    /// Network namespace the socket lives in, when it isn't ours (only
+    /// with `--all-netns`): an `ip netns` name, a short container ID, or
+    /// `net:[inode]`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub netns: Option<String>,
+    /// Established connections on this listener's port held by the same
+    /// process. Only filled in with `--with-conn-count`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub conn_count: Option<usize>,
+    /// Why this listener deserves a security review. Only filled in with
+    /// `--security`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub risk: Option<Risk>,
+    /// The processes normally found behind this port's service, when the
+    /// one bound to it isn't among them. Only filled in with `--anomaly`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected_processes: Option<Vec<String>>,
+    /// Whether the `--allow-file` list covers this listener. Only filled
+    /// in with `--allow-file`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allowed: Option<bool>,
+    /// Bytes per second received over this connection, sampled with
+    /// `--throughput`. Linux TCP only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rx_bytes_per_sec: Option<u64>,
+    /// Bytes per second sent and acknowledged by the peer, sampled with
+    /// `--throughput`. Linux TCP only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tx_bytes_per_sec: Option<u64>,
+    /// Numeric ID of the user owning the socket. Linux only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uid: Option<u32>,
+    /// Name of the user owning the socket: the socket's uid resolved
+    /// through the password database on Linux, `lsof`'s USER column on
+    /// macOS.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
 }
 
-// Manual Hash/Eq excludes command_line and cwd so that watch
-// mode does not flag a CWD change as a "new" port.
+// Manual Hash/Eq: a socket's identity is its port, protocol, pid,
+// process_name, address, remote_address, container, service_name and
+// netns. Everything else is left out so that watch mode doesn't report a
+// port as new when only its details change:
+//
+// - implied by an identity field: remote_host (remote_address),
+//   container_id and container_port (container), uid and user (the socket
+//   owner), framework;
+// - process details that change under a running socket: command_line,
+//   cwd, exe_path, warnings;
+// - live counters and TCP state: state, retransmits, conn_count,
+//   rx_bytes_per_sec, tx_bytes_per_sec;
+// - timestamps: first_seen, opened_after;
+// - verdicts computed per run: risk, expected_processes, allowed.
 impl PartialEq for PortInfo {
     fn eq(&self, other: &Self) -> bool {
         self.port == other.port
@@ -90,6 +217,7 @@ impl PartialEq for PortInfo {
             && self.remote_address == other.remote_address
             && self.container == other.container
             && self.service_name == other.service_name
+            && self.netns == other.netns
     }
 }
 
@@ -105,6 +233,7 @@ impl Hash for PortInfo {
         self.remote_address.hash(state);
         self.container.hash(state);
         self.service_name.hash(state);
+        self.netns.hash(state);
     }
 }
 
@@ -142,67 +271,284 @@ impl PortInfo {
             .map(|(_, name)| name.to_string());
     }
 
+    /// The peer as tables show it: `host:port` once `--resolve-names`
+    /// found a name, the address otherwise.
+    pub fn remote_display(&self) -> Option<String> {
+        let remote = self.remote_address.as_deref()?;
+        Some(match (&self.remote_host, remote.rsplit_once(':')) {
+            (Some(host), Some((_, port))) => format!("{host}:{port}"),
+            _ => remote.to_string(),
+        })
+    }
+
+    /// The most severe [`Risk`] this listener carries, if any. Sockets
+    /// reachable only over loopback, connections, and rows with an
+    /// unknown owner are never flagged.
+    pub fn risk(&self) -> Option<Risk> {
+        if self.remote_address.is_some() || self.is_loopback() {
+            return None;
+        }
+        if REMOTE_ADMIN_PORTS.contains(&self.port) {
+            return Some(Risk::ExposedAdminService);
+        }
+        if DATA_STORE_PORTS.contains(&self.port) {
+            return Some(Risk::ExposedDataStore);
+        }
+        if self.port >= 1024 {
+            return None;
+        }
+        let root = match (self.uid, self.user.as_deref()) {
+            (Some(uid), _) => uid == 0,
+            (None, Some(user)) => user == "root",
+            (None, None) => return None,
+        };
+        Some(if root {
+            Risk::RootOnPrivileged
+        } else {
+            Risk::NonRootOnPrivileged
+        })
+    }
+
+    /// The processes expected behind this listener's service, when its own
+    /// process isn't one of them (see [`EXPECTED_PROCESSES`]). Connections,
+    /// unknown owners, and ports forwarded by a container runtime are never
+    /// flagged. A name matches an expected one exactly or as a `-` variant
+    /// (`sshd-session`), so `sshdx` doesn't pass for `sshd`.
+    pub(crate) fn unexpected_process(&self) -> Option<&'static [&'static str]> {
+        if self.remote_address.is_some() || self.is_unattributed() || self.container.is_some() {
+            return None;
+        }
+        let service = self.service_name.as_deref()?;
+        let (_, expected) = EXPECTED_PROCESSES.iter().find(|(s, _)| *s == service)?;
+        let name = self.process_name.to_ascii_lowercase();
+        let known = expected.iter().chain(PORT_FORWARDERS).any(|e| {
+            name.strip_prefix(e)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('-'))
+        });
+        (!known).then_some(*expected)
+    }
+
+    /// `--anomaly`: fill in `expected_processes` on every row.
+    pub fn flag_anomalies(ports: &mut [PortInfo]) {
+        for port in ports.iter_mut() {
+            port.expected_processes = port
+                .unexpected_process()
+                .map(|names| names.iter().map(|n| n.to_string()).collect());
+        }
+    }
+
+    /// `--security`: fill in `risk` on every row and move the riskiest to
+    /// the top, keeping the existing order within each level.
+    pub fn rank_by_risk(ports: &mut [PortInfo]) {
+        for port in ports.iter_mut() {
+            port.risk = port.risk();
+        }
+        ports.sort_by_key(|p| (p.risk.is_none(), p.risk));
+    }
+
+    /// Fill in `conn_count` on each listener from `connections`: an
+    /// accepted connection keeps the listening socket's local port and
+    /// belongs to the same process, so that pair is the correlation key.
+    /// When several rows share a key (`0.0.0.0` and `[::]`), each
+    /// connection is counted once, on the row of its address family or
+    /// else the first one, so the column still adds up.
+    pub fn attach_conn_counts(ports: &mut [PortInfo], connections: &[PortInfo]) {
+        let is_v6 = |p: &PortInfo| p.address.starts_with('[');
+        let mut rows: HashMap<(u16, Protocol, u32), Vec<usize>> = HashMap::new();
+        for (i, port) in ports.iter_mut().enumerate() {
+            port.conn_count = Some(0);
+            rows.entry((port.port, port.protocol, port.pid))
+                .or_default()
+                .push(i);
+        }
+        for conn in connections {
+            let Some(rows) = rows.get(&(conn.port, conn.protocol, conn.pid)) else {
+                continue;
+            };
+            let row = rows
+                .iter()
+                .copied()
+                .find(|&i| is_v6(&ports[i]) == is_v6(conn))
+                .unwrap_or(rows[0]);
+            *ports[row].conn_count.get_or_insert(0) += 1;
+        }
+    }
+
+    /// Count ports per resolved service name (`--summary`). Named
+    /// services come first, most common first and then by name; ports
+    /// with no known service (`None`) come last.
+    pub fn count_by_service(ports: &[PortInfo]) -> Vec<(Option<String>, usize)> {
+        let mut counts: HashMap<Option<String>, usize> = HashMap::new();
+        for port in ports {
+            *counts.entry(port.service_name.clone()).or_default() += 1;
+        }
+        let mut counts: Vec<_> = counts.into_iter().collect();
+        counts.sort_by(|(a, a_count), (b, b_count)| {
+            (a.is_none(), b_count, a).cmp(&(b.is_none(), a_count, b))
+        });
+        counts
+    }
+
+    /// Group connections by remote host, port stripped (`--by-remote`).
+    /// Busiest hosts first, then by address. Rows without a remote end
+    /// (listeners) are skipped.
+    pub fn group_by_remote(ports: &[PortInfo]) -> Vec<RemoteGroup> {
+        let mut groups: HashMap<String, RemoteGroup> = HashMap::new();
+        for port in ports {
+            let Some(remote) = port.remote_address.as_deref() else {
+                continue;
+            };
+            let host = match parse_bind_ip(remote) {
+                Some(ip) => ip.to_string(),
+                None => remote.to_string(),
+            };
+            let group = groups.entry(host.clone()).or_insert_with(|| RemoteGroup {
+                remote: host,
+                count: 0,
+                local_ports: BTreeSet::new(),
+            });
+            group.count += 1;
+            group.local_ports.insert(port.port);
+        }
+        let mut groups: Vec<_> = groups.into_values().collect();
+        groups.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.remote.cmp(&b.remote)));
+        groups
+    }
+
+    /// Derive `warnings` from details already on the entry. Reuses the
+    /// `exe_path` read, so it costs no extra syscalls per PID.
+    pub fn resolve_health_warnings(&mut self) {
+        if self
+            .exe_path
+            .as_deref()
+            .is_some_and(|exe| exe.ends_with(" (deleted)"))
+        {
+            self.warnings.push(HealthWarning::DeletedBinary);
+        }
+    }
+
+    /// Sort by the requested field. Without `--sort`, order by port,
+    /// then protocol, then PID so output is reproducible across runs
+    /// instead of following `/proc` iteration order; `--sort none`
+    /// keeps that raw order.
     pub fn sort_vec(ports: &mut [PortInfo], sort: Option<SortField>) {
         match sort {
             Some(SortField::Port) => ports.sort_by_key(|p| p.port),
             Some(SortField::Pid) => ports.sort_by_key(|p| p.pid),
             Some(SortField::Name) => ports.sort_by(|a, b| a.process_name.cmp(&b.process_name)),
-            None => {}
+            Some(SortField::Protocol) => ports.sort_by_key(|p| p.protocol),
+            Some(SortField::Address) => {
+                ports.sort_by_cached_key(|p| (parse_bind_ip(&p.address), p.address.clone()))
+            }
+            Some(SortField::Container) => ports.sort_by(|a, b| {
+                (a.container.is_none(), &a.container).cmp(&(b.container.is_none(), &b.container))
+            }),
+            Some(SortField::Unsorted) => {}
+            None => ports.sort_by_key(|p| (p.port, p.protocol, p.pid)),
         }
     }
 
     /// Filter ports by port number or process/container name query.
     ///
-    /// When `use_regex` is true, the query is compiled as a regex. Returns an
-    /// `Err` with a clear message if the regex is invalid.
+    /// The query may list several comma-separated terms
+    /// (`nginx,redis,5432`); a port is kept if any term matches. When
+    /// `use_regex` is true, each term is compiled as its own regex. Returns
+    /// an `Err` with a clear message if a regex is invalid.
     pub fn filter_by_query(
         ports: Vec<PortInfo>,
         query: &str,
         use_regex: bool,
     ) -> Result<Vec<PortInfo>> {
+        let mut terms = split_query_terms(query);
+        if terms.is_empty() {
+            terms.push(query);
+        }
+
         if use_regex {
-            let re = Regex::new(query)
-                .map_err(|e| anyhow::anyhow!("Invalid regex '{}': {}", query, e))?;
+            let res = terms
+                .iter()
+                .map(|t| Regex::new(t).map_err(|e| anyhow::anyhow!("Invalid regex '{}': {}", t, e)))
+                .collect::<Result<Vec<_>>>()?;
             return Ok(ports
                 .into_iter()
-                .filter(|p| {
-                    re.is_match(&p.process_name)
-                        || p.container
-                            .as_ref()
-                            .map(|c| re.is_match(c))
-                            .unwrap_or(false)
-                        || p.framework
-                            .as_ref()
-                            .map(|f| re.is_match(f))
-                            .unwrap_or(false)
-                })
+                .filter(|p| res.iter().any(|re| p.matches_regex(re)))
                 .collect());
         }
 
-        let query_lower = query.to_lowercase();
-        if let Ok(port_num) = query.parse::<u16>() {
-            Ok(ports.into_iter().filter(|p| p.port == port_num).collect())
-        } else {
-            Ok(ports
-                .into_iter()
-                .filter(|p| {
-                    p.process_name.to_lowercase().contains(&query_lower)
-                        || p.container
-                            .as_ref()
-                            .map(|c| c.to_lowercase().contains(&query_lower))
-                            .unwrap_or(false)
-                        || p.framework
-                            .as_ref()
-                            .map(|f| f.to_lowercase().contains(&query_lower))
-                            .unwrap_or(false)
-                })
-                .collect())
+        Ok(ports
+            .into_iter()
+            .filter(|p| terms.iter().any(|t| p.matches_term(t)))
+            .collect())
+    }
+
+    /// The ports a plain query names when every term is a port number,
+    /// e.g. `8080` or `80,443`. Such a query can only match sockets on
+    /// those ports, so enumeration can skip the rest.
+    pub fn query_ports(query: &str, use_regex: bool) -> Option<Vec<u16>> {
+        if use_regex {
+            return None;
+        }
+        let terms = split_query_terms(query);
+        if terms.is_empty() {
+            return None;
+        }
+        terms
+            .iter()
+            .map(|t| split_protocol(t).0.parse().ok())
+            .collect()
+    }
+
+    fn matches_regex(&self, re: &Regex) -> bool {
+        re.is_match(&self.process_name)
+            || self
+                .container
+                .as_ref()
+                .map(|c| re.is_match(c))
+                .unwrap_or(false)
+            || self
+                .framework
+                .as_ref()
+                .map(|f| re.is_match(f))
+                .unwrap_or(false)
+    }
+
+    /// A numeric term matches the port exactly, narrowed to one protocol
+    /// by a `/tcp` or `/udp` suffix; anything else is a case-insensitive
+    /// substring of the process, container, or framework, or a prefix of
+    /// the container ID.
+    fn matches_term(&self, term: &str) -> bool {
+        if let (port, Some(protocol)) = split_protocol(term) {
+            return self.protocol == protocol && self.matches_term(port);
+        }
+        if let Ok(port_num) = term.parse::<u16>() {
+            return self.port == port_num;
         }
+        let term_lower = term.to_lowercase();
+        self.container_id_starts_with(&term_lower)
+            || self.process_name.to_lowercase().contains(&term_lower)
+            || self
+                .container
+                .as_ref()
+                .map(|c| c.to_lowercase().contains(&term_lower))
+                .unwrap_or(false)
+            || self
+                .framework
+                .as_ref()
+                .map(|f| f.to_lowercase().contains(&term_lower))
+                .unwrap_or(false)
+    }
+
+    /// Whether the container ID starts with `prefix`, as `docker` resolves
+    /// IDs. `prefix` must already be lowercase.
+    pub(crate) fn container_id_starts_with(&self, prefix: &str) -> bool {
+        self.container_id
+            .as_deref()
+            .is_some_and(|id| id.starts_with(prefix))
     }
 
     pub fn filter_protocol(ports: Vec<PortInfo>, filter: Option<ProtocolFilter>) -> Vec<PortInfo> {
         match filter {
-            None => ports,
+            None | Some(ProtocolFilter::Both) => ports,
             Some(ProtocolFilter::Tcp) => ports
                 .into_iter()
                 .filter(|p| p.protocol == Protocol::Tcp)
@@ -214,38 +560,97 @@ impl PortInfo {
         }
     }
 
+    /// Keep only sockets bound to an address on the named interface.
+    /// Wildcard binds (`0.0.0.0`, `::`) listen on every interface, so
+    /// they're always kept.
+    pub fn filter_interface(
+        ports: Vec<PortInfo>,
+        interface: Option<&str>,
+    ) -> Result<Vec<PortInfo>> {
+        let Some(name) = interface else {
+            return Ok(ports);
+        };
+        let addrs = platform::interface_addresses(name)?;
+        Ok(ports
+            .into_iter()
+            .filter(|p| p.is_bound_to_any(&addrs))
+            .collect())
+    }
+
+    /// Whether the socket is bound to a loopback address, i.e. not
+    /// reachable from other hosts.
+    pub(crate) fn is_loopback(&self) -> bool {
+        parse_bind_ip(&self.address).is_some_and(|ip| ip.is_loopback())
+    }
+
+    /// The owning process couldn't be determined; see [`UNATTRIBUTED_PID`].
+    pub(crate) fn is_unattributed(&self) -> bool {
+        self.pid == UNATTRIBUTED_PID
+    }
+
+    /// Known to be held by no process; see [`KERNEL_NAME`].
+    pub(crate) fn is_kernel(&self) -> bool {
+        self.is_unattributed() && self.process_name == KERNEL_NAME
+    }
+
+    /// Bound to the unspecified address (`0.0.0.0`, `::`, or lsof's `*`).
+    pub(crate) fn is_wildcard(&self) -> bool {
+        parse_bind_ip(&self.address).is_some_and(|ip| ip.is_unspecified())
+    }
+
+    fn is_bound_to_any(&self, addrs: &[IpAddr]) -> bool {
+        match parse_bind_ip(&self.address) {
+            Some(ip) if ip.is_unspecified() => true,
+            Some(ip) => addrs.iter().any(|a| a.to_canonical() == ip),
+            None => false,
+        }
+    }
+
+    /// Keep only sockets in the given TCP state. UDP has no states, so
+    /// any state filter drops UDP rows.
+    pub fn filter_state(ports: Vec<PortInfo>, filter: Option<StateFilter>) -> Vec<PortInfo> {
+        match filter {
+            None => ports,
+            Some(filter) => {
+                let wanted = SocketState::from(filter);
+                ports
+                    .into_iter()
+                    .filter(|p| p.state == Some(wanted))
+                    .collect()
+            }
+        }
+    }
+
     /// Enrich ports with Docker container information, returning the
     /// daemon reachability status alongside the populated ports.
     ///
-    /// For ports forwarded by `docker-proxy`, adds the container name.
-    /// Returns `DockerStatus::NotQueried` when no `docker-proxy` is
+    /// For ports held by a Docker forwarder process (see
+    /// `is_docker_forwarder`), adds the container name.
+    /// Returns `DockerStatus::NotQueried` when no forwarder is
     /// observed (no daemon contact attempted), `Ok` on a successful
     /// fetch, or `Unreachable { reason }` when the daemon could not
     /// be reached.
+    ///
+    /// With no forwarder in sight (`userland-proxy: false`, or another
+    /// user's `docker-proxy` we can't inspect), a locally configured
+    /// daemon is still asked when some listener has no known owner, since
+    /// it may be a published port. That query is a guess, so its failure
+    /// is reported as `NotQueried` rather than warned about.
     #[cfg(feature = "docker")]
     pub fn enrich_with_docker(ports: Vec<PortInfo>) -> (Vec<PortInfo>, DockerStatus) {
-        let has_docker_proxy = ports
-            .iter()
-            .any(|p| p.process_name.contains("docker-proxy"));
-        if !has_docker_proxy {
-            return (ports, DockerStatus::NotQueried);
+        let has_forwarder = ports.iter().any(|p| is_docker_forwarder(&p.process_name));
+        if !has_forwarder {
+            let has_ownerless = ports.iter().any(is_ownerless_listener);
+            if !has_ownerless || !docker::daemon_configured() {
+                return (ports, DockerStatus::NotQueried);
+            }
         }
 
-        let (mappings, status) = docker::get_port_mappings();
-
-        let enriched = ports
-            .into_iter()
-            .map(|mut p| {
-                if p.process_name.contains("docker-proxy") {
-                    if let Some(container) = mappings.get(&p.port) {
-                        p.container = Some(container.name.clone());
-                    }
-                }
-                p
-            })
-            .collect();
-
-        (enriched, status)
+        let (mappings, mut status) = docker::get_port_mappings();
+        if !has_forwarder && status != DockerStatus::Ok {
+            status = DockerStatus::NotQueried;
+        }
+        (attach_containers(ports, &mappings), status)
     }
 
     /// No-op when the `docker` feature is disabled.
@@ -255,9 +660,181 @@ impl PortInfo {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+/// Processes that hold a host socket on behalf of a container.
+///
+/// `docker-proxy` is the classic userland proxy. Rootless Docker forwards
+/// through `rootlesskit` (or `slirp4netns`), and Docker Desktop through
+/// `com.docker.backend` / `vpnkit`, so with those there's no
+/// `docker-proxy` at all. With `userland-proxy: false` published ports are
+/// iptables DNAT and no process holds them; see `is_ownerless_listener`.
+#[cfg_attr(not(feature = "docker"), allow(dead_code))]
+const DOCKER_FORWARDERS: &[&str] = &[
+    "docker-proxy",
+    "rootlesskit",
+    "slirp4netns",
+    "com.docker.backend",
+    "vpnkit",
+];
+
+#[cfg_attr(not(feature = "docker"), allow(dead_code))]
+fn is_docker_forwarder(process_name: &str) -> bool {
+    DOCKER_FORWARDERS.iter().any(|f| process_name.contains(f))
+}
+
+/// A listener no process is known to hold: one we weren't allowed to
+/// attribute, or a kernel socket (`--kernel`). Either may front a
+/// container when no forwarder process is visible.
+#[cfg_attr(not(feature = "docker"), allow(dead_code))]
+fn is_ownerless_listener(p: &PortInfo) -> bool {
+    p.is_unattributed() && p.remote_address.is_none()
+}
+
+/// Label forwarder-held and ownerless listeners with the container
+/// publishing that exact host port. A port some other process owns is
+/// never considered, so an unrelated service that happens to share a port
+/// number is never mislabeled, and a container name that's already set is
+/// left alone.
+#[cfg(feature = "docker")]
+fn attach_containers(
+    ports: Vec<PortInfo>,
+    mappings: &HashMap<u16, docker::ContainerInfo>,
+) -> Vec<PortInfo> {
+    ports
+        .into_iter()
+        .map(|mut p| {
+            let candidate = is_docker_forwarder(&p.process_name) || is_ownerless_listener(&p);
+            if p.container.is_none() && candidate {
+                if let Some(container) = mappings.get(&p.port) {
+                    p.container = Some(container.name.clone());
+                    p.container_id = container.id.clone();
+                    p.container_port = container.container_port;
+                }
+            }
+            p
+        })
+        .collect()
+}
+
+/// `python3.11` → `python`, `node` → `node`.
+pub(crate) fn interpreter_family(exe: &str) -> &str {
+    exe.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.')
+}
+
+/// Whether `name` is the interpreter behind `process_name` when Linux
+/// names the process after its script (`python:manage.py`). Lets names
+/// written by hand or saved before that, `python3` or `node`, keep matching.
+pub(crate) fn names_interpreter_of(name: &str, process_name: &str) -> bool {
+    process_name
+        .split_once(':')
+        .is_some_and(|(family, _)| interpreter_family(name) == family)
+}
+
+/// The local IP in a `PortInfo::address`. Addresses always end in a
+/// port: `ip:port` with IPv6 left unbracketed on Linux (`::1:8080`,
+/// `fe80::1%eth0:8080`), `[ip]:port` from the listeners crate, and
+/// `*:port` for lsof wildcards. The port is therefore split off the last
+/// colon before parsing, which an IPv6 parser would otherwise swallow as
+/// a final group. IPv4-mapped IPv6 addresses are folded to plain IPv4.
+pub(crate) fn parse_bind_ip(address: &str) -> Option<IpAddr> {
+    if let Ok(sa) = address.parse::<SocketAddr>() {
+        return Some(sa.ip().to_canonical());
+    }
+    let (host, _port) = address.rsplit_once(':')?;
+    if host == "*" {
+        return Some(IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+    }
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    let host = host.split('%').next().unwrap_or(host);
+    host.parse::<IpAddr>().ok().map(|ip| ip.to_canonical())
+}
+
+/// Split a query on top-level commas, trimming each term and dropping
+/// empty ones. Commas inside `{}` or `[]`, or escaped as `\,`, don't
+/// split, so regex terms like `a{1,3}` or `[a,b]` survive intact.
+fn split_query_terms(query: &str) -> Vec<&str> {
+    let mut terms = Vec::new();
+    let mut depth = 0usize;
+    let mut escaped = false;
+    let mut start = 0;
+    for (i, c) in query.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '{' | '[' => depth += 1,
+            '}' | ']' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                terms.push(&query[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    terms.push(&query[start..]);
+    terms
+        .into_iter()
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .collect()
+}
+
+/// Parse an inclusive `START-END` port range such as `3000-3010`.
+///
+/// Returns `Ok(None)` when `s` isn't shaped like a numeric range, so
+/// callers can fall back to name matching for targets like
+/// `docker-proxy`. A range whose start exceeds its end is an error.
+pub fn parse_port_range(s: &str) -> Result<Option<RangeInclusive<u16>>> {
+    let Some((start, end)) = s.split_once('-') else {
+        return Ok(None);
+    };
+    let (Ok(start), Ok(end)) = (start.parse::<u16>(), end.parse::<u16>()) else {
+        return Ok(None);
+    };
+    if start > end {
+        bail!("Invalid port range '{}': start is greater than end", s);
+    }
+    Ok(Some(start..=end))
+}
+
+/// Connections to one remote host, as `--by-remote` lists them.
+#[derive(Debug, Serialize)]
+pub struct RemoteGroup {
+    /// The remote IP, without its port.
+    pub remote: String,
+    pub count: usize,
+    /// Local ports those connections use, ascending.
+    pub local_ports: BTreeSet<u16>,
+}
+
+/// Split a `/tcp` or `/udp` qualifier off a port or port-range target,
+/// e.g. `53/udp` or `3000-3010/tcp`. Anything else, names included, comes
+/// back unchanged with no protocol.
+pub(crate) fn split_protocol(target: &str) -> (&str, Option<Protocol>) {
+    let Some((base, suffix)) = target.rsplit_once('/') else {
+        return (target, None);
+    };
+    let protocol = match suffix.to_ascii_lowercase().as_str() {
+        "tcp" => Protocol::Tcp,
+        "udp" => Protocol::Udp,
+        _ => return (target, None),
+    };
+    let is_port = base.parse::<u16>().is_ok()
+        || base
+            .split_once('-')
+            .is_some_and(|(a, b)| a.parse::<u16>().is_ok() && b.parse::<u16>().is_ok());
+    if is_port {
+        (base, Some(protocol))
+    } else {
+        (target, None)
+    }
+}
+
+/// Transport protocol of a socket. Part of the stable library surface.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
 #[serde(rename_all = "lowercase")]
 pub enum Protocol {
+    #[default]
     Tcp,
     Udp,
 }
@@ -271,11 +848,171 @@ impl fmt::Display for Protocol {
     }
 }
 
+/// Remote shells and desktops: ssh, telnet, RDP, VNC, WinRM.
+pub(crate) const REMOTE_ADMIN_PORTS: &[u16] = &[22, 23, 3389, 5900, 5985, 5986];
+/// Databases and caches that rarely belong on a public interface.
+pub(crate) const DATA_STORE_PORTS: &[u16] =
+    &[1433, 1521, 3306, 5432, 5984, 6379, 9200, 11211, 27017];
+
+/// `--anomaly`: the processes expected behind a well-known service, keyed
+/// by its name in the well-known port table. Names match as prefixes, so
+/// `sshd` also covers OpenSSH's `sshd-session`.
+pub(crate) const EXPECTED_PROCESSES: &[(&str, &[&str])] = &[
+    ("ssh", &["sshd", "dropbear"]),
+    ("ftp", &["vsftpd", "proftpd", "pure-ftpd"]),
+    ("smtp", SMTP_SERVERS),
+    ("smtps", SMTP_SERVERS),
+    ("submission", SMTP_SERVERS),
+    (
+        "dns",
+        &[
+            "named",
+            "dnsmasq",
+            "systemd-resolve",
+            "unbound",
+            "coredns",
+            "pdns",
+        ],
+    ),
+    ("http", WEB_SERVERS),
+    ("https", WEB_SERVERS),
+    ("pop3", MAIL_SERVERS),
+    ("pop3s", MAIL_SERVERS),
+    ("imap", MAIL_SERVERS),
+    ("imaps", MAIL_SERVERS),
+    ("mssql", &["sqlservr"]),
+    ("mysql", &["mysqld", "mariadbd"]),
+    ("postgres", &["postgres", "postmaster"]),
+    ("redis", &["redis-server", "valkey-server"]),
+    ("mongodb", &["mongod"]),
+];
+
+const WEB_SERVERS: &[&str] = &[
+    "nginx", "apache2", "httpd", "caddy", "haproxy", "envoy", "traefik", "lighttpd",
+];
+/// Postfix's listener is its `master` process.
+const SMTP_SERVERS: &[&str] = &[
+    "master",
+    "postfix",
+    "exim",
+    "sendmail",
+    "opensmtpd",
+    "smtpd",
+];
+const MAIL_SERVERS: &[&str] = &["dovecot", "cyrus"];
+
+/// Processes that hold a port on a container's behalf. They stand in for
+/// whatever the container runs, so they never count as unexpected.
+const PORT_FORWARDERS: &[&str] = &["docker-proxy", "rootlessport", "slirp4netns", "pasta"];
+
+/// Why a listener is flagged by `--security`, most severe first. New
+/// variants may be added in minor releases.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum Risk {
+    /// A remote-admin service reachable beyond loopback.
+    ExposedAdminService,
+    /// A database or cache reachable beyond loopback.
+    ExposedDataStore,
+    /// A non-root process on a privileged port (below 1024) reachable
+    /// beyond loopback. It got there through `CAP_NET_BIND_SERVICE` or a
+    /// lowered `net.ipv4.ip_unprivileged_port_start`, either of which is
+    /// worth knowing about.
+    NonRootOnPrivileged,
+    /// A root process on a privileged port reachable beyond loopback.
+    RootOnPrivileged,
+}
+
+impl fmt::Display for Risk {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Risk::ExposedAdminService => "exposed admin",
+            Risk::ExposedDataStore => "exposed data store",
+            Risk::NonRootOnPrivileged => "non-root <1024",
+            Risk::RootOnPrivileged => "root <1024",
+        })
+    }
+}
+
+/// TCP socket state, as reported by the kernel (Linux) or `lsof` (macOS).
+///
+/// Displayed and serialized with the netstat spelling, e.g. `TIME_WAIT`.
+/// New variants may be added in minor releases.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SocketState {
+    Established,
+    SynSent,
+    SynRecv,
+    FinWait1,
+    FinWait2,
+    TimeWait,
+    Close,
+    CloseWait,
+    LastAck,
+    Listen,
+    Closing,
+    Unknown(u8),
+}
+
+impl fmt::Display for SocketState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SocketState::Established => write!(f, "ESTABLISHED"),
+            SocketState::SynSent => write!(f, "SYN_SENT"),
+            SocketState::SynRecv => write!(f, "SYN_RECV"),
+            SocketState::FinWait1 => write!(f, "FIN_WAIT1"),
+            SocketState::FinWait2 => write!(f, "FIN_WAIT2"),
+            SocketState::TimeWait => write!(f, "TIME_WAIT"),
+            SocketState::Close => write!(f, "CLOSE"),
+            SocketState::CloseWait => write!(f, "CLOSE_WAIT"),
+            SocketState::LastAck => write!(f, "LAST_ACK"),
+            SocketState::Listen => write!(f, "LISTEN"),
+            SocketState::Closing => write!(f, "CLOSING"),
+            SocketState::Unknown(n) => write!(f, "UNKNOWN({n})"),
+        }
+    }
+}
+
+impl From<StateFilter> for SocketState {
+    fn from(filter: StateFilter) -> Self {
+        match filter {
+            StateFilter::Established => SocketState::Established,
+            StateFilter::SynSent => SocketState::SynSent,
+            StateFilter::SynRecv => SocketState::SynRecv,
+            StateFilter::FinWait1 => SocketState::FinWait1,
+            StateFilter::FinWait2 => SocketState::FinWait2,
+            StateFilter::TimeWait => SocketState::TimeWait,
+            StateFilter::Close => SocketState::Close,
+            StateFilter::CloseWait => SocketState::CloseWait,
+            StateFilter::LastAck => SocketState::LastAck,
+            StateFilter::Listen => SocketState::Listen,
+            StateFilter::Closing => SocketState::Closing,
+        }
+    }
+}
+
+impl Serialize for SocketState {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::collections::HashSet;
 
+    #[test]
+    fn socket_state_serializes_as_display_name() {
+        let json = serde_json::to_string(&SocketState::TimeWait).unwrap();
+        assert_eq!(json, "\"TIME_WAIT\"");
+    }
+
     #[test]
     fn docker_status_default_is_not_queried() {
         assert_eq!(DockerStatus::default(), DockerStatus::NotQueried);
@@ -304,6 +1041,25 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parse_port_range_valid() {
+        assert_eq!(parse_port_range("3000-3010").unwrap(), Some(3000..=3010));
+        assert_eq!(parse_port_range("80-80").unwrap(), Some(80..=80));
+    }
+
+    #[test]
+    fn parse_port_range_not_a_range() {
+        assert_eq!(parse_port_range("3000").unwrap(), None);
+        assert_eq!(parse_port_range("docker-proxy").unwrap(), None);
+        assert_eq!(parse_port_range("3000-").unwrap(), None);
+        assert_eq!(parse_port_range("1-70000").unwrap(), None);
+    }
+
+    #[test]
+    fn parse_port_range_reversed_is_error() {
+        assert!(parse_port_range("3010-3000").is_err());
+    }
+
     fn make_port_info() -> PortInfo {
         PortInfo {
             port: 8080,
@@ -311,15 +1067,198 @@ mod tests {
             pid: 1234,
             process_name: "node".to_string(),
             address: "127.0.0.1:8080".to_string(),
-            remote_address: None,
-            container: None,
-            service_name: None,
-            command_line: None,
-            cwd: None,
-            framework: None,
+            ..Default::default()
         }
     }
 
+    #[test]
+    fn interpreter_family_drops_version() {
+        assert_eq!(interpreter_family("python3.11"), "python");
+        assert_eq!(interpreter_family("python3"), "python");
+        assert_eq!(interpreter_family("node"), "node");
+    }
+
+    #[test]
+    fn interpreter_names_match_script_named_processes() {
+        assert!(names_interpreter_of("python3", "python:manage.py"));
+        assert!(names_interpreter_of("node", "node:server.js"));
+        assert!(!names_interpreter_of("node", "python:server.js"));
+        assert!(!names_interpreter_of("node", "nodejs-exporter"));
+    }
+
+    #[test]
+    fn risk_needs_exposure_and_ranks_admin_services_first() {
+        let listener = |port, address: &str, uid| PortInfo {
+            port,
+            address: address.to_string(),
+            uid,
+            ..make_port_info()
+        };
+
+        let redis = listener(6379, "0.0.0.0:6379", Some(999));
+        assert_eq!(redis.risk(), Some(Risk::ExposedDataStore));
+        let sshd = listener(22, "[::]:22", Some(0));
+        assert_eq!(sshd.risk(), Some(Risk::ExposedAdminService));
+        let nginx = listener(80, "0.0.0.0:80", Some(0));
+        assert_eq!(nginx.risk(), Some(Risk::RootOnPrivileged));
+        let capped = listener(443, "10.0.0.5:443", Some(1000));
+        assert_eq!(capped.risk(), Some(Risk::NonRootOnPrivileged));
+
+        // Loopback, unprivileged, and unknown owners aren't flagged.
+        assert_eq!(listener(6379, "127.0.0.1:6379", Some(0)).risk(), None);
+        assert_eq!(listener(8080, "0.0.0.0:8080", Some(0)).risk(), None);
+        assert_eq!(listener(80, "0.0.0.0:80", None).risk(), None);
+        let mut macos = listener(80, "*:80", None);
+        macos.user = Some("root".to_string());
+        assert_eq!(macos.risk(), Some(Risk::RootOnPrivileged));
+
+        let mut ports = vec![
+            listener(8080, "0.0.0.0:8080", Some(0)),
+            nginx,
+            capped,
+            redis,
+        ];
+        PortInfo::rank_by_risk(&mut ports);
+        let order: Vec<u16> = ports.iter().map(|p| p.port).collect();
+        assert_eq!(order, [6379, 443, 80, 8080]);
+    }
+
+    #[test]
+    fn anomaly_flags_unexpected_process_on_known_service() {
+        let bound_by = |port, name: &str| {
+            let mut p = PortInfo {
+                port,
+                process_name: name.to_string(),
+                ..make_port_info()
+            };
+            p.resolve_service_name();
+            p
+        };
+
+        assert_eq!(
+            bound_by(22, "nc").unexpected_process(),
+            Some(&["sshd", "dropbear"][..])
+        );
+        assert_eq!(bound_by(22, "sshd").unexpected_process(), None);
+        assert_eq!(bound_by(22, "sshd-session").unexpected_process(), None);
+        assert!(bound_by(22, "sshdx").unexpected_process().is_some());
+        assert!(bound_by(5432, "postgresql-evil")
+            .unexpected_process()
+            .is_some());
+        assert_eq!(bound_by(5432, "docker-proxy").unexpected_process(), None);
+        // No expectations for ports without a known service.
+        assert_eq!(bound_by(8080, "nc").unexpected_process(), None);
+
+        let mut container = bound_by(5432, "python3");
+        container.container = Some("db".to_string());
+        assert_eq!(container.unexpected_process(), None);
+        let mut conn = bound_by(22, "ssh");
+        conn.remote_address = Some("10.0.0.9:51000".to_string());
+        assert_eq!(conn.unexpected_process(), None);
+
+        let mut ports = vec![bound_by(22, "nc"), bound_by(6379, "redis-server")];
+        PortInfo::flag_anomalies(&mut ports);
+        assert_eq!(
+            ports[0].expected_processes,
+            Some(vec!["sshd".to_string(), "dropbear".to_string()])
+        );
+        assert_eq!(ports[1].expected_processes, None);
+    }
+
+    #[test]
+    fn filter_protocol_both_keeps_everything() {
+        let ports = vec![
+            make_port_info(),
+            PortInfo {
+                protocol: Protocol::Udp,
+                ..make_port_info()
+            },
+        ];
+        let kept = PortInfo::filter_protocol(ports.clone(), Some(ProtocolFilter::Both));
+        assert_eq!(kept.len(), 2);
+        let kept = PortInfo::filter_protocol(ports, Some(ProtocolFilter::Udp));
+        assert_eq!(kept.len(), 1);
+    }
+
+    #[test]
+    fn filter_state_keeps_matching_tcp_and_drops_udp() {
+        let with = |protocol, state| PortInfo {
+            protocol,
+            state,
+            ..make_port_info()
+        };
+        let ports = vec![
+            with(Protocol::Tcp, Some(SocketState::CloseWait)),
+            with(Protocol::Tcp, Some(SocketState::Established)),
+            with(Protocol::Udp, None),
+        ];
+
+        let filtered = PortInfo::filter_state(ports.clone(), Some(StateFilter::CloseWait));
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].state, Some(SocketState::CloseWait));
+
+        assert_eq!(PortInfo::filter_state(ports, None).len(), 3);
+    }
+
+    #[test]
+    fn default_sort_is_port_then_protocol_then_pid() {
+        let entry = |port, protocol, pid| PortInfo {
+            port,
+            protocol,
+            pid,
+            ..make_port_info()
+        };
+        let mut ports = vec![
+            entry(443, Protocol::Tcp, 1),
+            entry(53, Protocol::Udp, 7),
+            entry(53, Protocol::Tcp, 9),
+            entry(53, Protocol::Tcp, 3),
+        ];
+        PortInfo::sort_vec(&mut ports, None);
+        let keys: Vec<_> = ports.iter().map(|p| (p.port, p.protocol, p.pid)).collect();
+        assert_eq!(
+            keys,
+            [
+                (53, Protocol::Tcp, 3),
+                (53, Protocol::Tcp, 9),
+                (53, Protocol::Udp, 7),
+                (443, Protocol::Tcp, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn sort_by_address_is_numeric_and_by_container_puts_none_last() {
+        let at = |address: &str, container: Option<&str>| PortInfo {
+            address: address.to_string(),
+            container: container.map(String::from),
+            ..make_port_info()
+        };
+        let mut ports = vec![
+            at("10.0.0.10:80", None),
+            at("::1:80", Some("web")),
+            at("10.0.0.9:80", Some("api")),
+        ];
+
+        PortInfo::sort_vec(&mut ports, Some(SortField::Address));
+        let addrs: Vec<_> = ports.iter().map(|p| p.address.as_str()).collect();
+        assert_eq!(addrs, ["10.0.0.9:80", "10.0.0.10:80", "::1:80"]);
+
+        PortInfo::sort_vec(&mut ports, Some(SortField::Container));
+        let containers: Vec<_> = ports.iter().map(|p| p.container.as_deref()).collect();
+        assert_eq!(containers, [Some("api"), Some("web"), None]);
+    }
+
+    #[test]
+    fn sort_none_keeps_raw_order() {
+        let mut ports = vec![make_port_info(), make_port_info()];
+        ports[0].port = 9000;
+        ports[1].port = 80;
+        PortInfo::sort_vec(&mut ports, Some(SortField::Unsorted));
+        assert_eq!(ports[0].port, 9000);
+        assert_eq!(ports[1].port, 80);
+    }
+
     #[test]
     fn eq_ignores_framework() {
         let a = make_port_info();
@@ -368,4 +1307,333 @@ mod tests {
              in command_line/cwd as identical"
         );
     }
+
+    #[test]
+    fn same_socket_in_another_namespace_is_distinct() {
+        let host = make_port_info();
+        let mut container = make_port_info();
+        container.netns = Some("3f2a9c1b7d4e".into());
+
+        assert_ne!(host, container);
+        let set: HashSet<_> = [host, container].into_iter().collect();
+        assert_eq!(set.len(), 2);
+    }
+
+    #[test]
+    fn docker_forwarders_include_rootless_and_desktop() {
+        assert!(is_docker_forwarder("docker-proxy"));
+        assert!(is_docker_forwarder("rootlesskit"));
+        assert!(is_docker_forwarder("com.docker.backend"));
+        assert!(!is_docker_forwarder("postgres"));
+    }
+
+    #[cfg(feature = "docker")]
+    #[test]
+    fn attach_containers_labels_forwarders_and_ownerless_listeners() {
+        let mut mappings = HashMap::new();
+        mappings.insert(
+            8080,
+            docker::ContainerInfo {
+                name: "web".to_string(),
+                id: Some("3f2a9c1b7d4e".to_string()),
+                image: None,
+                container_port: Some(80),
+            },
+        );
+        let rootless = PortInfo {
+            process_name: "rootlesskit".to_string(),
+            ..make_port_info()
+        };
+        let bystander = make_port_info();
+        let named = PortInfo {
+            process_name: "docker-proxy".to_string(),
+            container: Some("api".to_string()),
+            ..make_port_info()
+        };
+
+        // userland-proxy: false, or a root docker-proxy seen by non-root.
+        let ownerless = PortInfo {
+            pid: UNATTRIBUTED_PID,
+            process_name: KERNEL_NAME.to_string(),
+            ..make_port_info()
+        };
+        let connection = PortInfo {
+            pid: UNATTRIBUTED_PID,
+            process_name: UNATTRIBUTED_NAME.to_string(),
+            remote_address: Some("10.0.0.9:51000".to_string()),
+            ..make_port_info()
+        };
+
+        let ports = attach_containers(
+            vec![rootless, bystander, named, ownerless, connection],
+            &mappings,
+        );
+        assert_eq!(ports[0].container.as_deref(), Some("web"));
+        assert_eq!(ports[0].container_id.as_deref(), Some("3f2a9c1b7d4e"));
+        assert_eq!(ports[0].container_port, Some(80));
+        assert_eq!(ports[1].container, None);
+        assert_eq!(ports[2].container.as_deref(), Some("api"));
+        assert_eq!(ports[3].container.as_deref(), Some("web"));
+        assert_eq!(ports[4].container, None);
+    }
+
+    #[test]
+    fn parse_bind_ip_handles_platform_formats() {
+        let ip = |s: &str| s.parse::<IpAddr>().unwrap();
+        assert_eq!(parse_bind_ip("10.0.0.5:8080"), Some(ip("10.0.0.5")));
+        assert_eq!(parse_bind_ip("[::1]:8080"), Some(ip("::1")));
+        assert_eq!(parse_bind_ip("::1:8080"), Some(ip("::1")));
+        assert_eq!(parse_bind_ip(":::80"), Some(ip("::")));
+        assert_eq!(parse_bind_ip("fe80::1%eth0:80"), Some(ip("fe80::1")));
+        assert_eq!(parse_bind_ip("[fe80::1%eth0]:80"), Some(ip("fe80::1")));
+        assert_eq!(parse_bind_ip("::ffff:10.0.0.5:80"), Some(ip("10.0.0.5")));
+        assert_eq!(parse_bind_ip("*:53"), Some(ip("0.0.0.0")));
+        assert_eq!(parse_bind_ip("garbage"), None);
+    }
+
+    #[test]
+    fn remote_display_prefers_the_resolved_name() {
+        let mut conn = PortInfo {
+            remote_address: Some("[2606:4700::1111]:443".to_string()),
+            ..make_port_info()
+        };
+        assert_eq!(
+            conn.remote_display().as_deref(),
+            Some("[2606:4700::1111]:443")
+        );
+        conn.remote_host = Some("one.one.one.one".to_string());
+        assert_eq!(
+            conn.remote_display().as_deref(),
+            Some("one.one.one.one:443")
+        );
+        assert_eq!(make_port_info().remote_display(), None);
+    }
+
+    #[test]
+    fn group_by_remote_strips_ports_and_counts() {
+        let conn = |port, remote: Option<&str>| PortInfo {
+            port,
+            remote_address: remote.map(str::to_string),
+            ..make_port_info()
+        };
+        let ports = vec![
+            conn(443, Some("203.0.113.9:51000")),
+            conn(443, Some("203.0.113.9:51001")),
+            conn(80, Some("::ffff:203.0.113.9:51002")),
+            conn(5432, Some("10.0.0.2:40000")),
+            conn(22, None),
+        ];
+
+        let groups = PortInfo::group_by_remote(&ports);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].remote, "203.0.113.9");
+        assert_eq!(groups[0].count, 3);
+        assert_eq!(
+            groups[0].local_ports.iter().copied().collect::<Vec<_>>(),
+            [80, 443]
+        );
+        assert_eq!(groups[1].remote, "10.0.0.2");
+        assert_eq!(groups[1].count, 1);
+    }
+
+    #[test]
+    fn interface_match_includes_wildcards() {
+        let eth0 = ["10.0.0.5".parse().unwrap()];
+        let at = |address: &str| PortInfo {
+            address: address.to_string(),
+            ..make_port_info()
+        };
+        assert!(at("10.0.0.5:80").is_bound_to_any(&eth0));
+        assert!(at("0.0.0.0:80").is_bound_to_any(&eth0));
+        assert!(at("[::]:80").is_bound_to_any(&eth0));
+        assert!(!at("127.0.0.1:80").is_bound_to_any(&eth0));
+    }
+
+    #[test]
+    fn deleted_exe_raises_health_warning() {
+        let mut deleted = PortInfo {
+            exe_path: Some("/usr/sbin/nginx (deleted)".to_string()),
+            ..make_port_info()
+        };
+        deleted.resolve_health_warnings();
+        assert_eq!(deleted.warnings, vec![HealthWarning::DeletedBinary]);
+
+        let mut intact = PortInfo {
+            exe_path: Some("/usr/sbin/nginx".to_string()),
+            ..make_port_info()
+        };
+        intact.resolve_health_warnings();
+        assert!(intact.warnings.is_empty());
+    }
+
+    #[test]
+    fn split_query_terms_respects_regex_groups() {
+        assert_eq!(
+            split_query_terms("nginx, redis,,postgres"),
+            ["nginx", "redis", "postgres"]
+        );
+        assert_eq!(split_query_terms("a{1,3},[x,y]z"), ["a{1,3}", "[x,y]z"]);
+        assert_eq!(split_query_terms(r"a\,b,c"), [r"a\,b", "c"]);
+    }
+
+    #[test]
+    fn filter_by_query_ors_comma_separated_terms() {
+        let named = |name: &str, port: u16| PortInfo {
+            process_name: name.to_string(),
+            port,
+            ..make_port_info()
+        };
+        let ports = vec![
+            named("nginx", 80),
+            named("redis", 6379),
+            named("node", 3000),
+        ];
+
+        let hits = PortInfo::filter_by_query(ports.clone(), "nginx,redis", false).unwrap();
+        assert_eq!(hits.len(), 2);
+        let hits = PortInfo::filter_by_query(ports.clone(), "3000,nginx", false).unwrap();
+        assert_eq!(hits.len(), 2);
+        let hits = PortInfo::filter_by_query(ports, "^ng,^re", true).unwrap();
+        assert_eq!(hits.len(), 2);
+    }
+
+    #[test]
+    fn query_ports_needs_every_term_to_be_a_port() {
+        assert_eq!(PortInfo::query_ports("8080", false), Some(vec![8080]));
+        assert_eq!(PortInfo::query_ports("80, 443", false), Some(vec![80, 443]));
+        assert_eq!(PortInfo::query_ports("80,nginx", false), None);
+        assert_eq!(PortInfo::query_ports("8080", true), None);
+        assert_eq!(PortInfo::query_ports("70000", false), None);
+        assert_eq!(PortInfo::query_ports(",", false), None);
+        assert_eq!(PortInfo::query_ports("53/udp", false), Some(vec![53]));
+    }
+
+    #[test]
+    fn protocol_suffix_narrows_port_targets_only() {
+        assert_eq!(split_protocol("53/udp"), ("53", Some(Protocol::Udp)));
+        assert_eq!(split_protocol("53/TCP"), ("53", Some(Protocol::Tcp)));
+        assert_eq!(
+            split_protocol("3000-3010/tcp"),
+            ("3000-3010", Some(Protocol::Tcp))
+        );
+        assert_eq!(split_protocol("53/sctp"), ("53/sctp", None));
+        assert_eq!(split_protocol("nginx/tcp"), ("nginx/tcp", None));
+        assert_eq!(split_protocol("53"), ("53", None));
+
+        let dns = |protocol| PortInfo {
+            port: 53,
+            protocol,
+            ..make_port_info()
+        };
+        let ports = vec![dns(Protocol::Tcp), dns(Protocol::Udp)];
+        let hits = PortInfo::filter_by_query(ports.clone(), "53/udp", false).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].protocol, Protocol::Udp);
+        assert_eq!(
+            PortInfo::filter_by_query(ports, "53", false).unwrap().len(),
+            2
+        );
+    }
+
+    #[test]
+    fn filter_by_query_matches_container_id_prefix() {
+        let web = PortInfo {
+            container: Some("web".to_string()),
+            container_id: Some("3f2a9c1b7d4e".to_string()),
+            ..make_port_info()
+        };
+        let ports = vec![web, make_port_info()];
+
+        let hits = PortInfo::filter_by_query(ports.clone(), "3F2A", false).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].container.as_deref(), Some("web"));
+        // A prefix, not a substring.
+        let hits = PortInfo::filter_by_query(ports, "9c1b", false).unwrap();
+        assert!(hits.is_empty());
+    }
+
+    #[test]
+    fn count_by_service_orders_by_count_with_unknown_last() {
+        let service = |port, name: Option<&str>| PortInfo {
+            port,
+            service_name: name.map(str::to_string),
+            ..make_port_info()
+        };
+        let ports = vec![
+            service(22, Some("ssh")),
+            service(3000, None),
+            service(80, Some("http")),
+            service(5432, Some("postgres")),
+            service(3001, None),
+            service(8080, Some("http")),
+            service(3002, None),
+        ];
+
+        let counts = PortInfo::count_by_service(&ports);
+        let expected = [
+            (Some("http"), 2),
+            (Some("postgres"), 1),
+            (Some("ssh"), 1),
+            (None, 3),
+        ];
+        assert_eq!(counts.len(), expected.len());
+        for ((name, count), (want_name, want_count)) in counts.iter().zip(expected) {
+            assert_eq!((name.as_deref(), *count), (want_name, want_count));
+        }
+    }
+
+    #[test]
+    fn attach_conn_counts_matches_port_and_pid() {
+        let listener = |port, pid| PortInfo {
+            port,
+            pid,
+            ..make_port_info()
+        };
+        let conn = |port, pid| PortInfo {
+            remote_address: Some("10.0.0.9:50000".to_string()),
+            ..listener(port, pid)
+        };
+        let mut ports = vec![listener(443, 10), listener(80, 10), listener(8443, 20)];
+        let connections = vec![
+            conn(443, 10),
+            conn(443, 10),
+            // Outbound connection from another process that happens to
+            // use the same local port number.
+            conn(443, 99),
+            conn(8443, 20),
+        ];
+
+        PortInfo::attach_conn_counts(&mut ports, &connections);
+        let counts: Vec<_> = ports.iter().map(|p| p.conn_count).collect();
+        assert_eq!(counts, [Some(2), Some(0), Some(1)]);
+    }
+
+    #[test]
+    fn attach_conn_counts_counts_dual_stack_connections_once() {
+        let listener = |address: &str| PortInfo {
+            port: 443,
+            pid: 10,
+            address: address.to_string(),
+            ..make_port_info()
+        };
+        let conn = |address: &str| PortInfo {
+            remote_address: Some("10.0.0.9:50000".to_string()),
+            ..listener(address)
+        };
+        let mut ports = vec![listener("[::]:443"), listener("0.0.0.0:443")];
+        let connections = vec![
+            conn("10.0.0.1:443"),
+            conn("10.0.0.1:443"),
+            conn("[2001:db8::1]:443"),
+        ];
+
+        PortInfo::attach_conn_counts(&mut ports, &connections);
+        let counts: Vec<_> = ports.iter().map(|p| p.conn_count).collect();
+        assert_eq!(counts, [Some(1), Some(2)]);
+
+        // A lone dual-stack `[::]` listener still gets every connection.
+        let mut ports = vec![listener("[::]:443")];
+        PortInfo::attach_conn_counts(&mut ports, &connections);
+        assert_eq!(ports[0].conn_count, Some(3));
+    }
 }
@@ -6,7 +6,7 @@ use std::time::Duration;
 use anyhow::Result;
 
 use crate::ancestry;
-use crate::cli::{ProtocolFilter, SortField};
+use crate::cli::{ProtocolFilter, SortField, StateFilter};
 use crate::filter;
 use crate::framework;
 use crate::output::{json, table};
@@ -16,14 +16,31 @@ use crate::types::{DockerStatus, PortInfo};
 
 pub struct WatchOptions {
     pub interval: Duration,
+    /// Stop after this many refreshes; `None` runs until interrupted.
+    pub iterations: Option<u64>,
     pub json: bool,
+    /// Print only what appeared and disappeared, one JSON line per
+    /// refresh, instead of redrawing the list.
+    pub json_diff: bool,
     pub filter: Option<String>,
     pub connections: bool,
     pub sort: Option<SortField>,
     pub protocol: Option<ProtocolFilter>,
+    pub state: Option<StateFilter>,
+    pub interface: Option<String>,
     pub use_regex: bool,
     pub why: bool,
     pub dev: bool,
+    /// Only rows published by a container.
+    pub docker_only: bool,
+    pub wide: bool,
+    pub bell: bool,
+    pub notify: bool,
+    /// Leave the screen alone on refreshes where no port appeared or
+    /// disappeared.
+    pub diff_only: bool,
+    /// Refresh on a keypress instead of every `interval`.
+    pub manual: bool,
 }
 
 pub fn run(options: WatchOptions) -> Result<()> {
@@ -33,9 +50,13 @@ pub fn run(options: WatchOptions) -> Result<()> {
     // unreachable daemon spams a line per interval into scrollback
     // (stderr is never cleared by `\x1B[2J`).
     let mut previous_status: Option<DockerStatus> = None;
+    // The first refresh is the baseline: everything is "new" to it, so
+    // alerts only fire from the second refresh onwards.
+    let mut first_refresh = true;
+    let mut refresh: u64 = 0;
 
     loop {
-        clear_screen();
+        refresh += 1;
         project::clear_cache();
         framework::clear_cache();
 
@@ -46,44 +67,146 @@ pub fn run(options: WatchOptions) -> Result<()> {
         };
         let docker_status = listing.docker_status;
 
-        if previous_status.as_ref() != Some(&docker_status) {
-            table::print_warning(&docker_status);
-            previous_status = Some(docker_status.clone());
-        }
-
-        let mut ports = PortInfo::filter_protocol(listing.ports, options.protocol);
+        let ports = PortInfo::filter_protocol(listing.ports, options.protocol);
+        let ports = PortInfo::filter_state(ports, options.state);
+        let mut ports = PortInfo::filter_interface(ports, options.interface.as_deref())?;
         if options.dev {
             filter::retain_dev_only(&mut ports);
         }
+        if options.docker_only {
+            ports.retain(|p| p.container.is_some());
+        }
         let mut filtered = filter_ports(ports, &options.filter, options.use_regex)?;
         PortInfo::sort_vec(&mut filtered, options.sort);
 
-        if options.why {
-            let pids_with_names: Vec<(u32, &str)> = filtered
-                .iter()
-                .map(|p| (p.pid, p.process_name.as_str()))
-                .collect();
-            let ancestry_map = ancestry::get_ancestry_batch(&pids_with_names);
-            if options.json {
-                json::print_ports_why(&filtered, &ancestry_map, &docker_status);
-            } else {
-                table::print_ports_why(&filtered, &ancestry_map);
+        let new_ports: HashSet<&PortInfo> =
+            filtered.iter().filter(|p| !previous.contains(*p)).collect();
+        let current: HashSet<&PortInfo> = filtered.iter().collect();
+        let mut gone: Vec<PortInfo> = previous
+            .iter()
+            .filter(|p| !current.contains(p))
+            .cloned()
+            .collect();
+
+        if options.diff_only && !first_refresh && new_ports.is_empty() && gone.is_empty() {
+            if options.iterations.is_some_and(|n| refresh >= n) || !wait(&options)? {
+                return Ok(());
             }
-        } else if options.json {
-            json::print_ports(&filtered, &docker_status);
-        } else {
-            let new_ports: HashSet<&PortInfo> =
-                filtered.iter().filter(|p| !previous.contains(*p)).collect();
+            continue;
+        }
 
-            table::print_ports_watch(&filtered, &new_ports);
+        if !options.json_diff {
+            clear_screen();
         }
+        if previous_status.as_ref() != Some(&docker_status) {
+            table::print_warning(&docker_status);
+            previous_status = Some(docker_status.clone());
+        }
+        // Appeared ports in display order, not HashSet order.
+        let appeared: Vec<&PortInfo> = filtered.iter().filter(|p| new_ports.contains(p)).collect();
+        PortInfo::sort_vec(&mut gone, options.sort);
 
-        print_watch_status(&options);
+        if options.json_diff {
+            // The first refresh reports everything as appeared, which
+            // gives consumers their starting set.
+            json::print_watch_diff(&mut io::stdout().lock(), &appeared, &gone)?;
+        } else {
+            print_refresh(&options, &filtered, &new_ports, &docker_status)?;
+            if !options.json {
+                table::print_ports_gone(&mut io::stdout().lock(), &gone)?;
+            }
+            print_watch_status(&options, refresh);
+        }
         io::stdout().flush()?;
 
+        if !first_refresh && !appeared.is_empty() {
+            alert(&options, &appeared);
+        }
+        first_refresh = false;
+
         previous = filtered.into_iter().collect();
-        thread::sleep(options.interval);
+        if options.iterations.is_some_and(|n| refresh >= n) || !wait(&options)? {
+            return Ok(());
+        }
+    }
+}
+
+/// Wait until the next refresh is due. Returns `false` when the user
+/// asked to quit.
+fn wait(options: &WatchOptions) -> Result<bool> {
+    if options.manual {
+        return wait_for_key();
+    }
+    thread::sleep(options.interval);
+    Ok(true)
+}
+
+/// Block until space, `r` or Enter (refresh) or `q`, Esc or Ctrl+C
+/// (quit). Raw mode is only on while waiting, so output keeps its normal
+/// line endings.
+#[cfg(feature = "tui")]
+fn wait_for_key() -> Result<bool> {
+    use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+    use crossterm::terminal;
+
+    struct RawMode;
+    impl Drop for RawMode {
+        fn drop(&mut self) {
+            let _ = terminal::disable_raw_mode();
+        }
     }
+
+    terminal::enable_raw_mode()?;
+    let _raw = RawMode;
+    loop {
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match key.code {
+            KeyCode::Char(' ') | KeyCode::Char('r') | KeyCode::Enter => return Ok(true),
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(false),
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                return Ok(false)
+            }
+            _ => {}
+        }
+    }
+}
+
+// `run_watch` rejects manual refresh when the feature is off.
+#[cfg(not(feature = "tui"))]
+fn wait_for_key() -> Result<bool> {
+    Ok(false)
+}
+
+/// Redraw the full list for one refresh.
+fn print_refresh(
+    options: &WatchOptions,
+    filtered: &[PortInfo],
+    new_ports: &HashSet<&PortInfo>,
+    docker_status: &DockerStatus,
+) -> io::Result<()> {
+    let mut out = io::stdout().lock();
+    if options.why {
+        let pids_with_names: Vec<(u32, &str)> = filtered
+            .iter()
+            .map(|p| (p.pid, p.process_name.as_str()))
+            .collect();
+        let ancestry_map = ancestry::get_ancestry_batch(&pids_with_names);
+        if options.json {
+            json::print_ports_why(&mut out, filtered, &ancestry_map, docker_status)?;
+        } else {
+            table::print_ports_why(&mut out, filtered, &ancestry_map)?;
+        }
+    } else if options.json {
+        json::print_ports(&mut out, filtered, docker_status)?;
+    } else {
+        table::print_ports_watch(&mut out, filtered, new_ports, options.wide)?;
+    }
+    Ok(())
 }
 
 fn filter_ports(
@@ -97,11 +220,54 @@ fn filter_ports(
     }
 }
 
+/// Bell and desktop notification for newly appeared ports. The bell goes
+/// to stderr so it can't corrupt `--json` output on stdout.
+fn alert(options: &WatchOptions, appeared: &[&PortInfo]) {
+    if options.bell {
+        eprint!("\x07");
+        let _ = io::stderr().flush();
+    }
+    if options.notify {
+        notify(appeared);
+    }
+}
+
+#[cfg(feature = "notify")]
+fn notify(appeared: &[&PortInfo]) {
+    use colored::Colorize;
+
+    let summary = match appeared.len() {
+        1 => "New port in ports watch".to_string(),
+        n => format!("{n} new ports in ports watch"),
+    };
+    let body = appeared
+        .iter()
+        .map(|p| format!("{}/{} {} ({})", p.port, p.protocol, p.process_name, p.pid))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    if let Err(e) = notify_rust::Notification::new()
+        .appname("ports")
+        .summary(&summary)
+        .body(&body)
+        .show()
+    {
+        eprintln!(
+            "{} desktop notification failed: {e}",
+            "Warning:".yellow().bold()
+        );
+    }
+}
+
+// `run_watch` rejects `--notify` when the feature is off.
+#[cfg(not(feature = "notify"))]
+fn notify(_appeared: &[&PortInfo]) {}
+
 fn clear_screen() {
     print!("\x1B[2J\x1B[1;1H");
 }
 
-fn print_watch_status(options: &WatchOptions) {
+fn print_watch_status(options: &WatchOptions, refresh: u64) {
     use colored::Colorize;
     let mode = if options.connections {
         "connections"
@@ -109,9 +275,77 @@ fn print_watch_status(options: &WatchOptions) {
         "listening"
     };
     println!(
-        "\n{} {} (every {:.1}s, Ctrl+C to exit)",
+        "\n{} {} ({})",
         "Watching".dimmed(),
         mode.dimmed(),
-        options.interval.as_secs_f64()
+        watch_status(options, refresh)
     );
 }
+
+/// The parenthesized part of the status line: cadence, then progress.
+fn watch_status(options: &WatchOptions, refresh: u64) -> String {
+    let cadence = if options.manual {
+        "space or r to refresh".to_string()
+    } else {
+        format!("every {:.1}s", options.interval.as_secs_f64())
+    };
+    let mut progress = match options.iterations {
+        Some(n) => format!("refresh {refresh}/{n}"),
+        None if options.manual => "q to quit".to_string(),
+        None => "Ctrl+C to exit".to_string(),
+    };
+    if options.diff_only {
+        progress.push_str(", redrawn on changes only");
+    }
+    format!("{cadence}, {progress}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options() -> WatchOptions {
+        WatchOptions {
+            interval: Duration::from_secs(2),
+            iterations: None,
+            json: false,
+            json_diff: false,
+            filter: None,
+            connections: false,
+            sort: None,
+            protocol: None,
+            state: None,
+            interface: None,
+            use_regex: false,
+            why: false,
+            dev: false,
+            docker_only: false,
+            wide: false,
+            bell: false,
+            notify: false,
+            diff_only: false,
+            manual: false,
+        }
+    }
+
+    #[test]
+    fn status_names_the_refresh_key_in_manual_mode() {
+        assert_eq!(watch_status(&options(), 1), "every 2.0s, Ctrl+C to exit");
+
+        let manual = WatchOptions {
+            manual: true,
+            ..options()
+        };
+        assert_eq!(watch_status(&manual, 1), "space or r to refresh, q to quit");
+
+        let counted = WatchOptions {
+            iterations: Some(3),
+            diff_only: true,
+            ..manual
+        };
+        assert_eq!(
+            watch_status(&counted, 2),
+            "space or r to refresh, refresh 2/3, redrawn on changes only"
+        );
+    }
+}
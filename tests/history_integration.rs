@@ -145,6 +145,30 @@ fn test_history_show_json_output() {
     assert!(json.unwrap().is_array(), "Expected JSON array");
 }
 
+#[test]
+fn test_history_show_ndjson_output() {
+    let temp_home = TempDir::new().expect("Failed to create temp dir");
+
+    let (success, _, _) = run_and_capture(&["history", "record"], &temp_home);
+    assert!(success, "record failed");
+
+    // --format wins over --json
+    let (success, stdout, stderr) = run_and_capture(
+        &[
+            "--json", "history", "show", "--format", "ndjson", "--limit", "0",
+        ],
+        &temp_home,
+    );
+    assert!(success, "history show --format ndjson failed: {}", stderr);
+
+    for line in stdout.lines() {
+        let entry: serde_json::Value = serde_json::from_str(line)
+            .unwrap_or_else(|e| panic!("Expected one JSON object per line ({e}): {line}"));
+        assert!(entry.is_object(), "Expected JSON object, got: {}", line);
+        assert!(entry.get("port").is_some(), "Missing port: {}", line);
+    }
+}
+
 #[test]
 fn test_history_show_with_port_filter() {
     let temp_home = TempDir::new().expect("Failed to create temp dir");
@@ -171,6 +195,29 @@ fn test_history_show_with_process_filter() {
     assert!(success, "history show -P failed: {}", stderr);
 }
 
+#[test]
+fn test_history_show_with_address_filter() {
+    let temp_home = TempDir::new().expect("Failed to create temp dir");
+
+    // Record
+    let _ = run_and_capture(&["history", "record"], &temp_home);
+
+    // Every returned entry must match the address substring
+    let (success, stdout, stderr) = run_and_capture(
+        &["--json", "history", "show", "--address", "127.0.0.1"],
+        &temp_home,
+    );
+    assert!(success, "history show --address failed: {}", stderr);
+    let json: serde_json::Value = serde_json::from_str(&stdout).expect("invalid JSON");
+    for entry in json.as_array().expect("Expected JSON array") {
+        let address = entry["address"].as_str().unwrap_or_default();
+        assert!(
+            address.contains("127.0.0.1"),
+            "unexpected address {address}"
+        );
+    }
+}
+
 #[test]
 fn test_history_show_with_hours_limit() {
     let temp_home = TempDir::new().expect("Failed to create temp dir");
@@ -196,6 +243,135 @@ fn test_history_show_with_limit() {
     assert!(success, "history show --limit failed: {}", stderr);
 }
 
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+#[test]
+fn test_history_records_socket_state() {
+    let temp_home = TempDir::new().expect("Failed to create temp dir");
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port().to_string();
+    let _client = std::net::TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    let _server = listener.accept().unwrap();
+
+    let (success, _, stderr) = run_and_capture(&["history", "record", "--connections"], &temp_home);
+    assert!(success, "record failed: {}", stderr);
+
+    let (success, stdout, stderr) =
+        run_and_capture(&["--json", "history", "show", "--port", &port], &temp_home);
+    assert!(success, "history show failed: {}", stderr);
+    let entries: serde_json::Value = serde_json::from_str(&stdout).expect("parse history");
+    let states: Vec<&str> = entries
+        .as_array()
+        .expect("entries array")
+        .iter()
+        .filter_map(|e| e["state"].as_str())
+        .collect();
+    assert!(states.contains(&"LISTEN"), "{states:?}");
+    assert!(states.contains(&"ESTABLISHED"), "{states:?}");
+}
+
+#[test]
+fn test_history_records_resolved_peer_names() {
+    let temp_home = TempDir::new().expect("Failed to create temp dir");
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port().to_string();
+    let _client = std::net::TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    let _server = listener.accept().unwrap();
+
+    let (success, _, stderr) = run_and_capture(
+        &["history", "record", "--connections", "--resolve-names"],
+        &temp_home,
+    );
+    assert!(success, "record failed: {}", stderr);
+
+    let (success, stdout, stderr) =
+        run_and_capture(&["--json", "history", "show", "--port", &port], &temp_home);
+    assert!(success, "history show failed: {}", stderr);
+    let entries: serde_json::Value = serde_json::from_str(&stdout).expect("parse history");
+    let peers: Vec<_> = entries
+        .as_array()
+        .expect("entries array")
+        .iter()
+        .filter(|e| e["remote_address"].is_string())
+        .collect();
+    assert!(!peers.is_empty(), "{stdout}");
+    // 127.0.0.1 is `localhost` in every hosts file.
+    assert!(
+        peers.iter().all(|e| e["remote_host"] == "localhost"),
+        "{stdout}"
+    );
+}
+
+#[test]
+fn test_history_show_filters_by_state() {
+    let temp_home = TempDir::new().expect("Failed to create temp dir");
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port().to_string();
+    let _client = std::net::TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    let _server = listener.accept().unwrap();
+
+    let (success, _, stderr) = run_and_capture(&["history", "record", "--connections"], &temp_home);
+    assert!(success, "record failed: {}", stderr);
+
+    let (success, stdout, stderr) = run_and_capture(
+        &[
+            "--json",
+            "history",
+            "show",
+            "--port",
+            &port,
+            "--state",
+            "established",
+        ],
+        &temp_home,
+    );
+    assert!(success, "history show failed: {}", stderr);
+    let entries: serde_json::Value = serde_json::from_str(&stdout).expect("parse history");
+    let states: Vec<&str> = entries
+        .as_array()
+        .expect("entries array")
+        .iter()
+        .map(|e| e["state"].as_str().unwrap_or("-"))
+        .collect();
+    assert!(!states.is_empty(), "no rows for port {port}");
+    assert!(states.iter().all(|s| *s == "ESTABLISHED"), "{states:?}");
+}
+
+#[test]
+fn test_history_state_is_rejected_outside_show() {
+    let temp_home = TempDir::new().expect("Failed to create temp dir");
+    for action in [&["record"][..], &["timeline", "80"], &["stats"], &["diff"]] {
+        let mut args = vec!["history"];
+        args.extend_from_slice(action);
+        args.extend(["--state", "established"]);
+        let (success, _, stderr) = run_and_capture(&args, &temp_home);
+        assert!(!success, "{args:?} accepted --state");
+        assert!(stderr.contains("--state only applies"), "{stderr}");
+    }
+}
+
+#[test]
+fn test_history_show_latest_keeps_one_row_per_listener() {
+    let temp_home = TempDir::new().expect("Failed to create temp dir");
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port().to_string();
+
+    for _ in 0..2 {
+        let (success, _, stderr) = run_and_capture(&["history", "record"], &temp_home);
+        assert!(success, "record failed: {}", stderr);
+    }
+
+    let count_rows = |extra: &[&str]| {
+        let mut args = vec!["--json", "history", "show", "--port", &port];
+        args.extend_from_slice(extra);
+        let (success, stdout, stderr) = run_and_capture(&args, &temp_home);
+        assert!(success, "history show failed: {}", stderr);
+        let entries: serde_json::Value = serde_json::from_str(&stdout).expect("parse history");
+        entries.as_array().expect("entries array").len()
+    };
+    assert_eq!(count_rows(&[]), 2);
+    assert_eq!(count_rows(&["--latest"]), 1);
+}
+
 // ============================================================================
 // history timeline
 // ============================================================================
@@ -319,6 +495,22 @@ fn test_history_stats_json_output() {
         "Expected db_size_bytes"
     );
     assert!(json.get("top_ports").is_some(), "Expected top_ports");
+    assert!(
+        json.get("top_processes").is_some_and(|v| v.is_array()),
+        "Expected top_processes array"
+    );
+
+    // The protocol split covers every entry; only TCP rows have a state.
+    let count = |v: &serde_json::Value| v.as_u64().expect("a count");
+    assert_eq!(
+        count(&json["tcp_entries"]) + count(&json["udp_entries"]),
+        count(&json["total_entries"])
+    );
+    let states = json["state_counts"].as_array().expect("state_counts array");
+    let counts: Vec<u64> = states.iter().map(|s| count(&s["count"])).collect();
+    assert!(states.iter().all(|s| s["state"].is_string()));
+    assert!(counts.windows(2).all(|w| w[0] >= w[1]), "most common first");
+    assert_eq!(counts.iter().sum::<u64>(), count(&json["tcp_entries"]));
 }
 
 // ============================================================================
@@ -391,6 +583,28 @@ fn test_history_clean_json_output() {
 // Integration workflow tests
 // ============================================================================
 
+#[test]
+fn test_history_diff_grouped_json() {
+    let temp_home = TempDir::new().expect("Failed to create temp dir");
+    for _ in 0..2 {
+        let (success, _, stderr) = run_and_capture(&["history", "record"], &temp_home);
+        assert!(success, "record failed: {}", stderr);
+    }
+
+    let (success, stdout, stderr) =
+        run_and_capture(&["--json", "history", "diff", "--grouped"], &temp_home);
+    assert!(success, "diff failed: {}", stderr);
+    let diff: serde_json::Value = serde_json::from_str(&stdout).expect("parse diff");
+    for key in ["appeared", "disappeared", "changed"] {
+        assert!(diff[key].is_array(), "missing {key}: {stdout}");
+    }
+
+    let (success, stdout, _) = run_and_capture(&["--json", "history", "diff"], &temp_home);
+    assert!(success);
+    let flat: serde_json::Value = serde_json::from_str(&stdout).expect("parse flat diff");
+    assert!(flat.is_array(), "flat diff should stay an array: {stdout}");
+}
+
 #[test]
 fn test_full_history_workflow() {
     let temp_home = TempDir::new().expect("Failed to create temp dir");
@@ -435,10 +649,14 @@ fn test_full_history_workflow() {
 fn test_repeated_records_increment_snapshots() {
     let temp_home = TempDir::new().expect("Failed to create temp dir");
 
-    // Record 3 times
+    // Record 3 times, likely within the same second
+    let mut recorded = Vec::new();
     for i in 1..=3 {
-        let (success, _, stderr) = run_and_capture(&["history", "record"], &temp_home);
+        let (success, stdout, stderr) =
+            run_and_capture(&["--json", "history", "record"], &temp_home);
         assert!(success, "record {} failed: {}", i, stderr);
+        let record: serde_json::Value = serde_json::from_str(&stdout).expect("parse record");
+        recorded.push(record["timestamp"].as_str().unwrap().to_string());
     }
 
     // Check stats
@@ -448,4 +666,172 @@ fn test_repeated_records_increment_snapshots() {
     let stats: serde_json::Value = serde_json::from_str(&stdout).expect("parse stats");
     let snapshot_count = stats["snapshot_count"].as_u64().unwrap_or(0);
     assert_eq!(snapshot_count, 3, "Expected 3 snapshots after 3 records");
+    // Oldest and newest follow recording order and keep the stored
+    // sub-second part.
+    assert_eq!(stats["oldest_snapshot"], recorded[0].as_str());
+    assert_eq!(stats["newest_snapshot"], recorded[2].as_str());
+}
+
+// ============================================================================
+// --age (first-seen tracking lives in the history database)
+// ============================================================================
+
+/// A plain listing records first-seen times, so the first `--age` run
+/// already counts from it, and later runs keep the same time.
+#[test]
+fn test_age_first_seen_is_stable_across_runs() {
+    let temp_home = TempDir::new().expect("Failed to create temp dir");
+    let db = temp_home.path().join(".local/share/ports/ports_history.db");
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    let (success, _, stderr) = run_and_capture(&["--json"], &temp_home);
+    assert!(success, "listing failed: {}", stderr);
+    assert!(db.exists(), "plain listing should create {}", db.display());
+    std::thread::sleep(std::time::Duration::from_millis(1100));
+    let age_started = chrono::Utc::now();
+
+    let first_seen = |args: &[&str]| {
+        let (success, stdout, stderr) = run_and_capture(args, &temp_home);
+        assert!(success, "--age failed: {}", stderr);
+        let output: serde_json::Value = serde_json::from_str(&stdout).expect("parse ports");
+        let ours = output["ports"]
+            .as_array()
+            .expect("ports array")
+            .iter()
+            .find(|p| p["port"] == port && p["pid"] == std::process::id())
+            .unwrap_or_else(|| panic!("our listener missing: {stdout}"))
+            .clone();
+        let since = ours["first_seen"]
+            .as_str()
+            .unwrap_or_else(|| panic!("Missing first_seen: {ours}"));
+        chrono::DateTime::parse_from_rfc3339(since).expect("RFC 3339 first_seen")
+    };
+
+    let before = first_seen(&["--json", "--age"]);
+    assert!(
+        before < age_started,
+        "first --age run didn't count from the listing"
+    );
+    let after = first_seen(&["--json", "--age"]);
+    assert_eq!(before, after, "first_seen moved for port {port}");
+    // A port query updates only its own ports, keeping the same time.
+    let queried = first_seen(&[&port.to_string(), "--json", "--age"]);
+    assert_eq!(before, queried, "port query restarted the clock");
+}
+
+#[test]
+fn test_age_on_connections_bounds_by_process_start() {
+    let temp_home = TempDir::new().expect("Failed to create temp dir");
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let _client = std::net::TcpStream::connect(addr).unwrap();
+    // The accepted end is the connection on `addr`'s port.
+    let _server = listener.accept().unwrap();
+    let port = addr.port().to_string();
+
+    let (success, stdout, stderr) =
+        run_and_capture(&[&port, "--age", "--connections", "--json"], &temp_home);
+    assert!(success, "{}", stderr);
+    let parsed: serde_json::Value = serde_json::from_str(&stdout).expect("valid JSON");
+    let ports = parsed["ports"].as_array().unwrap();
+    let ours = ports
+        .iter()
+        .find(|p| p["pid"] == std::process::id())
+        .expect("our own connection is listed");
+    assert!(ours["opened_after"].is_string(), "{ours}");
+    // Connections don't touch the listeners' first-seen map.
+    assert!(ports.iter().all(|p| p.get("first_seen").is_none()));
+}
+
+// ============================================================================
+// schema migrations
+// ============================================================================
+
+#[test]
+fn test_v1_database_upgrades_and_keeps_rows() {
+    let temp_home = TempDir::new().expect("Failed to create temp dir");
+    let db = temp_home.path().join("old.db");
+    {
+        let conn = rusqlite::Connection::open(&db).expect("create v1 db");
+        conn.execute_batch(
+            "
+            CREATE TABLE snapshots (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                timestamp TEXT NOT NULL,
+                unix_ts INTEGER NOT NULL
+            );
+            CREATE TABLE ports (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                snapshot_id INTEGER NOT NULL,
+                port INTEGER NOT NULL,
+                protocol TEXT NOT NULL,
+                address TEXT NOT NULL,
+                pid INTEGER,
+                process_name TEXT,
+                container TEXT,
+                state TEXT,
+                remote_addr TEXT,
+                FOREIGN KEY (snapshot_id) REFERENCES snapshots(id) ON DELETE CASCADE
+            );
+            INSERT INTO snapshots (timestamp, unix_ts)
+                VALUES ('2026-01-03T14:05:09+00:00', 1767449109);
+            INSERT INTO ports (snapshot_id, port, protocol, address, pid, process_name, state)
+                VALUES (1, 5432, 'tcp', '127.0.0.1:5432', 42, 'postgres', 'LISTEN');
+            PRAGMA user_version = 1;
+            ",
+        )
+        .expect("populate v1 db");
+    }
+    let db_arg = db.to_str().unwrap();
+
+    let (success, stdout, stderr) = run_and_capture(
+        &[
+            "--db-path",
+            db_arg,
+            "--json",
+            "history",
+            "show",
+            "--limit",
+            "0",
+            "--hours",
+            "876000",
+        ],
+        &temp_home,
+    );
+    assert!(success, "history show on v1 db failed: {}", stderr);
+    let entries: serde_json::Value = serde_json::from_str(&stdout).expect("parse history");
+    let old = entries
+        .as_array()
+        .expect("entries array")
+        .iter()
+        .find(|e| e["port"] == 5432 && e["process_name"] == "postgres")
+        .unwrap_or_else(|| panic!("old row missing: {stdout}"));
+    assert!(old["uid"].is_null() && old["user"].is_null(), "{old}");
+
+    let (success, _, stderr) =
+        run_and_capture(&["--db-path", db_arg, "history", "record"], &temp_home);
+    assert!(success, "record into upgraded db failed: {}", stderr);
+
+    let conn = rusqlite::Connection::open(&db).expect("reopen db");
+    let version: i32 = conn
+        .query_row("PRAGMA user_version", [], |r| r.get(0))
+        .unwrap();
+    assert_eq!(version, 4);
+    let hosts: i64 = conn
+        .query_row(
+            "SELECT COUNT(remote_host) FROM ports WHERE snapshot_id = 1",
+            [],
+            |r| r.get(0),
+        )
+        .unwrap();
+    assert_eq!(hosts, 0);
+    let old_rows: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM ports WHERE snapshot_id = 1 AND process_name = 'postgres'",
+            [],
+            |r| r.get(0),
+        )
+        .unwrap();
+    assert_eq!(old_rows, 1);
 }
@@ -68,6 +68,243 @@ fn json_output_has_wrapper_shape() {
         parsed.get("docker_reason").is_some(),
         "expected `docker_reason` key in JSON wrapper"
     );
+    assert_eq!(
+        parsed.get("version").and_then(|v| v.as_u64()),
+        Some(1),
+        "expected schema `version` 1 in JSON wrapper"
+    );
+    assert!(
+        parsed
+            .get("generated_at")
+            .and_then(|v| v.as_str())
+            .is_some_and(|s| s.ends_with('Z')),
+        "expected RFC 3339 UTC `generated_at` in JSON wrapper"
+    );
+}
+
+/// `--compact` emits the same envelope on a single line.
+#[test]
+fn json_compact_is_single_line() {
+    let output = Command::new("cargo")
+        .args(["run", "--", "--json", "--compact"])
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(stdout.trim_end().lines().count(), 1, "got: {stdout}");
+    let parsed: serde_json::Value = serde_json::from_str(&stdout).expect("valid JSON");
+    assert!(parsed.get("ports").is_some());
+}
+
+/// `--output` writes the document to the file and nothing to stdout, and
+/// refuses table output, which it doesn't cover.
+#[test]
+fn output_flag_writes_json_to_file() {
+    let temp = TempDir::new().unwrap();
+    let path = temp.path().join("ports.json");
+    let output = Command::new("cargo")
+        .args(["run", "--", "--json", "--output"])
+        .arg(&path)
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+    let text = std::fs::read_to_string(&path).expect("output file written");
+    let parsed: serde_json::Value = serde_json::from_str(&text).expect("valid JSON");
+    assert!(parsed.get("ports").is_some());
+    assert_eq!(std::fs::read_dir(temp.path()).unwrap().count(), 1);
+
+    let output = Command::new("cargo")
+        .args(["run", "--", "--output"])
+        .arg(&path)
+        .output()
+        .expect("Failed to execute command");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("--json or --porcelain"), "{stderr}");
+}
+
+/// `--allow-file` fails the run when a listener isn't covered, and passes
+/// once the file lists it.
+#[test]
+fn allow_file_fails_on_unexpected_listener() {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port().to_string();
+    let temp = TempDir::new().unwrap();
+    let path = temp.path().join("ports.allow");
+    let run = || {
+        Command::new("cargo")
+            .args([
+                "run",
+                "--",
+                &port,
+                "--json",
+                "--unexpected-only",
+                "--allow-file",
+            ])
+            .arg(&path)
+            .output()
+            .expect("Failed to execute command")
+    };
+
+    std::fs::write(&path, "# nothing allowed yet\n").unwrap();
+    let output = run();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("1 listening port(s) not in"), "{stderr}");
+    let parsed: serde_json::Value = serde_json::from_slice(&output.stdout).expect("valid JSON");
+    assert_eq!(parsed["ports"][0]["allowed"], false);
+
+    std::fs::write(&path, format!("{port}/tcp\n")).unwrap();
+    let output = run();
+    assert!(output.status.success());
+    let parsed: serde_json::Value = serde_json::from_slice(&output.stdout).expect("valid JSON");
+    assert_eq!(parsed["ports"].as_array().unwrap().len(), 0);
+}
+
+/// Watch, interactive and baseline runs never consult the allowlist, so
+/// combining them with it is refused rather than silently passing.
+#[test]
+fn allow_file_is_rejected_where_it_would_be_ignored() {
+    for mode in [
+        &["--watch", "--iterations", "1"][..],
+        &["--interactive"],
+        &["--baseline", "check"],
+    ] {
+        for allow in [
+            &["--allow-file", "/nonexistent"][..],
+            &["--allow-file", "/nonexistent", "--unexpected-only"],
+        ] {
+            let output = Command::new("cargo")
+                .args(["run", "--quiet", "--"])
+                .args(mode)
+                .args(allow)
+                .output()
+                .expect("Failed to execute command");
+            assert!(!output.status.success(), "{mode:?} {allow:?} exited 0");
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            assert!(stderr.contains("cannot be used with"), "{stderr}");
+        }
+    }
+}
+
+/// Porcelain's columns are fixed, so flags that add one are refused
+/// rather than dropped.
+#[test]
+fn porcelain_rejects_flags_that_add_columns() {
+    for flag in ["--security", "--anomaly", "--with-conn-count"] {
+        let output = Command::new("cargo")
+            .args(["run", "--quiet", "--", "--porcelain", flag])
+            .output()
+            .expect("Failed to execute command");
+        assert!(!output.status.success(), "{flag} exited 0");
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains("cannot be used with"), "{stderr}");
+    }
+}
+
+/// Even a kill that finds nothing prints its report, so scripts always
+/// get a document to parse.
+#[test]
+fn kill_json_reports_when_nothing_matches() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--quiet",
+            "--",
+            "kill",
+            "no-such-process-xyz",
+            "--json",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(!output.status.success());
+    let parsed: serde_json::Value = serde_json::from_slice(&output.stdout).expect("valid JSON");
+    assert_eq!(parsed["killed"], serde_json::json!([]));
+    assert_eq!(parsed["skipped_managed"], serde_json::json!([]));
+}
+
+#[test]
+fn open_reports_the_process_holding_a_port() {
+    let listener = std::net::TcpListener::bind("0.0.0.0:0").unwrap();
+    let port = listener.local_addr().unwrap().port().to_string();
+    let output = Command::new("cargo")
+        .args(["run", "--", "open", &port, "--protocol", "tcp", "--json"])
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(!output.status.success());
+    let parsed: serde_json::Value = serde_json::from_slice(&output.stdout).expect("valid JSON");
+    assert_eq!(parsed["status"]["tcp"], "in_use");
+    assert!(parsed["status"].get("udp").is_none());
+    assert_eq!(parsed["holders"][0]["pid"], std::process::id());
+}
+
+#[test]
+fn open_json_can_be_written_to_a_file() {
+    let listener = std::net::TcpListener::bind("0.0.0.0:0").unwrap();
+    let port = listener.local_addr().unwrap().port().to_string();
+    let temp = TempDir::new().unwrap();
+    let path = temp.path().join("open.json");
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "open",
+            &port,
+            "--protocol",
+            "tcp",
+            "--json",
+            "--output",
+        ])
+        .arg(&path)
+        .output()
+        .expect("Failed to execute command");
+
+    // In use still exits nonzero, after writing the report.
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    let written = std::fs::read(&path).expect("--output file written");
+    let parsed: serde_json::Value = serde_json::from_slice(&written).expect("valid JSON");
+    assert_eq!(parsed["status"]["tcp"], "in_use");
+}
+
+#[test]
+fn no_docker_never_queries_and_docker_only_keeps_containers() {
+    let output = Command::new("cargo")
+        .args(["run", "--quiet", "--", "--json", "--no-docker"])
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+    let parsed: serde_json::Value = serde_json::from_slice(&output.stdout).expect("valid JSON");
+    assert_eq!(parsed["docker_status"], "not_queried");
+
+    let output = Command::new("cargo")
+        .args(["run", "--quiet", "--", "--json", "--docker-only"])
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+    let parsed: serde_json::Value = serde_json::from_slice(&output.stdout).expect("valid JSON");
+    for port in parsed["ports"].as_array().unwrap() {
+        assert!(port["container"].is_string(), "{port}");
+    }
+}
+
+#[test]
+fn top_rejects_docker_only() {
+    let output = Command::new("cargo")
+        .args(["run", "--quiet", "--", "top", "--docker-only"])
+        .output()
+        .expect("Failed to execute command");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("--docker-only is not supported by `top`"),
+        "{stderr}"
+    );
 }
 
 /// When `docker_status` says the daemon was not contacted, no stderr
@@ -124,6 +361,165 @@ fn test_cli_version_runs() {
     assert!(output.status.success());
 }
 
+#[test]
+fn version_json_reports_features() {
+    let output = Command::new("cargo")
+        .args(["run", "--", "--version", "--json"])
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(output.status.success());
+    let info: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("version JSON should parse");
+    assert_eq!(info["name"], "ports");
+    assert_eq!(info["version"], env!("CARGO_PKG_VERSION"));
+    assert!(info["features"].is_array(), "{info}");
+    assert!(info["backend"].is_string(), "{info}");
+}
+
+#[test]
+fn watch_iterations_exits_after_n_refreshes() {
+    let output = Command::new("cargo")
+        .args(["run", "--", "--watch", "-n", "0.1", "--iterations", "2"])
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("refresh 1/2"), "got: {stdout}");
+    assert!(stdout.contains("refresh 2/2"), "got: {stdout}");
+}
+
+#[test]
+fn watch_diff_only_skips_unchanged_refreshes() {
+    // A query nothing matches keeps the port set empty, hence unchanged.
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "no-such-process-xyz",
+            "--watch",
+            "--diff-only",
+            "-n",
+            "0.1",
+            "--iterations",
+            "3",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("refresh 1/3"), "got: {stdout}");
+    assert!(!stdout.contains("refresh 2/3"), "got: {stdout}");
+    assert!(!stdout.contains("refresh 3/3"), "got: {stdout}");
+}
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+#[test]
+fn watch_json_diff_reports_baseline_then_changes() {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "--watch",
+            "--format",
+            "json-diff",
+            "-n",
+            "0.1",
+            "--iterations",
+            "2",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let events: Vec<serde_json::Value> = stdout
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("one JSON event per line"))
+        .collect();
+    assert_eq!(events.len(), 2, "got: {stdout}");
+    let has_port = |event: &serde_json::Value| {
+        event["appeared"]
+            .as_array()
+            .expect("`appeared` array")
+            .iter()
+            .any(|p| p["port"] == port)
+    };
+    assert!(has_port(&events[0]), "baseline lacks {port}: {stdout}");
+    assert!(!has_port(&events[1]), "{port} reported twice: {stdout}");
+    assert!(events[1]["disappeared"].is_array());
+}
+
+/// `cargo run` builds default features, which leave `notify` off.
+#[test]
+fn notify_without_feature_errors_before_watching() {
+    let output = Command::new("cargo")
+        .args(["run", "--", "--watch", "--notify"])
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("--features notify"),
+        "Expected feature hint, got: {stderr}"
+    );
+}
+
+#[test]
+fn env_connections_default_keeps_clap_conflicts() {
+    let temp = TempDir::new().expect("tempdir");
+    let output = Command::new("cargo")
+        .args(["run", "--quiet", "--", "--security"])
+        .env("HOME", temp.path())
+        .env_remove("XDG_CONFIG_HOME")
+        .env("PORTS_CONNECTIONS", "1")
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("--security cannot be used with --connections"),
+        "Expected conflict error, got: {stderr}"
+    );
+}
+
+#[test]
+fn throughput_needs_connections_from_flag_or_env() {
+    let temp = TempDir::new().expect("tempdir");
+    let run = |connections_env: Option<&str>| {
+        let mut cmd = Command::new("cargo");
+        cmd.args(["run", "--quiet", "--", "--throughput", "--json"])
+            .env("HOME", temp.path())
+            .env_remove("XDG_CONFIG_HOME")
+            .env_remove("PORTS_CONNECTIONS");
+        if let Some(value) = connections_env {
+            cmd.env("PORTS_CONNECTIONS", value);
+        }
+        cmd.output().expect("Failed to execute command")
+    };
+
+    let output = run(None);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("--throughput needs --connections"),
+        "{stderr}"
+    );
+
+    let output = run(Some("1"));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!stderr.contains("needs --connections"), "{stderr}");
+    if cfg!(target_os = "linux") {
+        assert!(output.status.success(), "{stderr}");
+    }
+}
+
 // HOME is set to a tempdir explicitly. Do not unset HOME — `dirs::home_dir`
 // falls through to `getpwuid_r` when HOME is empty/unset and would write
 // to the developer's real home directory.
@@ -211,3 +607,90 @@ fn print_flag_emits_stdout_without_writing_file() {
         "--print should not create the install file"
     );
 }
+
+#[test]
+fn output_flag_writes_completions_to_given_path() {
+    let temp = TempDir::new().expect("tempdir");
+    let target = temp.path().join("out/ports.ps1");
+    let output = ports_completions_cmd(&temp, &["completions", "powershell", "--output"])
+        .arg(&target)
+        .output()
+        .expect("run completions powershell --output");
+
+    assert!(output.status.success(), "exit not 0: {output:?}");
+    let body = std::fs::read_to_string(&target).expect("completion file written");
+    assert!(body.contains("ports"), "unexpected completion content");
+}
+
+#[test]
+fn json_flag_is_rejected_for_completions() {
+    let temp = TempDir::new().expect("tempdir");
+    let output = ports_completions_cmd(&temp, &["--json", "completions", "bash"])
+        .output()
+        .expect("run --json completions bash");
+
+    assert!(!output.status.success(), "expected non-zero exit");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("--json is not applicable to completions"),
+        "{stderr}"
+    );
+}
+
+/// `--baseline check` passes against a fresh `--baseline save` and fails,
+/// naming the port, once something new starts listening.
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+#[test]
+fn baseline_check_flags_new_listener() {
+    let temp = TempDir::new().expect("tempdir");
+    let file = temp.path().join("baseline.json");
+    let run = |action: &str| {
+        Command::new("cargo")
+            .args([
+                "run",
+                "--",
+                "--json",
+                "--baseline",
+                action,
+                "--baseline-file",
+            ])
+            .arg(&file)
+            .output()
+            .expect("Failed to execute command")
+    };
+
+    assert!(run("save").status.success());
+    let clean = run("check");
+    assert!(
+        clean.status.success(),
+        "{}",
+        String::from_utf8_lossy(&clean.stderr)
+    );
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let dirty = run("check");
+    assert!(!dirty.status.success());
+    let parsed: serde_json::Value =
+        serde_json::from_slice(&dirty.stdout).expect("invalid JSON output");
+    let appeared = parsed["appeared"].as_array().expect("`appeared` array");
+    assert!(
+        appeared.iter().any(|p| p["port"] == port),
+        "port {port} missing from {parsed}"
+    );
+}
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+#[test]
+fn limit_truncates_listing_after_sorting() {
+    let _a = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let _b = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let output = Command::new("cargo")
+        .args(["run", "--", "--json", "--limit", "1", "--sort", "port"])
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+    let parsed: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("invalid JSON output");
+    assert_eq!(parsed["ports"].as_array().expect("`ports` array").len(), 1);
+}
@@ -0,0 +1,68 @@
+//! The stable library surface: `portls::snapshot()` and friends, used the
+//! way a downstream crate would.
+
+use std::net::TcpListener;
+
+use portls::{PortInfo, PortsError, Protocol, Risk};
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+#[test]
+fn snapshot_sees_our_listener_in_sorted_order() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    let ports: Vec<PortInfo> = portls::snapshot().unwrap();
+
+    assert!(ports
+        .iter()
+        .any(|p| p.port == port && p.protocol == Protocol::Tcp && p.pid == std::process::id()));
+    assert!(ports
+        .windows(2)
+        .all(|w| (w[0].port, w[0].protocol, w[0].pid) <= (w[1].port, w[1].protocol, w[1].pid)));
+}
+
+#[test]
+fn ports_error_is_a_std_error_callers_can_match() {
+    fn assert_error<E: std::error::Error + Send + Sync + 'static>() {}
+    assert_error::<PortsError>();
+
+    let retry_with_sudo = |err: &PortsError| matches!(err, PortsError::PermissionDenied { .. });
+
+    let denied = PortsError::PermissionDenied {
+        path: "/proc/net/tcp".into(),
+    };
+    assert!(retry_with_sudo(&denied));
+    assert_eq!(
+        denied.to_string(),
+        "permission denied reading /proc/net/tcp (try again with sudo)"
+    );
+
+    let unsupported = PortsError::UnsupportedPlatform("no socket API here".to_string());
+    assert!(!retry_with_sudo(&unsupported));
+    assert_eq!(unsupported.to_string(), "no socket API here");
+
+    // The enum is non-exhaustive, so downstream matches need a catch-all.
+    let kind = |err: &PortsError| match err {
+        PortsError::PermissionDenied { .. } => "denied",
+        PortsError::UnsupportedPlatform(_) => "unsupported",
+        _ => "other",
+    };
+    assert_eq!(kind(&denied), "denied");
+    assert_eq!(kind(&unsupported), "unsupported");
+}
+
+#[test]
+fn risk_can_be_named_and_matched() {
+    let mut redis = PortInfo::default();
+    redis.port = 6379;
+    redis.address = "0.0.0.0:6379".to_string();
+
+    let risk: Option<Risk> = redis.risk();
+    assert_eq!(risk, Some(Risk::ExposedDataStore));
+    let admin = match risk {
+        Some(Risk::ExposedAdminService) => "admin",
+        Some(_) => "other",
+        None => "none",
+    };
+    assert_eq!(admin, "other");
+}
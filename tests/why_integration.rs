@@ -31,7 +31,7 @@ fn test_why_nonexistent_target_graceful() {
 }
 
 #[test]
-fn test_why_json_nonexistent_returns_empty_array() {
+fn test_why_json_nonexistent_returns_empty_ports() {
     let output = Command::new("cargo")
         .args(["run", "--", "why", "--json", "nonexistent_xyz_98765"])
         .output()
@@ -39,7 +39,44 @@ fn test_why_json_nonexistent_returns_empty_array() {
 
     assert!(output.status.success());
     let stdout = String::from_utf8_lossy(&output.stdout);
-    assert_eq!(stdout.trim(), "[]");
+    let parsed: serde_json::Value = serde_json::from_str(&stdout).expect("invalid JSON output");
+    assert_eq!(parsed["ports"], serde_json::json!([]));
+}
+
+/// Both `--why --json` and `why <target> --json` emit the same envelope
+/// and the same per-port keys, with ancestry nested under `ancestry`.
+#[test]
+fn test_why_json_shape_matches_between_flag_and_subcommand() {
+    let run = |args: &[&str]| -> serde_json::Value {
+        let output = Command::new("cargo")
+            .args(["run", "--"])
+            .args(args)
+            .output()
+            .expect("Failed to execute command");
+        assert!(output.status.success());
+        serde_json::from_str(&String::from_utf8_lossy(&output.stdout)).expect("invalid JSON output")
+    };
+
+    let from_flag = run(&["--why", "--json"]);
+    let Some(first) = from_flag["ports"].as_array().and_then(|a| a.first()) else {
+        // No listening ports -- nothing to compare.
+        return;
+    };
+    let port = first["port"].to_string();
+    let from_subcommand = run(&["why", "--json", &port]);
+
+    for envelope in [&from_flag, &from_subcommand] {
+        for key in ["version", "generated_at", "ports", "docker_status"] {
+            assert!(envelope.get(key).is_some(), "missing `{key}`: {envelope}");
+        }
+        let entry = &envelope["ports"][0];
+        for key in ["port", "protocol", "pid", "process_name", "address"] {
+            assert!(entry.get(key).is_some(), "missing `{key}`: {entry}");
+        }
+        if let Some(ancestry) = entry.get("ancestry") {
+            assert!(ancestry.get("chain").is_some() && ancestry.get("source").is_some());
+        }
+    }
 }
 
 #[test]
@@ -85,3 +122,88 @@ fn test_why_watch_rejected_for_why_subcommand() {
         stderr
     );
 }
+
+/// `--newer-than` keeps a process started within the window and drops it
+/// once the window is shorter than its age.
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+#[test]
+fn test_why_newer_than_filters_by_start_time() {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port().to_string();
+    let run = |window: &str| -> serde_json::Value {
+        let output = Command::new("cargo")
+            .args(["run", "--", "why", "--json", "--newer-than", window, &port])
+            .output()
+            .expect("Failed to execute command");
+        assert!(output.status.success());
+        serde_json::from_str(&String::from_utf8_lossy(&output.stdout)).expect("invalid JSON output")
+    };
+
+    let recent = run("1d");
+    let pids: Vec<_> = recent["ports"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|p| p["pid"].as_u64())
+        .collect();
+    assert!(pids.contains(&Some(std::process::id().into())), "{recent}");
+
+    assert_eq!(run("0s")["ports"], serde_json::json!([]));
+}
+
+/// The global protocol filter (here its `--udp` shorthand) applies to
+/// `why` as it does to listings.
+#[test]
+fn test_why_honors_protocol_filter() {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port().to_string();
+    let run = |protocol: &str| -> serde_json::Value {
+        let output = Command::new("cargo")
+            .args(["run", "--", "why", "--json", protocol, &port])
+            .output()
+            .expect("Failed to execute command");
+        assert!(output.status.success());
+        serde_json::from_str(&String::from_utf8_lossy(&output.stdout)).expect("invalid JSON output")
+    };
+
+    assert_eq!(run("--tcp")["ports"][0]["pid"], std::process::id());
+    assert_eq!(run("--udp")["ports"], serde_json::json!([]));
+}
+
+#[test]
+fn test_tcp_and_udp_shorthands_conflict() {
+    for args in [["--tcp", "--udp"], ["--tcp", "--protocol=tcp"]] {
+        let output = Command::new("cargo")
+            .args(["run", "--", "why", "1"])
+            .args(args)
+            .output()
+            .expect("Failed to execute command");
+        assert!(!output.status.success());
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains("cannot be used with"), "{stderr}");
+    }
+}
+
+/// `why --json` carries the full per-process picture: command line, owner
+/// and executable on each port, start time and uptime in its ancestry.
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+#[test]
+fn test_why_json_includes_cmdline_and_uptime() {
+    let _listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let pid = std::process::id().to_string();
+    let output = Command::new("cargo")
+        .args(["run", "--", "why", "--json", &pid])
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+    let parsed: serde_json::Value = serde_json::from_str(&String::from_utf8_lossy(&output.stdout))
+        .expect("invalid JSON output");
+
+    let entry = &parsed["ports"][0];
+    assert_eq!(entry["pid"], std::process::id(), "{parsed}");
+    assert!(entry["command_line"].is_string(), "{entry}");
+    assert!(entry["user"].is_string(), "{entry}");
+    assert!(entry["exe_path"].is_string(), "{entry}");
+    assert!(entry["ancestry"]["started_at"].is_string(), "{entry}");
+    assert!(entry["ancestry"]["uptime_secs"].is_u64(), "{entry}");
+}